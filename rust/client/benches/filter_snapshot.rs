@@ -0,0 +1,79 @@
+//! Compares validating orders with a [`FilterSnapshot`] to looking the market up for every
+//! order, as [`BpxClient::get_market_cached`] does.
+//!
+//! Run with `cargo bench -p bpx-api-client --bench filter_snapshot`.
+//!
+//! [`FilterSnapshot`]: bpx_api_client::types::markets::FilterSnapshot
+//! [`BpxClient::get_market_cached`]: bpx_api_client::BpxClient::get_market_cached
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use bpx_api_client::types::markets::Market;
+use bpx_api_client::types::order::{ExecuteOrderPayload, OrderType, Side};
+use rust_decimal::Decimal;
+
+const ORDERS: usize = 100_000;
+
+const MARKET_JSON: &str = r#"{
+    "symbol": "SOL_USDC",
+    "baseSymbol": "SOL",
+    "quoteSymbol": "USDC",
+    "marketType": "SPOT",
+    "filters": {
+        "price": { "minPrice": "0.01", "maxPrice": "10000", "tickSize": "0.01" },
+        "quantity": { "minQuantity": "0.01", "maxQuantity": "100000", "stepSize": "0.01" }
+    },
+    "orderBookState": "Open",
+    "createdAt": "2025-01-01T00:00:00"
+}"#;
+
+fn main() {
+    let market: Market = serde_json::from_str(MARKET_JSON).expect("valid market");
+    let orders: Vec<ExecuteOrderPayload> = (0..ORDERS)
+        .map(|i| ExecuteOrderPayload {
+            symbol: "SOL_USDC".to_string(),
+            side: if i % 2 == 0 { Side::Bid } else { Side::Ask },
+            order_type: OrderType::Limit,
+            price: Some(Decimal::new(15_000 + (i % 100) as i64, 2)),
+            quantity: Some(Decimal::new(1 + (i % 1000) as i64, 2)),
+            ..Default::default()
+        })
+        .collect();
+
+    // The client's market cache: a lock around a map, cloning the market out on every hit.
+    let cache = RwLock::new(HashMap::from([(market.symbol.clone(), market.clone())]));
+    let cached = measure(&orders, |order| {
+        let market = cache
+            .read()
+            .unwrap()
+            .get(&order.symbol)
+            .cloned()
+            .expect("cached market");
+        market.filter_snapshot().validate(order).is_ok()
+    });
+
+    let filters = market.filter_snapshot();
+    let snapshot = measure(&orders, |order| filters.validate(order).is_ok());
+
+    println!("cache lookup per order: {:>8.1} ns/order", per_order(cached));
+    println!("filter snapshot:        {:>8.1} ns/order", per_order(snapshot));
+}
+
+/// Validates every order, after a warm-up pass, and returns the time taken.
+fn measure(orders: &[ExecuteOrderPayload], validate: impl Fn(&ExecuteOrderPayload) -> bool) -> Duration {
+    let valid = orders.iter().filter(|order| validate(black_box(order))).count();
+    assert_eq!(valid, orders.len());
+
+    let start = Instant::now();
+    for order in orders {
+        black_box(validate(black_box(order)));
+    }
+    start.elapsed()
+}
+
+fn per_order(elapsed: Duration) -> f64 {
+    elapsed.as_nanos() as f64 / ORDERS as f64
+}
@@ -0,0 +1,219 @@
+//! Per-endpoint circuit breakers, see [`crate::BpxClientBuilder::circuit_breaker`].
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+
+/// Configures the circuit breakers that stop sending requests to a failing endpoint.
+///
+/// Each endpoint (URL path) has its own breaker. It opens after `failure_threshold`
+/// consecutive calls fail with a transient error (see [`Error::is_retryable`]), after which
+/// calls to that endpoint fail immediately with [`Error::CircuitOpen`] for `cooldown`. Once
+/// the cooldown elapses the breaker lets a single probe call through: if it succeeds the
+/// breaker closes again, otherwise it reopens for another cooldown. A call counts once
+/// however many times it is retried under the client's [`crate::RetryPolicy`], and errors
+/// caused by the request itself, such as a rejected order, don't count as failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Number of consecutive failed calls after which the breaker opens.
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before letting a probe call through.
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { probing: bool },
+}
+
+/// The breakers of every endpoint, shared by clones of a client.
+#[derive(Debug)]
+pub(crate) struct CircuitBreakers {
+    config: CircuitBreakerConfig,
+    states: Mutex<HashMap<String, State>>,
+}
+
+impl CircuitBreakers {
+    pub(crate) fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            states: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns a permit to call `endpoint`, or [`Error::CircuitOpen`] if its breaker is open
+    /// or already probing. The outcome of the call must be reported with [`Permit::record`].
+    pub(crate) fn permit(&self, endpoint: &str) -> Result<Permit<'_>> {
+        self.permit_at(endpoint, Instant::now())
+    }
+
+    fn permit_at(&self, endpoint: &str, now: Instant) -> Result<Permit<'_>> {
+        let mut states = self.states.lock().expect("circuit breaker states poisoned");
+        let state = states
+            .entry(endpoint.to_string())
+            .or_insert(State::Closed { failures: 0 });
+        let probe = match *state {
+            State::Closed { .. } => false,
+            State::Open { until } if now < until => {
+                return Err(Error::CircuitOpen {
+                    endpoint: endpoint.into(),
+                    retry_after: until - now,
+                });
+            }
+            State::Open { .. } | State::HalfOpen { probing: false } => {
+                *state = State::HalfOpen { probing: true };
+                true
+            }
+            State::HalfOpen { probing: true } => {
+                return Err(Error::CircuitOpen {
+                    endpoint: endpoint.into(),
+                    retry_after: Duration::ZERO,
+                });
+            }
+        };
+        Ok(Permit {
+            breakers: self,
+            endpoint: endpoint.to_string(),
+            probe,
+            recorded: false,
+        })
+    }
+
+    fn record(&self, endpoint: &str, failed: bool, now: Instant) {
+        let mut states = self.states.lock().expect("circuit breaker states poisoned");
+        let Some(state) = states.get_mut(endpoint) else {
+            return;
+        };
+        *state = match (*state, failed) {
+            (_, false) => State::Closed { failures: 0 },
+            (State::Closed { failures }, true) if failures + 1 < self.config.failure_threshold => {
+                State::Closed { failures: failures + 1 }
+            }
+            (_, true) => State::Open {
+                until: now + self.config.cooldown,
+            },
+        };
+    }
+}
+
+/// Permission to make one call to an endpoint.
+pub(crate) struct Permit<'a> {
+    breakers: &'a CircuitBreakers,
+    endpoint: String,
+    probe: bool,
+    recorded: bool,
+}
+
+impl Permit<'_> {
+    /// Reports the outcome of the call.
+    pub(crate) fn record<T>(self, result: &Result<T>) {
+        self.record_at(result, Instant::now());
+    }
+
+    fn record_at<T>(mut self, result: &Result<T>, now: Instant) {
+        let failed = result.as_ref().is_err_and(Error::is_retryable);
+        self.breakers.record(&self.endpoint, failed, now);
+        self.recorded = true;
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        // A probe that was cancelled has no outcome; let the next call probe instead.
+        if self.probe && !self.recorded {
+            let mut states = self.breakers.states.lock().expect("circuit breaker states poisoned");
+            if let Some(state @ State::HalfOpen { probing: true }) = states.get_mut(&self.endpoint) {
+                *state = State::HalfOpen { probing: false };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn server_error() -> Result<()> {
+        Err(Error::BpxApiError {
+            status_code: reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            message: "".into(),
+        })
+    }
+
+    fn state(breakers: &CircuitBreakers, endpoint: &str) -> State {
+        breakers.states.lock().expect("circuit breaker states poisoned")[endpoint]
+    }
+
+    #[test]
+    fn test_breaker_state_transitions() {
+        let breakers = CircuitBreakers::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(10),
+        });
+        let start = Instant::now();
+
+        breakers
+            .permit_at("/a", start)
+            .unwrap()
+            .record_at(&server_error(), start);
+        // Client errors and successes don't count towards the threshold.
+        breakers.permit_at("/a", start).unwrap().record_at::<()>(
+            &Err(Error::BpxApiError {
+                status_code: reqwest::StatusCode::BAD_REQUEST,
+                message: "".into(),
+            }),
+            start,
+        );
+        assert_eq!(state(&breakers, "/a"), State::Closed { failures: 0 });
+        breakers
+            .permit_at("/a", start)
+            .unwrap()
+            .record_at(&server_error(), start);
+        breakers
+            .permit_at("/a", start)
+            .unwrap()
+            .record_at(&server_error(), start);
+        assert!(matches!(state(&breakers, "/a"), State::Open { .. }));
+
+        // Other endpoints are unaffected.
+        assert!(breakers.permit_at("/b", start).is_ok());
+
+        let Err(Error::CircuitOpen { retry_after, .. }) = breakers.permit_at("/a", start + Duration::from_secs(4))
+        else {
+            panic!("expected the breaker to be open");
+        };
+        assert_eq!(retry_after, Duration::from_secs(6));
+
+        // A cancelled probe lets the next call probe.
+        let after_cooldown = start + Duration::from_secs(10);
+        drop(breakers.permit_at("/a", after_cooldown).unwrap());
+        let probe = breakers.permit_at("/a", after_cooldown).unwrap();
+        assert!(matches!(
+            breakers.permit_at("/a", after_cooldown),
+            Err(Error::CircuitOpen { .. })
+        ));
+        // A failed probe reopens the breaker.
+        probe.record_at(&server_error(), after_cooldown);
+        assert!(breakers.permit_at("/a", after_cooldown).is_err());
+        // A successful probe closes it.
+        let after_cooldown = after_cooldown + Duration::from_secs(10);
+        breakers
+            .permit_at("/a", after_cooldown)
+            .unwrap()
+            .record_at(&Ok(()), after_cooldown);
+        assert_eq!(state(&breakers, "/a"), State::Closed { failures: 0 });
+    }
+}
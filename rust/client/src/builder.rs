@@ -0,0 +1,451 @@
+//! Builder for configuring a [`BpxClient`].
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bpx_api_types::markets::PriceRounding;
+use bpx_api_types::order::{SelfTradePrevention, TimeInForce};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+
+use crate::breaker::{CircuitBreakerConfig, CircuitBreakers};
+use crate::error::{Error, Result};
+use crate::pool::{RateLimit, TokenBucket};
+use crate::{BpxClient, BpxHeaders, API_USER_AGENT, BACKPACK_API_BASE_URL, DEFAULT_WINDOW, JSON_CONTENT};
+
+/// Controls how `execute_order` snaps prices and quantities to the market's filters before
+/// sending them.
+///
+/// Quantities are always rounded down to the step size so an order never exceeds the
+/// requested size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RoundingConfig {
+    /// How prices are snapped to the tick size.
+    pub price: PriceRounding,
+}
+
+/// Controls how failed requests are retried.
+///
+/// `429 Too Many Requests` responses are retried for every method, waiting for the duration
+/// given in the `Retry-After` header when present. Other transient failures (server errors,
+/// timeouts and connection errors) are only retried for `GET` requests, as retrying an order
+/// submission could otherwise place it twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on each subsequent retry.
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub const NONE: Self = Self {
+        max_retries: 0,
+        base_delay: Duration::ZERO,
+    };
+
+    /// Returns the exponential backoff delay for the given (zero based) retry attempt.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay.saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// The longest receive window the exchange accepts.
+const MAX_WINDOW: Duration = Duration::from_secs(60);
+
+/// A hook customizing the `reqwest` client, see [`BpxClientBuilder::configure_http`].
+type HttpConfig = Box<dyn FnOnce(reqwest::ClientBuilder) -> reqwest::ClientBuilder + Send>;
+
+/// A builder for [`BpxClient`].
+///
+/// ```no_run
+/// # fn main() -> bpx_api_client::Result<()> {
+/// use bpx_api_client::{BpxClient, RoundingConfig};
+/// use bpx_api_client::types::markets::PriceRounding;
+///
+/// let client = BpxClient::builder()
+///     .secret("your_api_secret_here")
+///     .rounding(RoundingConfig { price: PriceRounding::AwayFromSpread })
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct BpxClientBuilder {
+    base_url: Option<String>,
+    ws_url: Option<String>,
+    secret: Option<String>,
+    headers: Option<BpxHeaders>,
+    user_agent: Option<String>,
+    recv_window: Option<Duration>,
+    rounding: Option<RoundingConfig>,
+    retry: RetryPolicy,
+    circuit_breaker: Option<CircuitBreakerConfig>,
+    order_rate_limit: Option<RateLimit>,
+    self_trade_prevention: Option<SelfTradePrevention>,
+    time_in_force: Option<TimeInForce>,
+    post_only_guard: bool,
+    http_config: Option<HttpConfig>,
+    #[cfg(feature = "ws")]
+    reconnect: crate::ReconnectPolicy,
+}
+
+impl BpxClientBuilder {
+    /// Creates a new builder with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a builder configured from environment variables, for binaries that take their
+    /// configuration from the environment.
+    ///
+    /// - `BPX_API_KEY` and `BPX_API_SECRET`: the base64 encoded API key and secret, required.
+    ///   The key must be the one of the secret.
+    /// - `BPX_BASE_URL`: the base URL of the REST API, see [`Self::base_url`].
+    /// - `BPX_WS_URL`: the WebSocket URL, see [`Self::ws_url`].
+    /// - `BPX_RECV_WINDOW`: the receive window in milliseconds, see [`Self::recv_window`].
+    ///
+    /// Empty variables count as unset. Fails with [`Error::MissingEnvVars`] listing every
+    /// required variable that is unset. The secret is never included in errors or logs. The
+    /// returned builder can be configured further before building.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let var = |name| var(name).filter(|value| !value.is_empty());
+        let api_key = var("BPX_API_KEY");
+        let secret = var("BPX_API_SECRET");
+        let (Some(api_key), Some(secret)) = (&api_key, &secret) else {
+            let missing = [("BPX_API_KEY", api_key.is_none()), ("BPX_API_SECRET", secret.is_none())];
+            return Err(Error::MissingEnvVars(
+                missing
+                    .iter()
+                    .filter(|(_, unset)| *unset)
+                    .map(|(name, _)| *name)
+                    .collect(),
+            ));
+        };
+        if STANDARD.encode(signing_key(secret)?.verifying_key()) != *api_key {
+            return Err(Error::InvalidRequest(
+                "BPX_API_KEY is not the key of BPX_API_SECRET".into(),
+            ));
+        }
+
+        let mut builder = Self::new().secret(secret.as_str());
+        builder.base_url = var("BPX_BASE_URL");
+        builder.ws_url = var("BPX_WS_URL");
+        if let Some(window) = var("BPX_RECV_WINDOW") {
+            let window = window.parse().map_err(|_| {
+                Error::InvalidRequest(format!("BPX_RECV_WINDOW is not a number of milliseconds: {window}").into())
+            })?;
+            builder = builder.recv_window(Duration::from_millis(window));
+        }
+        Ok(builder)
+    }
+
+    /// Sets the base URL of the REST API. Defaults to [`BACKPACK_API_BASE_URL`].
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Sets the WebSocket URL. Defaults to [`crate::BACKPACK_WS_URL`].
+    #[cfg(feature = "ws")]
+    pub fn ws_url(mut self, ws_url: impl Into<String>) -> Self {
+        self.ws_url = Some(ws_url.into());
+        self
+    }
+
+    /// Sets the base64 encoded API secret used to sign requests.
+    pub fn secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    /// Sets additional headers sent with every request.
+    pub fn headers(mut self, headers: BpxHeaders) -> Self {
+        self.headers = Some(headers);
+        self
+    }
+
+    /// Sets the `User-Agent` sent with REST requests and WebSocket handshakes. Defaults to
+    /// `bpx-api-client/<version>`.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets the window after the request timestamp during which signed requests are valid,
+    /// at most 60 seconds. Defaults to 5 seconds.
+    pub fn recv_window(mut self, recv_window: Duration) -> Self {
+        self.recv_window = Some(recv_window);
+        self
+    }
+
+    /// Enables auto-rounding of order prices and quantities in `execute_order`.
+    ///
+    /// Disabled by default, in which case orders are sent exactly as given.
+    pub fn rounding(mut self, rounding: RoundingConfig) -> Self {
+        self.rounding = Some(rounding);
+        self
+    }
+
+    /// Sets the retry policy for failed requests. Requests are not retried by default.
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Enables per-endpoint circuit breakers, so calls to an endpoint that keeps failing fail
+    /// fast with [`Error::CircuitOpen`] instead of adding load during an incident. See
+    /// [`CircuitBreakerConfig`] for how breakers open and recover. The breakers are shared by
+    /// clones of the client. Disabled by default.
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = Some(config);
+        self
+    }
+
+    /// Throttles order placement to `limit`, for venues that cap new orders more tightly
+    /// than requests in general.
+    ///
+    /// `execute_order` and `execute_orders` wait for the limit before sending, and each order
+    /// of a batch counts against it. Cancels, the dry run and every other request are not
+    /// throttled. The limit is tracked as a token bucket, see [`RateLimit`], shared by clones
+    /// of the client. Unset by default, in which case orders are sent immediately.
+    pub fn order_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.order_rate_limit = Some(limit);
+        self
+    }
+
+    /// Sets the self-trade prevention mode used for orders that don't set one.
+    ///
+    /// Unset by default, in which case the exchange default of
+    /// [`SelfTradePrevention::RejectTaker`] applies.
+    pub fn self_trade_prevention(mut self, self_trade_prevention: SelfTradePrevention) -> Self {
+        self.self_trade_prevention = Some(self_trade_prevention);
+        self
+    }
+
+    /// Sets the time in force used for limit orders that don't set one.
+    ///
+    /// Unset by default, in which case none is sent and the exchange default of
+    /// [`TimeInForce::GTC`] applies. Market orders never get the default, so only a time in
+    /// force set on the order itself is sent with them.
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+
+    /// Checks post-only limit orders against the order book before sending them.
+    ///
+    /// When enabled, `execute_order` fetches the market's best bid and ask before sending a
+    /// post-only order, and fails with [`Error::PostOnlyWouldCross`] if it would cross them
+    /// instead of letting the exchange expire it as `PostOnlyTaker`. This costs a depth
+    /// request per post-only order, and the book may still move before the order arrives.
+    /// Disabled by default. Use [`ExecuteOrderPayload::check_post_only`] to check against
+    /// prices already at hand.
+    ///
+    /// [`ExecuteOrderPayload::check_post_only`]: bpx_api_types::order::ExecuteOrderPayload::check_post_only
+    pub fn post_only_guard(mut self, enabled: bool) -> Self {
+        self.post_only_guard = enabled;
+        self
+    }
+
+    /// Customizes the `reqwest` client used for REST requests, for example to connect over a
+    /// transport other than TCP.
+    ///
+    /// Deployments colocated with the exchange can shave latency off every request by
+    /// bypassing the standard HTTPS stack, such as talking to a local gateway over a Unix
+    /// domain socket with [`reqwest::ClientBuilder::unix_socket`], or by wrapping the
+    /// connector with [`reqwest::ClientBuilder::connector_layer`]. The hook runs after the
+    /// client's own configuration, so it can override it, and doesn't apply to WebSocket
+    /// connections. Unset by default, in which case requests use TCP and TLS as usual.
+    ///
+    /// ```no_run
+    /// # fn main() -> bpx_api_client::Result<()> {
+    /// use bpx_api_client::BpxClient;
+    ///
+    /// let client = BpxClient::builder()
+    ///     .base_url("http://localhost")
+    ///     .secret("your_api_secret_here")
+    ///     .configure_http(|http| http.unix_socket("/run/bpx-gateway.sock"))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn configure_http(
+        mut self,
+        configure: impl FnOnce(reqwest::ClientBuilder) -> reqwest::ClientBuilder + Send + 'static,
+    ) -> Self {
+        self.http_config = Some(Box::new(configure));
+        self
+    }
+
+    /// Sets how the WebSocket connection is re-established after it drops. By default it is
+    /// retried forever with a jittered backoff of up to 30 seconds.
+    #[cfg(feature = "ws")]
+    pub fn reconnect_policy(mut self, reconnect: crate::ReconnectPolicy) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Builds the client.
+    ///
+    /// This sets up the signing and verification keys, and creates a `reqwest` client
+    /// with default headers including the content type.
+    pub fn build(self) -> Result<BpxClient> {
+        let secret = self.secret.ok_or(Error::SecretKey)?;
+        let recv_window = match self.recv_window {
+            Some(window) if window < Duration::from_millis(1) || window > MAX_WINDOW => {
+                return Err(Error::InvalidRequest(
+                    format!("receive window must be between 1 ms and 60 s, got {window:?}").into(),
+                ));
+            }
+            Some(window) => window.as_millis() as u32,
+            None => DEFAULT_WINDOW,
+        };
+        let signer = signing_key(&secret)?;
+        let verifier = signer.verifying_key();
+
+        let order_bucket = match self.order_rate_limit {
+            Some(limit) => {
+                limit.check()?;
+                Some(Arc::new(Mutex::new(TokenBucket::new(limit))))
+            }
+            None => None,
+        };
+
+        let mut headers = self.headers.unwrap_or_default();
+        headers.insert(CONTENT_TYPE, JSON_CONTENT.parse()?);
+
+        let user_agent = self.user_agent.unwrap_or_else(|| API_USER_AGENT.to_string());
+        let client = reqwest::Client::builder()
+            .user_agent(user_agent.parse::<HeaderValue>()?)
+            .default_headers(headers);
+        // Match the backend `tokio-tungstenite` picks for WebSocket connections.
+        #[cfg(feature = "native-tls")]
+        let client = client.use_native_tls();
+        let client = match self.http_config {
+            Some(configure) => configure(client),
+            None => client,
+        };
+        let client = client.build()?;
+
+        Ok(BpxClient {
+            api_key: api_key_header(&verifier)?,
+            signer,
+            verifier,
+            base_url: self.base_url.unwrap_or_else(|| BACKPACK_API_BASE_URL.to_string()),
+            ws_url: self.ws_url,
+            client,
+            user_agent,
+            recv_window,
+            rounding: self.rounding,
+            retry: self.retry,
+            breakers: self
+                .circuit_breaker
+                .map(|config| Arc::new(CircuitBreakers::new(config))),
+            order_bucket,
+            self_trade_prevention: self.self_trade_prevention,
+            time_in_force: self.time_in_force,
+            post_only_guard: self.post_only_guard,
+            markets: Default::default(),
+            capabilities: Default::default(),
+            #[cfg(feature = "ws")]
+            ws: std::sync::Arc::new(crate::ws::WsState::new(self.reconnect)),
+        })
+    }
+}
+
+/// Decodes a base64 encoded API secret into the key used to sign requests.
+pub(crate) fn signing_key(secret: &str) -> Result<SigningKey> {
+    STANDARD
+        .decode(secret)?
+        .try_into()
+        .map(|s| SigningKey::from_bytes(&s))
+        .map_err(|_| Error::SecretKey)
+}
+
+/// Returns the `X-API-Key` header value identifying the given key.
+pub(crate) fn api_key_header(verifier: &VerifyingKey) -> Result<HeaderValue> {
+    Ok(STANDARD.encode(verifier).parse()?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::test_utils::TEST_SECRET;
+
+    fn test_api_key() -> String {
+        STANDARD.encode(signing_key(TEST_SECRET).unwrap().verifying_key())
+    }
+
+    fn from_vars(vars: &[(&str, &str)]) -> Result<BpxClientBuilder> {
+        let vars: HashMap<_, _> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        BpxClientBuilder::from_vars(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_from_env() {
+        let api_key = test_api_key();
+        let client = from_vars(&[
+            ("BPX_API_KEY", &api_key),
+            ("BPX_API_SECRET", TEST_SECRET),
+            ("BPX_BASE_URL", "https://api.test"),
+            ("BPX_WS_URL", "wss://ws.test"),
+            ("BPX_RECV_WINDOW", "10000"),
+        ])
+        .unwrap()
+        .build()
+        .unwrap();
+        assert_eq!(client.base_url(), "https://api.test");
+        assert_eq!(client.recv_window(), Duration::from_secs(10));
+        assert_eq!(STANDARD.encode(client.verifier()), api_key);
+        #[cfg(feature = "ws")]
+        assert_eq!(client.ws_url(), "wss://ws.test");
+    }
+
+    #[test]
+    fn test_from_env_errors() {
+        let err = from_vars(&[]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Missing environment variables: BPX_API_KEY, BPX_API_SECRET"
+        );
+        let err = from_vars(&[("BPX_API_KEY", &test_api_key()), ("BPX_API_SECRET", "")])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "Missing environment variables: BPX_API_SECRET");
+
+        let other_key = STANDARD.encode([1; 32]);
+        let err = from_vars(&[("BPX_API_KEY", &other_key), ("BPX_API_SECRET", TEST_SECRET)])
+            .err()
+            .unwrap();
+        assert!(!err.to_string().contains(TEST_SECRET));
+        assert!(matches!(err, Error::InvalidRequest(_)));
+
+        let api_key = test_api_key();
+        let vars = [("BPX_API_KEY", api_key.as_str()), ("BPX_API_SECRET", TEST_SECRET)];
+        let window = |window| [vars[0], vars[1], ("BPX_RECV_WINDOW", window)];
+        assert!(matches!(from_vars(&window("5s")), Err(Error::InvalidRequest(_))));
+        assert!(matches!(
+            from_vars(&window("60001")).unwrap().build(),
+            Err(Error::InvalidRequest(_))
+        ));
+        assert_eq!(
+            from_vars(&vars).unwrap().build().unwrap().base_url(),
+            BACKPACK_API_BASE_URL
+        );
+    }
+}
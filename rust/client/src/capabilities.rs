@@ -0,0 +1,183 @@
+//! Detection of the optional endpoints served by the exchange.
+//!
+//! Older or self-hosted deployments may not serve every endpoint this crate calls. The
+//! routes of optional endpoints report a missing endpoint as [`Error::Unsupported`], and
+//! [`BpxClient::capabilities`] probes them all upfront.
+
+use std::fmt;
+
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::routes::{capital::API_COLLATERAL, rfq::API_RFQ, strategies::API_STRATEGY_HISTORY};
+use crate::BpxClient;
+
+/// A group of endpoints that a deployment may not serve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Requests for quote and quotes.
+    Rfq,
+    /// The strategy history.
+    Strategies,
+    /// The collateral summary.
+    Collateral,
+}
+
+impl Capability {
+    /// The endpoint requested to check whether the capability is available.
+    const fn probe_path(&self) -> &'static str {
+        match self {
+            Self::Rfq => API_RFQ,
+            Self::Strategies => API_STRATEGY_HISTORY,
+            Self::Collateral => API_COLLATERAL,
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Rfq => "RFQ",
+            Self::Strategies => "strategies",
+            Self::Collateral => "collateral",
+        })
+    }
+}
+
+/// The optional endpoints served by the exchange, see [`BpxClient::capabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    pub rfq: bool,
+    pub strategies: bool,
+    pub collateral: bool,
+}
+
+impl Capabilities {
+    /// Returns `true` if the endpoints of `capability` are served.
+    pub const fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Rfq => self.rfq,
+            Capability::Strategies => self.strategies,
+            Capability::Collateral => self.collateral,
+        }
+    }
+}
+
+impl BpxClient {
+    /// Probes which optional endpoints the exchange serves.
+    ///
+    /// One request is sent per [`Capability`], and an endpoint counts as served unless it
+    /// answers `404 Not Found` with a body that isn't an API error. Errors such as a
+    /// `405 Method Not Allowed` or an authentication failure still show that the endpoint
+    /// exists. The result is cached and shared by all clones of the client; once probed,
+    /// calls to the endpoints of a missing capability fail with [`Error::Unsupported`]
+    /// without sending a request.
+    pub async fn capabilities(&self) -> Result<Capabilities> {
+        self.capabilities
+            .get_or_try_init(|| async {
+                Ok(Capabilities {
+                    rfq: self.probe(Capability::Rfq).await?,
+                    strategies: self.probe(Capability::Strategies).await?,
+                    collateral: self.probe(Capability::Collateral).await?,
+                })
+            })
+            .await
+            .copied()
+    }
+
+    /// Fails with [`Error::Unsupported`] if the capabilities were probed and `capability`
+    /// is missing.
+    pub(crate) fn require(&self, capability: Capability) -> Result<()> {
+        match self.capabilities.get() {
+            Some(capabilities) if !capabilities.supports(capability) => Err(Error::Unsupported(capability)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns whether the endpoint of `capability` is served.
+    async fn probe(&self, capability: Capability) -> Result<bool> {
+        let url = format!("{}{}", self.base_url, capability.probe_path());
+        match self
+            .get(url)
+            .await
+            .map_err(|err| unsupported_if_missing(err, capability))
+        {
+            Err(Error::Unsupported(_)) => Ok(false),
+            Ok(_) | Err(Error::BpxApiError { .. }) => Ok(true),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Turns a `404 Not Found` for a missing endpoint into [`Error::Unsupported`].
+///
+/// The exchange answers requests for missing resources, such as an unknown order, with a
+/// JSON error carrying a `code`; any other `404` body means the endpoint itself is missing.
+pub(crate) fn unsupported_if_missing(err: Error, capability: Capability) -> Error {
+    match &err {
+        Error::BpxApiError { status_code, message } if *status_code == StatusCode::NOT_FOUND => {
+            let api_error = serde_json::from_str::<Value>(message).is_ok_and(|body| body.get("code").is_some());
+            if api_error {
+                err
+            } else {
+                Error::Unsupported(capability)
+            }
+        }
+        _ => err,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{test_client_builder, MockResponse, MockServer};
+
+    #[tokio::test]
+    async fn test_missing_endpoint_is_unsupported() {
+        let server = MockServer::start(vec![
+            MockResponse::new(405, ""),
+            MockResponse::new(404, "Not Found"),
+            MockResponse::new(200, "{}"),
+        ])
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let capabilities = client.capabilities().await.unwrap();
+        assert_eq!(
+            capabilities,
+            Capabilities {
+                rfq: true,
+                strategies: false,
+                collateral: true,
+            }
+        );
+        // Cached, and shared with clones.
+        assert_eq!(client.clone().capabilities().await.unwrap(), capabilities);
+        assert_eq!(server.requests().len(), 3);
+
+        let err = client.get_strategy_history(Default::default()).await.unwrap_err();
+        assert!(matches!(err, Error::Unsupported(Capability::Strategies)));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_api_not_found_is_kept() {
+        let not_found = || Error::BpxApiError {
+            status_code: StatusCode::NOT_FOUND,
+            message: r#"{"code":"RESOURCE_NOT_FOUND","message":"Not found"}"#.into(),
+        };
+        assert!(matches!(
+            unsupported_if_missing(not_found(), Capability::Rfq),
+            Error::BpxApiError { .. }
+        ));
+        let missing = Error::BpxApiError {
+            status_code: StatusCode::NOT_FOUND,
+            message: "<html>Not Found</html>".into(),
+        };
+        assert!(matches!(
+            unsupported_if_missing(missing, Capability::Rfq),
+            Error::Unsupported(Capability::Rfq)
+        ));
+    }
+}
@@ -3,6 +3,8 @@
 //! Defines a custom `Error` type and a `Result` type alias to encapsulate
 //! various errors that can occur during API interactions.
 
+use std::time::Duration;
+
 /// A type alias for `Result` using the custom `Error` type.
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -20,6 +22,10 @@ pub enum Error {
         message: Box<str>,
     },
 
+    /// The connection closed before the whole response body was received.
+    #[error("Incomplete response: received {received} of {expected} bytes")]
+    IncompleteResponse { expected: u64, received: u64 },
+
     /// Invalid HTTP header value.
     #[error(transparent)]
     InvalidHeaderValue(#[from] reqwest::header::InvalidHeaderValue),
@@ -32,6 +38,47 @@ pub enum Error {
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
 
+    /// An order submission timed out and no open order with its client ID was found, even
+    /// after waiting out the receive window, past which the exchange rejects the request.
+    /// The order was most likely never placed or already filled, cancelled or expired, but
+    /// if the client's clock runs behind the exchange's it may still land, so check the
+    /// open orders and fills before resubmitting it.
+    #[error("Order with client ID {client_id} timed out and is not open")]
+    OrderUnconfirmed { client_id: u32 },
+
+    /// A post-only order would cross the book and be rejected by the exchange, see
+    /// [`crate::BpxClientBuilder::post_only_guard`].
+    #[error(transparent)]
+    PostOnlyWouldCross(#[from] bpx_api_types::order::PostOnlyWouldCross),
+
+    /// The exchange doesn't serve the endpoints of this capability, see
+    /// [`crate::BpxClient::capabilities`].
+    #[error("The exchange does not support {0}")]
+    Unsupported(crate::Capability),
+
+    /// Every rate limit bucket is empty and the caller chose not to wait, see
+    /// [`crate::BpxClientPool::try_acquire`].
+    #[error("Rate limited, retry in {retry_after:?}")]
+    RateLimited { retry_after: Duration },
+
+    /// The circuit breaker of the endpoint is open after repeated failures, see
+    /// [`crate::BpxClientBuilder::circuit_breaker`]. The breaker lets a probe through after
+    /// `retry_after`.
+    #[error("Circuit open for {endpoint}, retry in {retry_after:?}")]
+    CircuitOpen { endpoint: Box<str>, retry_after: Duration },
+
+    /// A notional or aggregate calculation exceeded the range of `Decimal`.
+    #[error(transparent)]
+    DecimalOverflow(#[from] bpx_api_types::DecimalOverflow),
+
+    /// A request didn't complete by its deadline, see [`crate::BpxClient::with_deadline`].
+    #[error("Request deadline exceeded")]
+    DeadlineExceeded,
+
+    /// Required environment variables are unset, see [`crate::BpxClientBuilder::from_env`].
+    #[error("Missing environment variables: {}", .0.join(", "))]
+    MissingEnvVars(Vec<&'static str>),
+
     /// Invalid secret key provided.
     #[error("Invalid secret key")]
     SecretKey,
@@ -48,7 +95,31 @@ pub enum Error {
     #[error(transparent)]
     Utf8(#[from] std::str::Utf8Error),
 
+    /// WebSocket connection error.
+    #[cfg(feature = "ws")]
+    #[error(transparent)]
+    WebSocket(#[from] Box<tokio_tungstenite::tungstenite::Error>),
+
+    /// The WebSocket connection closed before a message could be sent.
+    #[cfg(feature = "ws")]
+    #[error("WebSocket connection closed")]
+    WebSocketClosed,
+
     /// Invalid URL format.
     #[error("Invalid URL: {0}")]
     UrlParseError(Box<str>),
 }
+
+impl Error {
+    /// Returns whether the request that produced this error may succeed if sent again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::BpxApiError { status_code, .. } => {
+                *status_code == reqwest::StatusCode::TOO_MANY_REQUESTS || status_code.is_server_error()
+            }
+            Error::IncompleteResponse { .. } => true,
+            Error::Reqwest(err) => err.is_timeout() || err.is_connect(),
+            _ => false,
+        }
+    }
+}
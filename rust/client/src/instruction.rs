@@ -0,0 +1,303 @@
+//! The instructions identifying signed requests.
+
+use std::fmt;
+
+use reqwest::Method;
+
+use crate::routes::{
+    account::{
+        API_ACCOUNT, API_ACCOUNT_CONVERT_DUST, API_ACCOUNT_MAX_BORROW, API_ACCOUNT_MAX_ORDER,
+        API_ACCOUNT_MAX_WITHDRAWAL,
+    },
+    borrow_lend::API_BORROW_LEND_POSITIONS,
+    capital::{API_CAPITAL, API_COLLATERAL, API_DEPOSITS, API_DEPOSIT_ADDRESS, API_WITHDRAWALS},
+    futures::API_FUTURES_POSITION,
+    history::{API_FILL_HISTORY, API_ORDER_HISTORY},
+    order::{API_ORDER, API_ORDERS},
+    rfq::{API_RFQ, API_RFQ_QUOTE},
+    strategies::API_STRATEGY_HISTORY,
+    user::API_USER_2FA,
+};
+
+/// The instruction a signed request is signed with, prepended to the signee as
+/// `instruction=<name>`.
+///
+/// Each signed endpoint maps to exactly one instruction, see [`Instruction::for_request`].
+/// Requests to any other endpoint are sent unsigned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    AccountQuery,
+    AccountUpdate,
+    BalanceQuery,
+    BorrowLendPositionQuery,
+    CollateralQuery,
+    ConvertDust,
+    DepositAddressQuery,
+    DepositQueryAll,
+    FillHistoryQueryAll,
+    IssueTwoFactorToken,
+    MaxBorrowQuantity,
+    MaxOrderQuantity,
+    MaxWithdrawalQuantity,
+    OrderCancel,
+    OrderCancelAll,
+    OrderExecute,
+    OrderHistoryQueryAll,
+    OrderQuery,
+    OrderQueryAll,
+    PositionQuery,
+    QuoteSubmit,
+    RfqSubmit,
+    StrategyHistoryQueryAll,
+    Withdraw,
+    WithdrawalQueryAll,
+}
+
+impl Instruction {
+    /// Returns the instruction for a request to `path` with `method`, or `None` if the
+    /// endpoint is not signed.
+    pub fn for_request(path: &str, method: &Method) -> Option<Self> {
+        let get = *method == Method::GET;
+        let post = *method == Method::POST;
+        let delete = *method == Method::DELETE;
+        let patch = *method == Method::PATCH;
+        Some(match path {
+            API_CAPITAL if get => Self::BalanceQuery,
+            API_DEPOSITS if get => Self::DepositQueryAll,
+            API_DEPOSIT_ADDRESS if get => Self::DepositAddressQuery,
+            API_WITHDRAWALS if get => Self::WithdrawalQueryAll,
+            API_WITHDRAWALS if post => Self::Withdraw,
+            API_USER_2FA if post => Self::IssueTwoFactorToken,
+            API_ORDER if get => Self::OrderQuery,
+            API_ORDER if post => Self::OrderExecute,
+            API_ORDER if delete => Self::OrderCancel,
+            API_ORDERS if get => Self::OrderQueryAll,
+            API_ORDERS if post => Self::OrderExecute,
+            API_ORDERS if delete => Self::OrderCancelAll,
+            API_RFQ if post => Self::RfqSubmit,
+            API_RFQ_QUOTE if post => Self::QuoteSubmit,
+            API_FUTURES_POSITION if get => Self::PositionQuery,
+            API_BORROW_LEND_POSITIONS if get => Self::BorrowLendPositionQuery,
+            API_COLLATERAL if get => Self::CollateralQuery,
+            API_ACCOUNT if get => Self::AccountQuery,
+            API_ACCOUNT if patch => Self::AccountUpdate,
+            API_ACCOUNT_MAX_BORROW if get => Self::MaxBorrowQuantity,
+            API_ACCOUNT_MAX_WITHDRAWAL if get => Self::MaxWithdrawalQuantity,
+            API_ACCOUNT_MAX_ORDER if get => Self::MaxOrderQuantity,
+            API_ACCOUNT_CONVERT_DUST if post => Self::ConvertDust,
+            API_STRATEGY_HISTORY if get => Self::StrategyHistoryQueryAll,
+            API_FILL_HISTORY if get => Self::FillHistoryQueryAll,
+            API_ORDER_HISTORY if get => Self::OrderHistoryQueryAll,
+            _ => return None,
+        })
+    }
+
+    /// Returns the name the exchange expects in the signee.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::AccountQuery => "accountQuery",
+            Self::AccountUpdate => "accountUpdate",
+            Self::BalanceQuery => "balanceQuery",
+            Self::BorrowLendPositionQuery => "borrowLendPositionQuery",
+            Self::CollateralQuery => "collateralQuery",
+            Self::ConvertDust => "convertDust",
+            Self::DepositAddressQuery => "depositAddressQuery",
+            Self::DepositQueryAll => "depositQueryAll",
+            Self::FillHistoryQueryAll => "fillHistoryQueryAll",
+            Self::IssueTwoFactorToken => "issueTwoFactorToken",
+            Self::MaxBorrowQuantity => "maxBorrowQuantity",
+            Self::MaxOrderQuantity => "maxOrderQuantity",
+            Self::MaxWithdrawalQuantity => "maxWithdrawalQuantity",
+            Self::OrderCancel => "orderCancel",
+            Self::OrderCancelAll => "orderCancelAll",
+            Self::OrderExecute => "orderExecute",
+            Self::OrderHistoryQueryAll => "orderHistoryQueryAll",
+            Self::OrderQuery => "orderQuery",
+            Self::OrderQueryAll => "orderQueryAll",
+            Self::PositionQuery => "positionQuery",
+            Self::QuoteSubmit => "quoteSubmit",
+            Self::RfqSubmit => "rfqSubmit",
+            Self::StrategyHistoryQueryAll => "strategyHistoryQueryAll",
+            Self::Withdraw => "withdraw",
+            Self::WithdrawalQueryAll => "withdrawalQueryAll",
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{test_client_builder, MockResponse, MockServer};
+    use crate::BpxClient;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use bpx_api_types::{
+        account::ConvertDustPayload,
+        capital::RequestWithdrawalPayload,
+        history::{FillHistorySearchParams, OrderHistorySearchParams},
+        order::{CancelOpenOrdersPayload, ExecuteOrderPayload, OrderType, Side},
+        rfq::{QuotePayload, RequestForQuotePayload},
+        strategies::StrategyHistorySearchParams,
+        user::RequestTwoFactorPayload,
+        Blockchain,
+    };
+    use ed25519_dalek::Signature;
+    use reqwest::Url;
+    use rust_decimal::Decimal;
+    use serde_json::Value;
+
+    /// Asserts that the route called since the previous check sent a single request, signed
+    /// with `instruction` or unsigned if `None`.
+    fn assert_signed_with(
+        client: &BpxClient,
+        server: &MockServer,
+        checked: &mut usize,
+        instruction: Option<Instruction>,
+    ) {
+        let requests = server.requests();
+        assert_eq!(requests.len(), *checked + 1, "expected one request for {instruction:?}");
+        let request = &requests[*checked];
+        *checked += 1;
+
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        let header = |name: &str| {
+            let prefix = format!("{name}: ");
+            head.lines().find_map(|line| line.strip_prefix(&prefix))
+        };
+        let Some(instruction) = instruction else {
+            assert_eq!(header("x-signature"), None, "{head}");
+            return;
+        };
+
+        let target = head.split_whitespace().nth(1).unwrap();
+        let url = Url::parse(&format!("{}{target}", server.url())).unwrap();
+        let payload = (!body.is_empty()).then(|| serde_json::from_str::<Value>(body).unwrap());
+        let signee = format!(
+            "{}&timestamp={}&window={}",
+            client.signee(instruction, payload.as_ref(), &url).unwrap(),
+            header("x-timestamp").unwrap(),
+            header("x-window").unwrap(),
+        );
+        let signature = STANDARD.decode(header("x-signature").unwrap()).unwrap();
+        let signature = Signature::from_slice(&signature).unwrap();
+        assert!(
+            client.verifier.verify_strict(signee.as_bytes(), &signature).is_ok(),
+            "{head} is not signed as {signee}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_routes_sign_with_expected_instructions() {
+        let server = MockServer::start_with(|_| MockResponse::new(200, "{}")).await;
+        let client = test_client_builder(&server).build().unwrap();
+        let mut checked = 0;
+        let symbol = "SOL_USDC";
+        let order = ExecuteOrderPayload {
+            client_id: Some(7),
+            order_type: OrderType::Limit,
+            price: Some(Decimal::ONE_HUNDRED),
+            quantity: Some(Decimal::ONE),
+            side: Side::Bid,
+            symbol: symbol.to_string(),
+            ..Default::default()
+        };
+        let mut check = |instruction| assert_signed_with(&client, &server, &mut checked, instruction);
+
+        let _ = client.get_balances().await;
+        check(Some(Instruction::BalanceQuery));
+        let _ = client.get_deposits(Some(10), None).await;
+        check(Some(Instruction::DepositQueryAll));
+        let _ = client.get_deposit_address(Blockchain::Solana).await;
+        check(Some(Instruction::DepositAddressQuery));
+        let _ = client.get_withdrawals(None, Some(5)).await;
+        check(Some(Instruction::WithdrawalQueryAll));
+        let _ = client.request_withdrawal(RequestWithdrawalPayload::default()).await;
+        check(Some(Instruction::Withdraw));
+        let _ = client.get_collateral().await;
+        check(Some(Instruction::CollateralQuery));
+        let _ = client.request_two_factor(RequestTwoFactorPayload::default()).await;
+        check(Some(Instruction::IssueTwoFactorToken));
+
+        let _ = client.get_open_order(symbol, None, Some(7)).await;
+        check(Some(Instruction::OrderQuery));
+        let _ = client.execute_order(order.clone()).await;
+        check(Some(Instruction::OrderExecute));
+        let _ = client.execute_orders(vec![order.clone(), order]).await;
+        check(Some(Instruction::OrderExecute));
+        let _ = client.cancel_order(symbol, Some("111"), None).await;
+        check(Some(Instruction::OrderCancel));
+        let _ = client.get_open_orders(Some(symbol)).await;
+        check(Some(Instruction::OrderQueryAll));
+        let _ = client
+            .cancel_open_orders(CancelOpenOrdersPayload {
+                symbol: symbol.to_string(),
+            })
+            .await;
+        check(Some(Instruction::OrderCancelAll));
+
+        let rfq = RequestForQuotePayload {
+            client_id: None,
+            quantity: Some(Decimal::ONE),
+            quote_quantity: None,
+            price: None,
+            symbol: symbol.to_string(),
+            side: Side::Ask,
+            execution_mode: None,
+        };
+        let _ = client.submit_rfq(rfq).await;
+        check(Some(Instruction::RfqSubmit));
+        let quote = QuotePayload {
+            rfq_id: "1".to_string(),
+            bid_price: Decimal::ONE,
+            ask_price: Decimal::TWO,
+        };
+        let _ = client.submit_quote(quote).await;
+        check(Some(Instruction::QuoteSubmit));
+
+        let _ = client.get_open_future_positions(Some(symbol)).await;
+        check(Some(Instruction::PositionQuery));
+        let _ = client.get_borrow_lend_positions().await;
+        check(Some(Instruction::BorrowLendPositionQuery));
+        let _ = client.get_account().await;
+        check(Some(Instruction::AccountQuery));
+        let _ = client.set_leverage(Decimal::TEN).await;
+        check(Some(Instruction::AccountUpdate));
+        let _ = client.get_account_max_borrow("SOL").await;
+        check(Some(Instruction::MaxBorrowQuantity));
+        let _ = client.get_account_max_withdrawal("SOL", Some(true), None).await;
+        check(Some(Instruction::MaxWithdrawalQuantity));
+        let _ = client
+            .get_account_max_order_quantity(symbol, Side::Bid, Some(Decimal::ONE), None, None, None, None)
+            .await;
+        check(Some(Instruction::MaxOrderQuantity));
+        let _ = client.convert_dust_balance(ConvertDustPayload::default()).await;
+        check(Some(Instruction::ConvertDust));
+
+        let _ = client
+            .get_strategy_history(StrategyHistorySearchParams::default())
+            .await;
+        check(Some(Instruction::StrategyHistoryQueryAll));
+        let fills = FillHistorySearchParams {
+            symbol: Some(symbol.to_string()),
+            ..Default::default()
+        };
+        let _ = client.get_fill_history(fills).await;
+        check(Some(Instruction::FillHistoryQueryAll));
+        let orders = OrderHistorySearchParams {
+            symbol: Some(symbol.to_string()),
+            ..Default::default()
+        };
+        let _ = client.get_order_history(orders).await;
+        check(Some(Instruction::OrderHistoryQueryAll));
+
+        let _ = client.get_markets().await;
+        check(None);
+        let _ = client.get_ticker(symbol).await;
+        check(None);
+    }
+}
@@ -9,6 +9,13 @@
 //! - Supports both REST and WebSocket endpoints.
 //! - Includes modules for managing capital, orders, trades, and user data.
 //!
+//! ## TLS backends
+//! - `rustls-tls` (default): uses `rustls`, with no dependency on OpenSSL.
+//! - `native-tls`: uses the platform TLS library and certificate store.
+//!
+//! The same backend is used for REST and WebSocket connections. If both features are
+//! enabled, `native-tls` takes precedence.
+//!
 //! ## Example
 //! ```no_run
 //! # // We depend on tokio only when the `ws` feature is enabled.
@@ -34,33 +41,39 @@
 //! ```
 
 use base64::{engine::general_purpose::STANDARD, Engine};
+use bpx_api_types::markets::Market;
+use bpx_api_types::order::{SelfTradePrevention, TimeInForce};
 use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
-use reqwest::{header::CONTENT_TYPE, IntoUrl, Method, Request, Response, StatusCode};
-use routes::{
-    account::{
-        API_ACCOUNT, API_ACCOUNT_CONVERT_DUST, API_ACCOUNT_MAX_BORROW, API_ACCOUNT_MAX_ORDER,
-        API_ACCOUNT_MAX_WITHDRAWAL,
-    },
-    borrow_lend::API_BORROW_LEND_POSITIONS,
-    capital::{API_CAPITAL, API_COLLATERAL, API_DEPOSITS, API_DEPOSIT_ADDRESS, API_WITHDRAWALS},
-    futures::API_FUTURES_POSITION,
-    history::{API_FILL_HISTORY, API_ORDER_HISTORY},
-    order::{API_ORDER, API_ORDERS},
-    rfq::{API_RFQ, API_RFQ_QUOTE},
-    strategies::API_STRATEGY_HISTORY,
-    user::API_USER_2FA,
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE, RETRY_AFTER},
+    IntoUrl, Method, Request, Response, ResponseBuilderExt, StatusCode, Url,
 };
 use serde::Serialize;
 use serde_json::Value;
 use std::{
     borrow::Cow,
-    collections::BTreeMap,
-    time::{SystemTime, UNIX_EPOCH},
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+#[cfg(not(any(feature = "rustls-tls", feature = "native-tls")))]
+compile_error!("either the `rustls-tls` or the `native-tls` feature must be enabled");
+
 pub mod error;
 
+mod breaker;
+mod builder;
+mod capabilities;
+mod instruction;
+mod pool;
 mod routes;
+#[cfg(feature = "signature-debug")]
+mod signature_debug;
+
+#[cfg(test)]
+mod test_utils;
 
 #[cfg(feature = "ws")]
 mod ws;
@@ -71,7 +84,21 @@ pub use bpx_api_types as types;
 /// Re-export of the custom `Error` type and `Result` alias for error handling.
 pub use error::{Error, Result};
 
-const API_USER_AGENT: &str = "bpx-rust-client";
+pub use breaker::CircuitBreakerConfig;
+pub use builder::{BpxClientBuilder, RetryPolicy, RoundingConfig};
+pub use capabilities::{Capabilities, Capability};
+pub use instruction::Instruction;
+pub use pool::{BpxClientPool, RateLimit};
+pub use routes::markets::{SymbolContext, MAX_KLINES_LIMIT};
+pub use routes::order::{OrderVerdict, MAX_CONCURRENT_CANCELS};
+pub use routes::system::{ComponentHealth, Health};
+#[cfg(feature = "signature-debug")]
+pub use signature_debug::SignatureDebug;
+#[cfg(feature = "ws")]
+pub use ws::{ConnectionState, Misaligned, OrderBookOptions, ReconnectPolicy, WsStream};
+
+/// The default `User-Agent`, identifying the crate and its version.
+const API_USER_AGENT: &str = concat!("bpx-api-client/", env!("CARGO_PKG_VERSION"));
 const API_KEY_HEADER: &str = "X-API-Key";
 
 const DEFAULT_WINDOW: u32 = 5000;
@@ -82,6 +109,9 @@ const WINDOW_HEADER: &str = "X-Window";
 
 const JSON_CONTENT: &str = "application/json; charset=utf-8";
 
+/// Most bytes reserved for a response body up front, whatever its `Content-Length` claims.
+const MAX_BODY_PREALLOCATION: u64 = 1 << 20;
+
 /// The official base URL for the Backpack Exchange REST API.
 pub const BACKPACK_API_BASE_URL: &str = "https://api.backpack.exchange";
 
@@ -92,14 +122,32 @@ pub const BACKPACK_WS_URL: &str = "wss://ws.backpack.exchange";
 pub type BpxHeaders = reqwest::header::HeaderMap;
 
 /// A client for interacting with the Backpack Exchange API.
+///
+/// The client is `Clone + Send + Sync`. Cloning is cheap and returns a handle to the same
+/// connection pool and shared state (such as the market cache), so a single client can be
+/// cloned into as many tasks as needed instead of being wrapped in a `Mutex`.
 #[derive(Debug, Clone)]
 pub struct BpxClient {
+    api_key: HeaderValue,
     signer: SigningKey,
     verifier: VerifyingKey,
     base_url: String,
     #[allow(dead_code)]
     ws_url: Option<String>,
     client: reqwest::Client,
+    user_agent: String,
+    recv_window: u32,
+    rounding: Option<RoundingConfig>,
+    retry: RetryPolicy,
+    breakers: Option<Arc<breaker::CircuitBreakers>>,
+    order_bucket: Option<Arc<Mutex<pool::TokenBucket>>>,
+    self_trade_prevention: Option<SelfTradePrevention>,
+    time_in_force: Option<TimeInForce>,
+    post_only_guard: bool,
+    markets: Arc<RwLock<HashMap<String, Market>>>,
+    capabilities: Arc<tokio::sync::OnceCell<Capabilities>>,
+    #[cfg(feature = "ws")]
+    ws: Arc<ws::WsState>,
 }
 
 impl std::ops::Deref for BpxClient {
@@ -127,50 +175,59 @@ impl BpxClient {
     /// Initializes a new client with the given base URL, API secret, and optional headers.
     ///
     /// This sets up the signing and verification keys, and creates a `reqwest` client
-    /// with default headers including the API key and content type.
+    /// with default headers including the content type.
     pub fn init(base_url: String, secret: &str, headers: Option<BpxHeaders>) -> Result<Self> {
-        Self::init_internal(base_url, None, secret, headers)
+        Self::init_builder(base_url, secret, headers).build()
     }
 
     /// Initializes a new client with WebSocket support.
     #[cfg(feature = "ws")]
     pub fn init_with_ws(base_url: String, ws_url: String, secret: &str, headers: Option<BpxHeaders>) -> Result<Self> {
-        Self::init_internal(base_url, Some(ws_url), secret, headers)
-    }
-
-    /// Internal helper function for client initialization.
-    fn init_internal(
-        base_url: String,
-        ws_url: Option<String>,
-        secret: &str,
-        headers: Option<BpxHeaders>,
-    ) -> Result<Self> {
-        let signer = STANDARD
-            .decode(secret)?
-            .try_into()
-            .map(|s| SigningKey::from_bytes(&s))
-            .map_err(|_| Error::SecretKey)?;
-
-        let verifier = signer.verifying_key();
-
-        let mut headers = headers.unwrap_or_default();
-        headers.insert(API_KEY_HEADER, STANDARD.encode(verifier).parse()?);
-        headers.insert(CONTENT_TYPE, JSON_CONTENT.parse()?);
+        Self::init_builder(base_url, secret, headers).ws_url(ws_url).build()
+    }
 
-        let client = reqwest::Client::builder()
-            .user_agent(API_USER_AGENT)
-            .default_headers(headers)
-            .build()?;
+    /// Returns a builder to configure a new client.
+    pub fn builder() -> BpxClientBuilder {
+        BpxClientBuilder::new()
+    }
 
-        Ok(BpxClient {
+    /// Returns a client signing with the given base64 encoded API secret, for rotating keys
+    /// without downtime.
+    ///
+    /// The new client shares this client's HTTP connection pool, market cache and
+    /// configuration. Requests already sent or in flight on this client complete with the old
+    /// key. WebSocket subscriptions are not shared: the new client opens its own connection,
+    /// authenticated with the new key, on its first subscription.
+    ///
+    /// ```no_run
+    /// # fn example(client: std::sync::Arc<bpx_api_client::BpxClient>, new_secret: &str) -> bpx_api_client::Result<()> {
+    /// let rotated = std::sync::Arc::new(client.with_new_credentials(new_secret)?);
+    /// // Swap `rotated` in wherever `client` was shared, then drop the old client.
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_new_credentials(&self, secret: &str) -> Result<Self> {
+        let signer = builder::signing_key(secret)?;
+        let verifier = signer.verifying_key();
+        Ok(Self {
+            api_key: builder::api_key_header(&verifier)?,
             signer,
             verifier,
-            base_url,
-            ws_url,
-            client,
+            #[cfg(feature = "ws")]
+            ws: Arc::new(ws::WsState::new(self.ws.reconnect)),
+            ..self.clone()
         })
     }
 
+    /// Internal helper mapping the `init` arguments onto a builder.
+    fn init_builder(base_url: String, secret: &str, headers: Option<BpxHeaders>) -> BpxClientBuilder {
+        let builder = BpxClientBuilder::new().base_url(base_url).secret(secret);
+        match headers {
+            Some(headers) => builder.headers(headers),
+            None => builder,
+        }
+    }
+
     /// Creates a new, empty `BpxHeaders` instance.
     pub fn create_headers() -> BpxHeaders {
         reqwest::header::HeaderMap::new()
@@ -179,7 +236,9 @@ impl BpxClient {
     /// Processes the response to check for HTTP errors and extracts
     /// the response content.
     ///
-    /// Returns a custom error if the status code is non-2xx.
+    /// Returns a custom error if the status code is non-2xx, and
+    /// [`Error::IncompleteResponse`] if the connection drops before the number of bytes
+    /// announced in `Content-Length` is received.
     async fn process_response(res: Response) -> Result<Response> {
         if let Err(e) = res.error_for_status_ref() {
             let err_text = res.text().await?;
@@ -189,39 +248,120 @@ impl BpxClient {
             };
             return Err(err);
         }
-        Ok(res)
+        Self::read_body(res).await
+    }
+
+    /// Reads the whole body of a response, so a truncated body is reported as
+    /// [`Error::IncompleteResponse`] rather than as a JSON error, and returns an equivalent
+    /// response, with the same status, headers and URL, holding the buffered body.
+    async fn read_body(mut res: Response) -> Result<Response> {
+        let expected = res.content_length();
+        let mut head = http::Response::builder()
+            .status(res.status())
+            .version(res.version())
+            .url(res.url().clone());
+        if let Some(headers) = head.headers_mut() {
+            *headers = std::mem::take(res.headers_mut());
+        }
+
+        // The announced length is only a hint: a bogus one must not allocate up front.
+        let capacity = expected.unwrap_or_default().min(MAX_BODY_PREALLOCATION);
+        let mut body = Vec::with_capacity(capacity as usize);
+        loop {
+            match res.chunk().await {
+                Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                Ok(None) => break,
+                Err(_) if expected.is_some() => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let received = body.len() as u64;
+        if let Some(expected) = expected.filter(|&expected| received < expected) {
+            return Err(Error::IncompleteResponse { expected, received });
+        }
+
+        let head = head
+            .body(body)
+            .map_err(|e| Error::InvalidRequest(e.to_string().into()))?;
+        Ok(Response::from(head))
     }
 
     /// Sends a GET request to the specified URL and signs it before execution.
     pub async fn get<U: IntoUrl>(&self, url: U) -> Result<Response> {
-        let req = self.build_and_maybe_sign_request::<(), _>(url, Method::GET, None)?;
-        tracing::debug!("req: {:?}", req);
-        let res = self.client.execute(req).await?;
-        Self::process_response(res).await
+        self.send::<(), _>(url, Method::GET, None).await
     }
 
     /// Sends a POST request with a JSON payload to the specified URL and signs it.
     pub async fn post<P: Serialize, U: IntoUrl>(&self, url: U, payload: P) -> Result<Response> {
-        let req = self.build_and_maybe_sign_request(url, Method::POST, Some(&payload))?;
-        tracing::debug!("req: {:?}", req);
-        let res = self.client.execute(req).await?;
-        Self::process_response(res).await
+        self.send(url, Method::POST, Some(&payload)).await
     }
 
     /// Sends a DELETE request with a JSON payload to the specified URL and signs it.
     pub async fn delete<P: Serialize, U: IntoUrl>(&self, url: U, payload: P) -> Result<Response> {
-        let req = self.build_and_maybe_sign_request(url, Method::DELETE, Some(&payload))?;
-        tracing::debug!("req: {:?}", req);
-        let res = self.client.execute(req).await?;
-        Self::process_response(res).await
+        self.send(url, Method::DELETE, Some(&payload)).await
     }
 
     /// Sends a PATCH request with a JSON payload to the specified URL and signs it.
     pub async fn patch<P: Serialize, U: IntoUrl>(&self, url: U, payload: P) -> Result<Response> {
-        let req = self.build_and_maybe_sign_request(url, Method::PATCH, Some(&payload))?;
-        tracing::debug!("req: {:?}", req);
-        let res = self.client.execute(req).await?;
-        Self::process_response(res).await
+        self.send(url, Method::PATCH, Some(&payload)).await
+    }
+
+    /// Sends a GET request to the specified URL like [`BpxClient::get`], failing with
+    /// [`Error::DeadlineExceeded`] if it doesn't complete by `deadline`.
+    pub async fn get_with_deadline<U: IntoUrl>(&self, url: U, deadline: tokio::time::Instant) -> Result<Response> {
+        Self::with_deadline(deadline, self.get(url)).await
+    }
+
+    /// Bounds a call on the client by `deadline`, failing with [`Error::DeadlineExceeded`] if
+    /// it doesn't complete in time. Retries are covered by the same deadline.
+    ///
+    /// This bounds a single slow call on a latency-critical path without changing the
+    /// timeout of other requests. A deadline already past fails without sending anything.
+    /// An abandoned request is dropped along with its connection rather than returned to
+    /// the pool half-read, so later requests are unaffected. An order submission that misses
+    /// its deadline may still have reached the exchange.
+    ///
+    /// ```no_run
+    /// # async fn example(client: bpx_api_client::BpxClient) -> bpx_api_client::Result<()> {
+    /// use std::time::Duration;
+    /// use bpx_api_client::BpxClient;
+    ///
+    /// let deadline = tokio::time::Instant::now() + Duration::from_millis(50);
+    /// let depth = BpxClient::with_deadline(deadline, client.get_order_book_depth("SOL_USDC")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn with_deadline<T>(
+        deadline: tokio::time::Instant,
+        request: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        if deadline <= tokio::time::Instant::now() {
+            return Err(Error::DeadlineExceeded);
+        }
+        tokio::time::timeout_at(deadline, request)
+            .await
+            .unwrap_or(Err(Error::DeadlineExceeded))
+    }
+
+    /// Returns the base URL of the REST API.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns the WebSocket URL.
+    #[cfg(feature = "ws")]
+    pub fn ws_url(&self) -> &str {
+        self.ws_url.as_deref().unwrap_or(BACKPACK_WS_URL)
+    }
+
+    /// Returns the `User-Agent` sent with REST requests and WebSocket handshakes.
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    /// Returns the window after the request timestamp during which signed requests are valid.
+    pub const fn recv_window(&self) -> Duration {
+        Duration::from_millis(self.recv_window as u64)
     }
 
     /// Returns a reference to the `VerifyingKey` used for request verification.
@@ -230,6 +370,9 @@ impl BpxClient {
     }
 
     /// Returns a reference to the underlying HTTP client.
+    ///
+    /// The API key is added to each request by the client rather than set as a default
+    /// header, so requests sent directly through the HTTP client don't carry it.
     pub const fn client(&self) -> &reqwest::Client {
         &self.client
     }
@@ -237,6 +380,66 @@ impl BpxClient {
 
 // Private functions.
 impl BpxClient {
+    /// Builds the URL of an API path, URL-encoding and appending the query parameters.
+    fn url_with_query(&self, path: &str, params: &[(&str, String)]) -> Result<Url> {
+        let mut url = Url::parse(&format!("{}{}", self.base_url, path))
+            .map_err(|e| Error::UrlParseError(e.to_string().into()))?;
+        if !params.is_empty() {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+        Ok(url)
+    }
+
+    /// Sends a GET request to an API path with the given query parameters.
+    async fn get_with_query(&self, path: &str, params: &[(&str, String)]) -> Result<Response> {
+        let url = self.url_with_query(path, params)?;
+        self.get(url).await
+    }
+
+    /// Sends a request through the endpoint's circuit breaker, if enabled.
+    async fn send<P: Serialize, U: IntoUrl>(&self, url: U, method: Method, payload: Option<&P>) -> Result<Response> {
+        let url = url.into_url()?;
+        let Some(breakers) = &self.breakers else {
+            return self.send_with_retries(url, method, payload).await;
+        };
+        let permit = breakers.permit(url.path())?;
+        let res = self.send_with_retries(url, method, payload).await;
+        permit.record(&res);
+        res
+    }
+
+    /// Sends a request, retrying it according to the client's [`RetryPolicy`].
+    ///
+    /// The request is signed again on every attempt so the timestamp stays within the
+    /// receive window.
+    async fn send_with_retries<P: Serialize>(&self, url: Url, method: Method, payload: Option<&P>) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            let req = self.build_and_maybe_sign_request(url.clone(), method.clone(), payload)?;
+            tracing::debug!("req: {:?}", req);
+            let can_retry = attempt < self.retry.max_retries;
+            let delay = match self.client.execute(req).await {
+                Ok(res) if can_retry && res.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    retry_after(res.headers()).unwrap_or_else(|| self.retry.backoff(attempt))
+                }
+                Ok(res) => match Self::process_response(res).await {
+                    Err(err) if can_retry && method == Method::GET && err.is_retryable() => self.retry.backoff(attempt),
+                    res => return res,
+                },
+                Err(err) => {
+                    let err = Error::from(err);
+                    if !(can_retry && method == Method::GET && err.is_retryable()) {
+                        return Err(err);
+                    }
+                    self.retry.backoff(attempt)
+                }
+            };
+            tracing::warn!("retrying {method} {url} in {delay:?}");
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
     /// Signs a request by generating a signature from the request details
     /// and appending necessary headers for authentication.
     ///
@@ -249,63 +452,62 @@ impl BpxClient {
         payload: Option<&P>,
     ) -> Result<Request> {
         let url = url.into_url()?;
-        let instruction = match url.path() {
-            API_CAPITAL if method == Method::GET => "balanceQuery",
-            API_DEPOSITS if method == Method::GET => "depositQueryAll",
-            API_DEPOSIT_ADDRESS if method == Method::GET => "depositAddressQuery",
-            API_WITHDRAWALS if method == Method::GET => "withdrawalQueryAll",
-            API_WITHDRAWALS if method == Method::POST => "withdraw",
-            API_USER_2FA if method == Method::POST => "issueTwoFactorToken",
-            API_ORDER if method == Method::GET => "orderQuery",
-            API_ORDER if method == Method::POST => "orderExecute",
-            API_ORDER if method == Method::DELETE => "orderCancel",
-            API_ORDERS if method == Method::GET => "orderQueryAll",
-            API_ORDERS if method == Method::POST => "orderExecute",
-            API_ORDERS if method == Method::DELETE => "orderCancelAll",
-            API_RFQ if method == Method::POST => "rfqSubmit",
-            API_RFQ_QUOTE if method == Method::POST => "quoteSubmit",
-            API_FUTURES_POSITION if method == Method::GET => "positionQuery",
-            API_BORROW_LEND_POSITIONS if method == Method::GET => "borrowLendPositionQuery",
-            API_COLLATERAL if method == Method::GET => "collateralQuery",
-            API_ACCOUNT if method == Method::GET => "accountQuery",
-            API_ACCOUNT_MAX_BORROW if method == Method::GET => "maxBorrowQuantity",
-            API_ACCOUNT_MAX_WITHDRAWAL if method == Method::GET => "maxWithdrawalQuantity",
-            API_ACCOUNT_MAX_ORDER if method == Method::GET => "maxOrderQuantity",
-            API_ACCOUNT if method == Method::PATCH => "accountUpdate",
-            API_ACCOUNT_CONVERT_DUST if method == Method::POST => "convertDust",
-            API_STRATEGY_HISTORY if method == Method::GET => "strategyHistoryQueryAll",
-            API_FILL_HISTORY if method == Method::GET => "fillHistoryQueryAll",
-            API_ORDER_HISTORY if method == Method::GET => "orderHistoryQueryAll",
-            _ => {
-                let req = self.client().request(method, url);
-                if let Some(payload) = payload {
-                    return Ok(req.json(payload).build()?);
-                } else {
-                    return Ok(req.build()?);
-                }
+        let Some(instruction) = Instruction::for_request(url.path(), &method) else {
+            let mut req = self.client().request(method, url);
+            if let Some(payload) = payload {
+                req = req.json(payload);
             }
+            let mut req = req.build()?;
+            req.headers_mut().insert(API_KEY_HEADER, self.api_key.clone());
+            return Ok(req);
         };
 
-        let query_params = url.query_pairs().collect::<BTreeMap<Cow<'_, str>, Cow<'_, str>>>();
-
         let timestamp = now_millis();
-        let mut signee = String::new();
+        let payload_value = payload.map(serde_json::to_value).transpose()?;
+        let mut signee = self.signee(instruction, payload_value.as_ref(), &url)?;
+        signee.push_str(&format!("&timestamp={timestamp}&window={}", self.recv_window));
+        #[cfg(feature = "signature-debug")]
+        tracing::debug!("signee: {}", signee);
+
+        let signature: Signature = self.signer.sign(signee.as_bytes());
+        let signature = STANDARD.encode(signature.to_bytes());
+
+        let mut req = self.client().request(method, url);
+        if let Some(payload) = payload {
+            req = req.json(payload);
+        }
+        let mut req = req.build()?;
+        req.headers_mut().insert(API_KEY_HEADER, self.api_key.clone());
+        req.headers_mut().insert(SIGNATURE_HEADER, signature.parse()?);
+        req.headers_mut()
+            .insert(TIMESTAMP_HEADER, timestamp.to_string().parse()?);
+        req.headers_mut()
+            .insert(WINDOW_HEADER, self.recv_window.to_string().parse()?);
+        if matches!(req.method(), &Method::POST | &Method::DELETE) {
+            req.headers_mut().insert(CONTENT_TYPE, JSON_CONTENT.parse()?);
+        }
+        Ok(req)
+    }
 
-        if let Some(payload_ref) = payload {
-            let s = serde_json::to_value(payload_ref)?;
+    /// Builds the string signed for a request with `instruction`, up to but excluding the
+    /// trailing timestamp and window.
+    fn signee(&self, instruction: Instruction, payload: Option<&Value>, url: &Url) -> Result<String> {
+        let query_params = url.query_pairs().collect::<BTreeMap<Cow<'_, str>, Cow<'_, str>>>();
+        let mut signee = String::new();
 
-            if instruction == "orderExecute" && matches!(s, Value::Array(_)) {
+        if let Some(s) = payload {
+            if instruction == Instruction::OrderExecute && s.is_array() {
                 // Batch order execution
                 if let Value::Array(arr) = s {
                     let mut parts: Vec<String> = Vec::new();
                     for item in arr {
-                        let map = self.to_sorted_map(&item)?;
+                        let map = self.to_sorted_map(item)?;
                         let params_str = map
                             .iter()
                             .map(|(k, v)| format!("{k}={v}"))
                             .collect::<Vec<_>>()
                             .join("&");
-                        parts.push(format!("instruction=orderExecute&{params_str}"));
+                        parts.push(format!("instruction={instruction}&{params_str}"));
                     }
                     signee.push_str(&parts.join("&"));
                 }
@@ -313,7 +515,7 @@ impl BpxClient {
                 // Normal case
                 signee.push_str(&format!("instruction={instruction}"));
                 if let Value::Object(_) = s {
-                    let map = self.to_sorted_map(&s)?;
+                    let map = self.to_sorted_map(s)?;
                     for (k, v) in map {
                         signee.push_str(&format!("&{k}={v}"));
                     }
@@ -331,26 +533,7 @@ impl BpxClient {
             signee.push_str(&format!("&{k}={v}"));
         }
 
-        signee.push_str(&format!("&timestamp={timestamp}&window={DEFAULT_WINDOW}"));
-        tracing::debug!("signee: {}", signee);
-
-        let signature: Signature = self.signer.sign(signee.as_bytes());
-        let signature = STANDARD.encode(signature.to_bytes());
-
-        let mut req = self.client().request(method, url);
-        if let Some(payload) = payload {
-            req = req.json(payload);
-        }
-        let mut req = req.build()?;
-        req.headers_mut().insert(SIGNATURE_HEADER, signature.parse()?);
-        req.headers_mut()
-            .insert(TIMESTAMP_HEADER, timestamp.to_string().parse()?);
-        req.headers_mut()
-            .insert(WINDOW_HEADER, DEFAULT_WINDOW.to_string().parse()?);
-        if matches!(req.method(), &Method::POST | &Method::DELETE) {
-            req.headers_mut().insert(CONTENT_TYPE, JSON_CONTENT.parse()?);
-        }
-        Ok(req)
+        Ok(signee)
     }
 
     fn to_sorted_map(&self, value: &Value) -> Result<BTreeMap<String, String>> {
@@ -368,6 +551,17 @@ impl BpxClient {
     }
 }
 
+/// Parses a `Retry-After` header given either in seconds or as an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait_millis = date.timestamp_millis().saturating_sub(now_millis() as i64);
+    Some(Duration::from_millis(wait_millis.max(0) as u64))
+}
+
 /// Returns the current time in milliseconds since UNIX epoch.
 fn now_millis() -> u64 {
     SystemTime::now()
@@ -375,3 +569,293 @@ fn now_millis() -> u64 {
         .expect("Time went backwards")
         .as_millis() as u64
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{test_client_builder, MockResponse, MockServer, TEST_MARKET_JSON};
+
+    #[test]
+    fn test_retry_after_parse() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "2".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(2)));
+
+        headers.insert(RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_url_with_query() {
+        let client = BpxClient::init("https://api.test".to_string(), crate::test_utils::TEST_SECRET, None).unwrap();
+        let url = client.url_with_query("/api/v1/depth", &[]).unwrap();
+        assert_eq!(url.as_str(), "https://api.test/api/v1/depth");
+
+        let params = [("symbol", "SOL_USDC".to_string()), ("note", "a b&c".to_string())];
+        let url = client.url_with_query("/api/v1/depth", &params).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://api.test/api/v1/depth?symbol=SOL_USDC&note=a+b%26c"
+        );
+    }
+
+    #[test]
+    fn test_configuration_accessors() {
+        let client = BpxClient::init("https://api.test".to_string(), crate::test_utils::TEST_SECRET, None).unwrap();
+        assert_eq!(client.base_url(), "https://api.test");
+        assert_eq!(client.recv_window(), Duration::from_secs(5));
+        assert_eq!(
+            client.user_agent(),
+            concat!("bpx-api-client/", env!("CARGO_PKG_VERSION"))
+        );
+        #[cfg(feature = "ws")]
+        assert_eq!(client.ws_url(), BACKPACK_WS_URL);
+    }
+
+    #[test]
+    fn test_client_builds_with_enabled_tls_backend() {
+        assert!(BpxClient::init(BACKPACK_API_BASE_URL.to_string(), crate::test_utils::TEST_SECRET, None).is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_after_is_honored_on_429() {
+        let server = MockServer::start(vec![
+            MockResponse::new(429, "").with_header("Retry-After", "2"),
+            MockResponse::new(200, "[]"),
+        ])
+        .await;
+        let client = test_client_builder(&server)
+            .retry_policy(RetryPolicy {
+                max_retries: 1,
+                base_delay: Duration::from_millis(10),
+            })
+            .build()
+            .unwrap();
+
+        // Time is paused, so the wait elapses instantly but still shows on the tokio clock.
+        let start = tokio::time::Instant::now();
+        let orders = client.get_open_orders(None).await.unwrap();
+        assert!(orders.is_empty());
+        assert!(start.elapsed() >= Duration::from_secs(2));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_body_is_incomplete_and_retried() {
+        let body = r#"[{"symbol":"SOL_USDC"}]"#;
+        let server = MockServer::start(vec![
+            MockResponse::new(200, body).truncated(5),
+            MockResponse::new(200, "[]").truncated(1),
+            MockResponse::new(200, "[]"),
+        ])
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+        let err = client.get_open_orders(None).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::IncompleteResponse {
+                expected: 23,
+                received: 5
+            }
+        ));
+        assert!(err.is_retryable());
+
+        let client = test_client_builder(&server)
+            .retry_policy(RetryPolicy {
+                max_retries: 1,
+                base_delay: Duration::from_millis(10),
+            })
+            .build()
+            .unwrap();
+        assert!(client.get_open_orders(None).await.unwrap().is_empty());
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_buffered_response_keeps_url_and_ignores_bogus_length() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, "[]"),
+            MockResponse::new(200, "[]").with_content_length(u64::MAX / 2),
+        ])
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+        let url = format!("{}/api/v1/orders", server.url());
+
+        let res = client.get(&url).await.unwrap();
+        assert_eq!(res.url().as_str(), url);
+        // Announcing more bytes than could ever be allocated fails without reserving them.
+        let err = client.get(&url).await.unwrap_err();
+        assert!(matches!(err, Error::IncompleteResponse { received: 2, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_opens_and_recovers() {
+        let server = MockServer::start(vec![
+            MockResponse::new(503, "unavailable"),
+            MockResponse::new(502, "bad gateway"),
+            MockResponse::new(200, "[]"),
+            MockResponse::new(200, r#"{"status":"Ok"}"#),
+        ])
+        .await;
+        let client = test_client_builder(&server)
+            .circuit_breaker(CircuitBreakerConfig {
+                failure_threshold: 2,
+                cooldown: Duration::from_millis(200),
+            })
+            .build()
+            .unwrap();
+
+        assert!(client.get_status().await.is_err());
+        assert!(client.get_status().await.is_err());
+        // Open: clones share the breaker, and nothing reaches the server.
+        let err = client.clone().get_status().await.unwrap_err();
+        let Error::CircuitOpen { endpoint, retry_after } = err else {
+            panic!("expected an open circuit, got {err:?}");
+        };
+        assert_eq!(&*endpoint, "/api/v1/status");
+        assert!(retry_after <= Duration::from_millis(200));
+        assert_eq!(server.requests().len(), 2);
+        // Other endpoints still go through.
+        assert!(client.get_open_orders(None).await.is_ok());
+
+        // Half-open after the cooldown: the probe succeeds and closes the breaker.
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        let status = client.get_status().await.unwrap();
+        assert_eq!(status.status, bpx_api_types::system::StatusType::Ok);
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_header() {
+        let server = MockServer::start(vec![MockResponse::new(200, "[]"), MockResponse::new(200, "[]")]).await;
+
+        let client = test_client_builder(&server).build().unwrap();
+        client.get_open_orders(None).await.unwrap();
+        let client = test_client_builder(&server).user_agent("my-bot/1.0").build().unwrap();
+        client.get_open_orders(None).await.unwrap();
+
+        let requests = server.requests();
+        let default = format!("user-agent: {API_USER_AGENT}\r\n");
+        assert!(requests[0].to_lowercase().contains(&default));
+        assert!(requests[1].contains("my-bot/1.0\r\n"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_custom_transport_over_unix_socket() {
+        let path = std::env::temp_dir().join(format!("bpx-api-client-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = MockServer::start_unix(&path, vec![MockResponse::new(200, r#"{"status":"Ok"}"#)]).await;
+
+        let socket = path.clone();
+        let client = test_client_builder(&server)
+            .configure_http(move |http| http.unix_socket(socket))
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.get_status().await.unwrap().status,
+            bpx_api_types::system::StatusType::Ok
+        );
+        assert!(server.requests()[0].starts_with("GET /api/v1/status "));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_deadline_bounds_a_single_request() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, "[]").delayed(Duration::from_secs(5)),
+            MockResponse::new(200, "[]"),
+        ])
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+        let url = format!("{}/api/v1/trades?symbol=SOL_USDC", server.url());
+
+        let past = tokio::time::Instant::now() - Duration::from_millis(1);
+        let started = std::time::Instant::now();
+        let err = client.get_with_deadline(&url, past).await.unwrap_err();
+        assert!(matches!(err, Error::DeadlineExceeded));
+        assert!(started.elapsed() < Duration::from_millis(50));
+        assert!(server.requests().is_empty());
+
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(100);
+        let err = client.get_with_deadline(&url, deadline).await.unwrap_err();
+        assert!(matches!(err, Error::DeadlineExceeded));
+
+        // The abandoned request doesn't hold up the next one.
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+        assert!(
+            BpxClient::with_deadline(deadline, client.get_recent_trades("SOL_USDC", None))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_client_is_clone_send_sync() {
+        fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+        assert_shareable::<BpxClient>();
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_state_across_tasks() {
+        let server = MockServer::start(vec![MockResponse::new(200, TEST_MARKET_JSON)]).await;
+        let client = test_client_builder(&server).build().unwrap();
+        client.get_market_cached("SOL_USDC").await.unwrap();
+
+        let handles: Vec<_> = (0..32)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.get_market_cached("SOL_USDC").await })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().unwrap().symbol, "SOL_USDC");
+        }
+        assert_eq!(server.requests().len(), 1);
+
+        client.clone().clear_market_cache();
+        assert!(client.markets.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_with_new_credentials_signs_with_new_key() {
+        const NEW_SECRET: &str = "AgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgI=";
+        let server = MockServer::start(vec![
+            MockResponse::new(200, TEST_MARKET_JSON),
+            MockResponse::new(200, "[]"),
+            MockResponse::new(200, "[]"),
+        ])
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+        client.get_market_cached("SOL_USDC").await.unwrap();
+
+        let rotated = client.with_new_credentials(NEW_SECRET).unwrap();
+        assert_ne!(rotated.verifier, client.verifier);
+        assert_eq!(rotated.get_market_cached("SOL_USDC").await.unwrap().symbol, "SOL_USDC");
+
+        rotated.get_open_orders(None).await.unwrap();
+        client.get_open_orders(None).await.unwrap();
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        let api_key = |client: &BpxClient| format!("x-api-key: {}", STANDARD.encode(client.verifier));
+        assert!(requests[1].contains(&api_key(&rotated)));
+        assert!(requests[2].contains(&api_key(&client)));
+
+        let signed = |request: &str, header: &str| {
+            let prefix = format!("{header}: ");
+            let line = request.lines().find(|line| line.starts_with(&prefix)).unwrap();
+            line[prefix.len()..].to_string()
+        };
+        let signee = format!(
+            "instruction=orderQueryAll&timestamp={}&window={DEFAULT_WINDOW}",
+            signed(&requests[1], "x-timestamp")
+        );
+        let signature = STANDARD.decode(signed(&requests[1], "x-signature")).unwrap();
+        let signature = Signature::from_slice(&signature).unwrap();
+        assert!(rotated.verifier.verify_strict(signee.as_bytes(), &signature).is_ok());
+    }
+}
@@ -0,0 +1,287 @@
+//! A pool of clients spreading signed requests across several API keys.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::error::{Error, Result};
+use crate::BpxClient;
+
+/// The request budget of a single API key: up to `requests` requests per `interval`.
+///
+/// The budget is tracked as a token bucket, so a member can burst up to `requests` requests
+/// after being idle and is then refilled at a steady rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Number of requests allowed per interval, and the size of a burst.
+    pub requests: u32,
+    /// The interval over which `requests` are allowed.
+    pub interval: Duration,
+}
+
+impl RateLimit {
+    /// Fails with [`Error::InvalidRequest`] if the limit allows no requests at all.
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.requests == 0 || self.interval.is_zero() {
+            return Err(Error::InvalidRequest(
+                "rate limit must allow at least one request".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A pool of clients, each with its own API key and rate limit bucket.
+///
+/// Use [`BpxClientPool::acquire`] to take a request from a member's bucket and get a handle
+/// to that member, then call any route on it:
+///
+/// ```no_run
+/// # async fn example(a: bpx_api_client::BpxClient, b: bpx_api_client::BpxClient) -> bpx_api_client::Result<()> {
+/// use std::time::Duration;
+/// use bpx_api_client::{BpxClientPool, RateLimit};
+///
+/// let limit = RateLimit { requests: 10, interval: Duration::from_secs(1) };
+/// let pool = BpxClientPool::new([a, b], limit)?;
+/// let orders = pool.acquire().await.get_open_orders(Some("SOL_USDC")).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Members are picked round-robin: each call starts at the member after the one the previous
+/// call started at and takes the first member with capacity, so load is spread evenly while
+/// exhausted members are skipped. When every bucket is empty the call waits for the earliest
+/// refill; waiting callers are not served in any particular order. Latency-sensitive callers
+/// that would rather shed load than queue can use [`BpxClientPool::try_acquire`], which fails
+/// with [`Error::RateLimited`] instead of waiting.
+///
+/// Keys belonging to different subaccounts are not interchangeable. Use
+/// [`BpxClientPool::acquire_pinned`] to always go through the same member, for example to
+/// cancel an order placed with that member's key.
+///
+/// Cloning the pool is cheap and clones share the same buckets.
+#[derive(Debug, Clone)]
+pub struct BpxClientPool {
+    members: Arc<[Member]>,
+    next: Arc<AtomicUsize>,
+}
+
+#[derive(Debug)]
+struct Member {
+    client: BpxClient,
+    bucket: Mutex<TokenBucket>,
+}
+
+impl BpxClientPool {
+    /// Creates a pool from the given clients, giving each its own bucket with the given limit.
+    pub fn new(clients: impl IntoIterator<Item = BpxClient>, limit: RateLimit) -> Result<Self> {
+        limit.check()?;
+        let members: Arc<[Member]> = clients
+            .into_iter()
+            .map(|client| Member {
+                client,
+                bucket: Mutex::new(TokenBucket::new(limit)),
+            })
+            .collect();
+        if members.is_empty() {
+            return Err(Error::InvalidRequest("client pool requires at least one client".into()));
+        }
+        Ok(Self {
+            members,
+            next: Default::default(),
+        })
+    }
+
+    /// Returns the clients in the pool, in the order they were given.
+    pub fn clients(&self) -> impl Iterator<Item = &BpxClient> {
+        self.members.iter().map(|member| &member.client)
+    }
+
+    /// Takes one request from the next member with capacity and returns that member's client,
+    /// waiting for a bucket to refill if all are empty.
+    pub async fn acquire(&self) -> BpxClient {
+        loop {
+            match self.take() {
+                Ok(client) => return client,
+                Err(retry_in) => tokio::time::sleep(retry_in).await,
+            }
+        }
+    }
+
+    /// Takes one request from the next member with capacity and returns that member's client,
+    /// like [`BpxClientPool::acquire`], but fails with [`Error::RateLimited`] if all buckets
+    /// are empty instead of waiting. The error tells how long until the earliest refill.
+    pub fn try_acquire(&self) -> Result<BpxClient> {
+        self.take().map_err(|retry_after| Error::RateLimited { retry_after })
+    }
+
+    /// Takes one request from the member at `index` and returns its client, waiting for its
+    /// bucket to refill if it is empty.
+    pub async fn acquire_pinned(&self, index: usize) -> Result<BpxClient> {
+        let member = self.member(index)?;
+        while let Err(retry_in) = member.try_acquire() {
+            tokio::time::sleep(retry_in).await;
+        }
+        Ok(member.client.clone())
+    }
+
+    /// Takes one request from the member at `index` and returns its client, failing with
+    /// [`Error::RateLimited`] if its bucket is empty instead of waiting.
+    pub fn try_acquire_pinned(&self, index: usize) -> Result<BpxClient> {
+        let member = self.member(index)?;
+        member
+            .try_acquire()
+            .map_err(|retry_after| Error::RateLimited { retry_after })?;
+        Ok(member.client.clone())
+    }
+
+    /// Takes one request from the next member with capacity, or returns how long until the
+    /// earliest refill.
+    fn take(&self) -> std::result::Result<BpxClient, Duration> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut wait = Duration::MAX;
+        for offset in 0..self.members.len() {
+            let member = &self.members[(start + offset) % self.members.len()];
+            match member.try_acquire() {
+                Ok(()) => return Ok(member.client.clone()),
+                Err(retry_in) => wait = wait.min(retry_in),
+            }
+        }
+        Err(wait)
+    }
+
+    fn member(&self, index: usize) -> Result<&Member> {
+        self.members
+            .get(index)
+            .ok_or_else(|| Error::InvalidRequest(format!("no client at index {index}").into()))
+    }
+}
+
+impl Member {
+    fn try_acquire(&self) -> std::result::Result<(), Duration> {
+        self.bucket
+            .lock()
+            .expect("rate limit bucket poisoned")
+            .try_take(Instant::now())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    /// Tokens added per second.
+    rate: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        let capacity = f64::from(limit.requests);
+        Self {
+            capacity,
+            tokens: capacity,
+            rate: capacity / limit.interval.as_secs_f64(),
+            updated: Instant::now(),
+        }
+    }
+
+    /// Takes a token, or returns how long until one is available.
+    pub(crate) fn try_take(&mut self, now: Instant) -> std::result::Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::TEST_SECRET;
+
+    const OTHER_SECRET: &str = "AgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgI=";
+
+    fn test_pool(limit: RateLimit) -> BpxClientPool {
+        let clients = [TEST_SECRET, OTHER_SECRET].map(|secret| BpxClient::builder().secret(secret).build().unwrap());
+        BpxClientPool::new(clients, limit).unwrap()
+    }
+
+    #[test]
+    fn test_token_bucket_refills() {
+        let limit = RateLimit {
+            requests: 2,
+            interval: Duration::from_secs(1),
+        };
+        let mut bucket = TokenBucket::new(limit);
+        let now = bucket.updated;
+        assert!(bucket.try_take(now).is_ok());
+        assert!(bucket.try_take(now).is_ok());
+        assert_eq!(bucket.try_take(now), Err(Duration::from_millis(500)));
+        assert!(bucket.try_take(now + Duration::from_millis(500)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_spreads_across_members() {
+        let pool = test_pool(RateLimit {
+            requests: 1,
+            interval: Duration::from_millis(100),
+        });
+        let first = pool.acquire().await;
+        let second = pool.acquire().await;
+        assert_ne!(first.verifier, second.verifier);
+
+        let start = Instant::now();
+        pool.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_pinned() {
+        let pool = test_pool(RateLimit {
+            requests: 5,
+            interval: Duration::from_secs(1),
+        });
+        let expected = pool.clients().nth(1).unwrap().verifier;
+        for _ in 0..3 {
+            assert_eq!(pool.acquire_pinned(1).await.unwrap().verifier, expected);
+        }
+        assert!(matches!(pool.acquire_pinned(2).await, Err(Error::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn test_try_acquire_fails_fast_when_exhausted() {
+        let pool = test_pool(RateLimit {
+            requests: 2,
+            interval: Duration::from_secs(1),
+        });
+        for _ in 0..4 {
+            pool.try_acquire().unwrap();
+        }
+
+        let start = Instant::now();
+        let Err(Error::RateLimited { retry_after }) = pool.try_acquire() else {
+            panic!("expected the pool to be rate limited");
+        };
+        assert!(start.elapsed() < Duration::from_millis(50));
+        // A token refills every 500ms.
+        assert!(retry_after > Duration::from_millis(400) && retry_after <= Duration::from_millis(500));
+        assert!(matches!(pool.try_acquire_pinned(0), Err(Error::RateLimited { .. })));
+        assert!(matches!(pool.try_acquire_pinned(2), Err(Error::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn test_empty_pool() {
+        let limit = RateLimit {
+            requests: 1,
+            interval: Duration::from_secs(1),
+        };
+        assert!(matches!(BpxClientPool::new([], limit), Err(Error::InvalidRequest(_))));
+    }
+}
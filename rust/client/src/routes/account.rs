@@ -1,10 +1,12 @@
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::BpxClient;
 use bpx_api_types::account::{
     AccountMaxBorrow, AccountMaxOrder, AccountMaxWithdrawal, AccountSettings, ConvertDustPayload, UpdateAccountPayload,
 };
+use bpx_api_types::markets::MarketType;
 use bpx_api_types::order::Side;
 use rust_decimal::Decimal;
+use tokio::task::JoinSet;
 
 #[doc(hidden)]
 pub const API_ACCOUNT: &str = "/api/v1/account";
@@ -27,13 +29,18 @@ impl BpxClient {
 
     /// Fetches the account's maximum borrow amount for a given symbol.
     pub async fn get_account_max_borrow(&self, symbol: &str) -> Result<AccountMaxBorrow> {
-        let url = format!("{}{}?symbol={}", self.base_url, API_ACCOUNT_MAX_BORROW, symbol);
-        let res = self.get(url).await?;
+        let res = self
+            .get_with_query(API_ACCOUNT_MAX_BORROW, &[("symbol", symbol.to_string())])
+            .await?;
         res.json().await.map_err(Into::into)
     }
 
     /// Fetches the maximum quantity an account can trade for a given symbol based on the
     /// account's balances, existing exposure and margin requirements.
+    ///
+    /// Use [`AccountMaxOrder::can_afford`] or [`AccountMaxOrder::capped_to_max`] to size an
+    /// order against the result before sending it.
+    #[allow(clippy::too_many_arguments)]
     pub async fn get_account_max_order_quantity(
         &self,
         symbol: &str,
@@ -44,27 +51,24 @@ impl BpxClient {
         auto_borrow_repay: Option<bool>,
         auto_lend_redeem: Option<bool>,
     ) -> Result<AccountMaxOrder> {
-        let mut url = format!(
-            "{}{}?symbol={}&side={}",
-            self.base_url, API_ACCOUNT_MAX_ORDER, symbol, side
-        );
+        let mut params = vec![("symbol", symbol.to_string()), ("side", side.to_string())];
         if let Some(price) = price {
-            url.push_str(&format!("&price={price}"));
+            params.push(("price", price.to_string()));
         }
         if let Some(reduce_only) = reduce_only {
-            url.push_str(&format!("&reduceOnly={reduce_only}"));
+            params.push(("reduceOnly", reduce_only.to_string()));
         }
         if let Some(auto_borrow) = auto_borrow {
-            url.push_str(&format!("&autoBorrow={auto_borrow}"));
+            params.push(("autoBorrow", auto_borrow.to_string()));
         }
         if let Some(auto_borrow_repay) = auto_borrow_repay {
-            url.push_str(&format!("&autoBorrowRepay={auto_borrow_repay}"));
+            params.push(("autoBorrowRepay", auto_borrow_repay.to_string()));
         }
         if let Some(auto_lend_redeem) = auto_lend_redeem {
-            url.push_str(&format!("&autoLendRedeem={auto_lend_redeem}"));
+            params.push(("autoLendRedeem", auto_lend_redeem.to_string()));
         }
 
-        let res = self.get(url).await?;
+        let res = self.get_with_query(API_ACCOUNT_MAX_ORDER, &params).await?;
         res.json().await.map_err(Into::into)
     }
 
@@ -75,15 +79,15 @@ impl BpxClient {
         auto_borrow: Option<bool>,
         auto_lend_redeem: Option<bool>,
     ) -> Result<AccountMaxWithdrawal> {
-        let mut url = format!("{}{}?symbol={}", self.base_url, API_ACCOUNT_MAX_WITHDRAWAL, symbol);
+        let mut params = vec![("symbol", symbol.to_string())];
         if let Some(auto_borrow) = auto_borrow {
-            url.push_str(&format!("&autoBorrow={auto_borrow}"));
+            params.push(("autoBorrow", auto_borrow.to_string()));
         }
         if let Some(auto_lend_redeem) = auto_lend_redeem {
-            url.push_str(&format!("&autoLendRedeem={auto_lend_redeem}"));
+            params.push(("autoLendRedeem", auto_lend_redeem.to_string()));
         }
 
-        let res = self.get(url).await?;
+        let res = self.get_with_query(API_ACCOUNT_MAX_WITHDRAWAL, &params).await?;
         res.json().await.map_err(Into::into)
     }
 
@@ -95,12 +99,140 @@ impl BpxClient {
         Ok(())
     }
 
+    /// Fetches the account-wide leverage limit applied to futures positions.
+    pub async fn get_leverage(&self) -> Result<Decimal> {
+        Ok(self.get_account().await?.leverage_limit)
+    }
+
+    /// Sets the account-wide leverage limit applied to futures positions.
+    ///
+    /// The limit is not scoped to a symbol, use [`bpx_api_types::markets::Market::validate_leverage`]
+    /// to check it against a given market's filters.
+    pub async fn set_leverage(&self, leverage: Decimal) -> Result<()> {
+        if leverage <= Decimal::ZERO {
+            return Err(Error::InvalidRequest(
+                format!("leverage must be positive, got {leverage}").into(),
+            ));
+        }
+        self.update_account(UpdateAccountPayload {
+            leverage_limit: Some(leverage),
+            ..Default::default()
+        })
+        .await
+    }
+
     /// Converts a dust balance to USDC. The balance (including lend) must be less
-    /// than the minimum quantity tradable on the spot order book.
+    /// than the minimum quantity tradable on the spot order book, which can be checked
+    /// beforehand with [`BpxClient::can_convert_dust`].
     pub async fn convert_dust_balance(&self, payload: ConvertDustPayload) -> Result<()> {
         let url = format!("{}{}", self.base_url, API_ACCOUNT_CONVERT_DUST);
         self.post(url, payload).await?;
 
         Ok(())
     }
+
+    /// Returns whether a balance of `quantity` of the asset `symbol` is dust, that is below the
+    /// minimum quantity of its `<ASSET>_USDC` spot market, and so can be converted with
+    /// [`BpxClient::convert_dust_balance`]. The market is served from the client's cache.
+    pub async fn can_convert_dust(&self, symbol: &str, quantity: Decimal) -> Result<bool> {
+        let market = self.get_market_cached(&format!("{symbol}_USDC")).await?;
+        Ok(quantity < market.filters.quantity.min_quantity)
+    }
+
+    /// Converts every dust balance to USDC.
+    ///
+    /// A balance is dust when its total is below the minimum quantity of the asset's
+    /// `<ASSET>_USDC` spot market. Assets without such a market are left untouched. The
+    /// conversions are sent concurrently and the outcome of each one is returned alongside
+    /// its symbol, so a single failure does not hide the others.
+    pub async fn convert_all_dust(&self) -> Result<Vec<(String, Result<()>)>> {
+        let balances = self.get_balances().await?;
+        let markets = self.get_markets().await?;
+
+        let mut conversions = JoinSet::new();
+        for (symbol, balance) in balances {
+            let total = balance.total();
+            let is_dust = markets.iter().any(|m| {
+                m.market_type == MarketType::Spot
+                    && m.base_symbol == symbol
+                    && m.quote_symbol == "USDC"
+                    && total < m.filters.quantity.min_quantity
+            });
+            if total.is_zero() || !is_dust {
+                continue;
+            }
+            let client = self.clone();
+            conversions.spawn(async move {
+                let payload = ConvertDustPayload {
+                    symbol: Some(symbol.clone()),
+                };
+                (symbol, client.convert_dust_balance(payload).await)
+            });
+        }
+
+        let mut results = Vec::with_capacity(conversions.len());
+        while let Some(result) = conversions.join_next().await {
+            results.push(result.expect("dust conversion task panicked"));
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rust_decimal_macros::dec;
+
+    use crate::test_utils::{test_client_builder, MockResponse, MockServer, TEST_MARKET_JSON};
+
+    #[tokio::test]
+    async fn test_set_leverage_patches_account() {
+        let server = MockServer::start(vec![MockResponse::new(200, "")]).await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        client.set_leverage(dec!(5)).await.unwrap();
+        assert!(client.set_leverage(dec!(0)).await.is_err());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("PATCH /api/v1/account"));
+        assert!(requests[0].ends_with(r#"{"leverageLimit":"5"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_can_convert_dust() {
+        let server = MockServer::start(vec![MockResponse::new(200, TEST_MARKET_JSON)]).await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        assert!(client.can_convert_dust("SOL", dec!(0.009)).await.unwrap());
+        assert!(!client.can_convert_dust("SOL", dec!(0.01)).await.unwrap());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("GET /api/v1/market?symbol=SOL_USDC"));
+    }
+
+    #[tokio::test]
+    async fn test_convert_all_dust_only_converts_dust() {
+        let balances = r#"{
+            "SOL": { "available": "0.004", "locked": "0", "staked": "0" },
+            "USDC": { "available": "0.001", "locked": "0", "staked": "0" },
+            "BTC": { "available": "0.000001", "locked": "0", "staked": "0" }
+        }"#;
+        let server = MockServer::start(vec![
+            MockResponse::new(200, balances),
+            MockResponse::new(200, format!("[{TEST_MARKET_JSON}]")),
+            MockResponse::new(200, ""),
+        ])
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let results = client.convert_all_dust().await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "SOL");
+        assert!(results[0].1.is_ok());
+
+        let requests = server.requests();
+        assert!(requests[2].starts_with("POST /api/v1/account/convertDust"));
+        assert!(requests[2].ends_with(r#"{"symbol":"SOL"}"#));
+    }
 }
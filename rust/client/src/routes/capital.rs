@@ -1,11 +1,16 @@
-use crate::error::Result;
+use crate::error::{Error, Result};
 use std::collections::HashMap;
 
 use bpx_api_types::{
-    capital::{Balance, Collateral, Deposit, DepositAddress, RequestWithdrawalPayload, Withdrawal},
+    capital::{
+        Balance, Collateral, Deposit, DepositAddress, RequestWithdrawalPayload, TransferHistoryParams, TransferStatus,
+        Withdrawal,
+    },
+    history::MAX_HISTORY_LIMIT,
     Blockchain,
 };
 
+use crate::capabilities::{unsupported_if_missing, Capability};
 use crate::BpxClient;
 
 #[doc(hidden)]
@@ -29,35 +34,63 @@ impl BpxClient {
 
     /// Retrieves a list of deposits with optional pagination.
     pub async fn get_deposits(&self, limit: Option<i64>, offset: Option<i64>) -> Result<Vec<Deposit>> {
-        let mut url = format!("{}{}", self.base_url, API_DEPOSITS);
-        for (k, v) in [("limit", limit), ("offset", offset)] {
-            if let Some(v) = v {
-                url.push_str(&format!("&{k}={v}"));
-            }
-        }
-        let res = self.get(url).await?;
+        let params: Vec<_> = [("limit", limit), ("offset", offset)]
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k, v.to_string())))
+            .collect();
+        let res = self.get_with_query(API_DEPOSITS, &params).await?;
         res.json().await.map_err(Into::into)
     }
 
+    /// Retrieves deposits matching the given search params.
+    pub async fn get_deposit_history(&self, params: TransferHistoryParams) -> Result<Vec<Deposit>> {
+        let res = self.get_with_query(API_DEPOSITS, &transfer_query(&params)?).await?;
+        let mut deposits: Vec<Deposit> = res.json().await?;
+        if let Some(status) = params.status {
+            deposits.retain(|deposit| status.matches_deposit(deposit.status));
+        }
+        Ok(deposits)
+    }
+
+    /// Retrieves the pending deposits among the latest [`MAX_HISTORY_LIMIT`].
+    pub async fn pending_deposits(&self) -> Result<Vec<Deposit>> {
+        self.get_deposit_history(pending_transfers()).await
+    }
+
     /// Fetches the deposit address for a specified blockchain.
     pub async fn get_deposit_address(&self, blockchain: Blockchain) -> Result<DepositAddress> {
-        let url = format!("{}{}?blockchain={}", self.base_url, API_DEPOSIT_ADDRESS, blockchain);
-        let res = self.get(url).await?;
+        let res = self
+            .get_with_query(API_DEPOSIT_ADDRESS, &[("blockchain", blockchain.to_string())])
+            .await?;
         res.json().await.map_err(Into::into)
     }
 
     /// Retrieves a list of withdrawals with optional pagination.
     pub async fn get_withdrawals(&self, limit: Option<i64>, offset: Option<i64>) -> Result<Vec<Withdrawal>> {
-        let mut url = format!("{}{}", self.base_url, API_WITHDRAWALS);
-        for (k, v) in [("limit", limit), ("offset", offset)] {
-            if let Some(v) = v {
-                url.push_str(&format!("{k}={v}&"));
-            }
-        }
-        let res = self.get(url).await?;
+        let params: Vec<_> = [("limit", limit), ("offset", offset)]
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k, v.to_string())))
+            .collect();
+        let res = self.get_with_query(API_WITHDRAWALS, &params).await?;
         res.json().await.map_err(Into::into)
     }
 
+    /// Retrieves withdrawals matching the given search params.
+    pub async fn get_withdrawal_history(&self, params: TransferHistoryParams) -> Result<Vec<Withdrawal>> {
+        let res = self.get_with_query(API_WITHDRAWALS, &transfer_query(&params)?).await?;
+        let mut withdrawals: Vec<Withdrawal> = res.json().await?;
+        if let Some(status) = params.status {
+            withdrawals.retain(|withdrawal| status.matches_withdrawal(withdrawal.status));
+        }
+        Ok(withdrawals)
+    }
+
+    /// Retrieves the pending withdrawals, including those being verified, among the latest
+    /// [`MAX_HISTORY_LIMIT`].
+    pub async fn pending_withdrawals(&self) -> Result<Vec<Withdrawal>> {
+        self.get_withdrawal_history(pending_transfers()).await
+    }
+
     /// Submits a withdrawal request for the specified payload.
     pub async fn request_withdrawal(&self, payload: RequestWithdrawalPayload) -> Result<Withdrawal> {
         let endpoint = format!("{}{}", self.base_url, API_WITHDRAWALS);
@@ -67,8 +100,88 @@ impl BpxClient {
 
     /// Fetches the subaccount's collateral information.
     pub async fn get_collateral(&self) -> Result<Collateral> {
+        self.require(Capability::Collateral)?;
         let url = format!("{}{}", self.base_url, API_COLLATERAL);
-        let res = self.get(url).await?;
+        let res = self
+            .get(url)
+            .await
+            .map_err(|err| unsupported_if_missing(err, Capability::Collateral))?;
         res.json().await.map_err(Into::into)
     }
 }
+
+/// Returns the query of the transfer history params, after validating them.
+fn transfer_query(params: &TransferHistoryParams) -> Result<Vec<(&'static str, String)>> {
+    params
+        .validate()
+        .map_err(|e| Error::InvalidRequest(e.to_string().into()))?;
+    Ok([
+        ("from", params.from.map(|from| from.to_string())),
+        ("to", params.to.map(|to| to.to_string())),
+        ("limit", params.limit.map(|limit| limit.to_string())),
+        ("offset", params.offset.map(|offset| offset.to_string())),
+    ]
+    .into_iter()
+    .filter_map(|(k, v)| v.map(|v| (k, v)))
+    .collect())
+}
+
+fn pending_transfers() -> TransferHistoryParams {
+    TransferHistoryParams {
+        limit: Some(MAX_HISTORY_LIMIT),
+        status: Some(TransferStatus::Pending),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{test_client_builder, MockResponse, MockServer};
+
+    #[tokio::test]
+    async fn test_withdrawal_history_query_and_status_filter() {
+        let withdrawal = |id: u32, status: &str| {
+            format!(
+                r#"{{"id":{id},"blockchain":"Solana","quantity":"1","fee":"0.01","symbol":"SOL","status":"{status}","toAddress":"addr","createdAt":"2025-01-01T00:00:00"}}"#
+            )
+        };
+        let body = format!(
+            "[{},{},{},{}]",
+            withdrawal(1, "confirmed"),
+            withdrawal(2, "pending"),
+            withdrawal(3, "verifying"),
+            withdrawal(4, "void")
+        );
+        let server = MockServer::start(vec![MockResponse::new(200, &body), MockResponse::new(200, &body)]).await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let params = TransferHistoryParams {
+            from: Some(1_700_000_000_000),
+            to: Some(1_700_086_400_000),
+            limit: Some(50),
+            offset: Some(100),
+            status: Some(TransferStatus::Failed),
+        };
+        let failed = client.get_withdrawal_history(params).await.unwrap();
+        assert_eq!(failed.iter().map(|w| w.id).collect::<Vec<_>>(), [4]);
+        let pending = client.pending_withdrawals().await.unwrap();
+        assert_eq!(pending.iter().map(|w| w.id).collect::<Vec<_>>(), [2, 3]);
+
+        // The status isn't sent.
+        let requests = server.requests();
+        assert!(requests[0]
+            .starts_with("GET /wapi/v1/capital/withdrawals?from=1700000000000&to=1700086400000&limit=50&offset=100 "));
+        assert!(requests[1].starts_with("GET /wapi/v1/capital/withdrawals?limit=1000 "));
+
+        let invalid = TransferHistoryParams {
+            from: Some(2),
+            to: Some(1),
+            ..Default::default()
+        };
+        assert!(matches!(
+            client.get_deposit_history(invalid).await,
+            Err(Error::InvalidRequest(_))
+        ));
+    }
+}
@@ -1,5 +1,8 @@
-use crate::{BpxClient, Result};
+use crate::{BpxClient, Error, Result};
+use std::collections::HashMap;
+
 use bpx_api_types::history::{FillHistorySearchParams, HistoricFill, HistoricOrder, OrderHistorySearchParams};
+use bpx_api_types::order::{Order, OrderLike};
 
 #[doc(hidden)]
 pub const API_FILL_HISTORY: &str = "/wapi/v1/history/fills";
@@ -8,82 +11,148 @@ pub const API_ORDER_HISTORY: &str = "/wapi/v1/history/orders";
 impl BpxClient {
     /// Retrieves historical fills, with optional filtering for a specific order or symbol.
     pub async fn get_fill_history(&self, search_params: FillHistorySearchParams) -> Result<Vec<HistoricFill>> {
-        let mut query_vec = Vec::new();
-
+        search_params
+            .validate()
+            .map_err(|e| Error::InvalidRequest(e.to_string().into()))?;
+        let mut params = Vec::new();
         if let Some(order_id) = search_params.order_id {
-            query_vec.push(format!("order_id={order_id}"))
+            params.push(("orderId", order_id));
         }
         if let Some(strategy_id) = search_params.strategy_id {
-            query_vec.push(format!("strategy_id={strategy_id}"))
+            params.push(("strategyId", strategy_id));
         }
         if let Some(from) = search_params.from {
-            query_vec.push(format!("from={from}"))
+            params.push(("from", from.to_string()));
         }
         if let Some(to) = search_params.to {
-            query_vec.push(format!("to={to}"))
+            params.push(("to", to.to_string()));
         }
         if let Some(symbol) = search_params.symbol {
-            query_vec.push(format!("symbol={symbol}"))
+            params.push(("symbol", symbol));
         }
         if let Some(limit) = search_params.limit {
-            query_vec.push(format!("limit={limit}"))
+            params.push(("limit", limit.to_string()));
         }
         if let Some(offset) = search_params.offset {
-            query_vec.push(format!("offset={offset}"))
+            params.push(("offset", offset.to_string()));
         }
         if let Some(fill_type) = search_params.fill_type {
-            query_vec.push(format!("fill_type={fill_type}"))
+            params.push(("fillType", fill_type.to_string()));
         }
         if let Some(market_type) = search_params.market_type {
-            query_vec.push(format!("market_type={market_type}"))
+            params.push(("marketType", market_type.to_string()));
         }
         if let Some(sort_direction) = search_params.sort_direction {
-            query_vec.push(format!("sort_direction={sort_direction}"))
+            params.push(("sortDirection", sort_direction.to_string()));
         }
-        let query_string = if query_vec.is_empty() {
-            "".to_string()
-        } else {
-            format!("?{}", query_vec.join("&"))
-        };
-        let url = format!("{}{}{}", self.base_url, API_FILL_HISTORY, query_string);
-        let res = self.get(url).await?;
+        let res = self.get_with_query(API_FILL_HISTORY, &params).await?;
         res.json().await.map_err(Into::into)
     }
 
     /// Retrieves the order history for the user. This includes orders that have been filled and
     /// are no longer on the book. It may include orders that are on the book, but the `/orders`
-    /// endpoint contains more up to date data.
+    /// endpoint contains more up to date data, see [`BpxClient::enrich_with_live`].
     pub async fn get_order_history(&self, search_params: OrderHistorySearchParams) -> Result<Vec<HistoricOrder>> {
-        let mut query_vec = Vec::new();
-
+        search_params
+            .validate()
+            .map_err(|e| Error::InvalidRequest(e.to_string().into()))?;
+        let mut params = Vec::new();
         if let Some(order_id) = search_params.order_id {
-            query_vec.push(format!("order_id={order_id}"))
+            params.push(("orderId", order_id));
         }
         if let Some(strategy_id) = search_params.strategy_id {
-            query_vec.push(format!("strategy_id={strategy_id}"))
+            params.push(("strategyId", strategy_id));
         }
         if let Some(symbol) = search_params.symbol {
-            query_vec.push(format!("symbol={symbol}"))
+            params.push(("symbol", symbol));
         }
         if let Some(limit) = search_params.limit {
-            query_vec.push(format!("limit={limit}"))
+            params.push(("limit", limit.to_string()));
         }
         if let Some(offset) = search_params.offset {
-            query_vec.push(format!("offset={offset}"))
+            params.push(("offset", offset.to_string()));
         }
         if let Some(market_type) = search_params.market_type {
-            query_vec.push(format!("market_type={market_type}"))
+            params.push(("marketType", market_type.to_string()));
         }
         if let Some(sort_direction) = search_params.sort_direction {
-            query_vec.push(format!("sort_direction={sort_direction}"))
+            params.push(("sortDirection", sort_direction.to_string()));
         }
-        let query_string = if query_vec.is_empty() {
-            "".to_string()
-        } else {
-            format!("?{}", query_vec.join("&"))
-        };
-        let url = format!("{}{}{}", self.base_url, API_ORDER_HISTORY, query_string);
-        let res = self.get(url).await?;
+        let res = self.get_with_query(API_ORDER_HISTORY, &params).await?;
         res.json().await.map_err(Into::into)
     }
+
+    /// Updates historic orders that are still open with their live status and executed
+    /// quantities from [`BpxClient::get_open_orders`], which is fetched once.
+    pub async fn enrich_with_live(&self, mut historic: Vec<HistoricOrder>) -> Result<Vec<HistoricOrder>> {
+        let open_orders = self.get_open_orders(None).await?;
+        let live: HashMap<&str, &Order> = open_orders.iter().map(|order| (order.id(), order)).collect();
+        for order in &mut historic {
+            let Some(live) = live.get(order.id.as_str()) else {
+                continue;
+            };
+            order.status = live.status();
+            (order.executed_quantity, order.executed_quote_quantity) = match live {
+                Order::Market(o) => (Some(o.executed_quantity), Some(o.executed_quote_quantity)),
+                Order::Limit(o) => (Some(o.executed_quantity), Some(o.executed_quote_quantity)),
+            };
+        }
+        Ok(historic)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bpx_api_types::history::{FillHistorySearchParams, HistoricOrder};
+    use bpx_api_types::order::OrderStatus;
+    use bpx_api_types::SortDirection;
+    use rust_decimal_macros::dec;
+
+    use crate::test_utils::{test_client_builder, MockResponse, MockServer};
+
+    #[tokio::test]
+    async fn test_fill_history_query_uses_camel_case_keys() {
+        let server = MockServer::start(vec![MockResponse::new(200, "[]")]).await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let params = FillHistorySearchParams::builder()
+            .order_id("123")
+            .symbol("SOL_USDC")
+            .sort_direction(SortDirection::Asc)
+            .build()
+            .unwrap();
+        client.get_fill_history(params).await.unwrap();
+
+        let request = &server.requests()[0];
+        assert!(request.starts_with("GET /wapi/v1/history/fills?orderId=123&symbol=SOL_USDC&sortDirection=Asc "));
+    }
+
+    #[tokio::test]
+    async fn test_enrich_with_live_overrides_open_orders() {
+        let live = r#"[{"orderType":"Limit","id":"1","clientId":null,"symbol":"SOL_USDC","side":"Bid","quantity":"2",
+            "executedQuantity":"1.5","executedQuoteQuantity":"225","price":"150","timeInForce":"GTC",
+            "selfTradePrevention":"RejectTaker","postOnly":false,"status":"PartiallyFilled","createdAt":0}]"#;
+        let server = MockServer::start(vec![MockResponse::new(200, live)]).await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let historic = |id: &str| -> HistoricOrder {
+            serde_json::from_str(&format!(
+                r#"{{"id":"{id}","createdAt":"2025-01-01T00:00:00","executedQuantity":"0","orderType":"Limit",
+                "price":"150","quantity":"2","selfTradePrevention":"RejectTaker","status":"New","side":"Bid",
+                "symbol":"SOL_USDC"}}"#
+            ))
+            .unwrap()
+        };
+        let orders = client
+            .enrich_with_live(vec![historic("1"), historic("2")])
+            .await
+            .unwrap();
+
+        assert_eq!(orders[0].status, OrderStatus::PartiallyFilled);
+        assert_eq!(orders[0].executed_quantity, Some(dec!(1.5)));
+        assert_eq!(orders[0].executed_quote_quantity, Some(dec!(225)));
+        assert_eq!(orders[1].status, OrderStatus::New);
+        assert_eq!(orders[1].executed_quantity, Some(dec!(0)));
+        assert_eq!(server.requests().len(), 1);
+    }
 }
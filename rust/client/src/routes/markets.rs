@@ -1,9 +1,17 @@
+use std::collections::HashMap;
+
 use bpx_api_types::markets::{
-    Asset, FundingRate, Kline, KlineInterval, KlinePriceType, MarkPrice, Market, OrderBookDepth, Ticker,
+    Asset, FilterSnapshot, FundingRate, FundingSnapshot, Kline, KlineInterval, KlinePriceType, MarkPrice, Market,
+    MarketType, OrderBookDepth, Ticker,
 };
+use bpx_api_types::order::Side;
+use bpx_api_types::trade::Trade;
+use chrono::{DateTime, Utc};
+use reqwest::header::DATE;
+use rust_decimal::Decimal;
 
-use crate::error::Result;
-use crate::BpxClient;
+use crate::error::{Error, Result};
+use crate::{now_millis, BpxClient};
 
 const API_ASSETS: &str = "/api/v1/assets";
 const API_MARKET: &str = "/api/v1/market";
@@ -15,6 +23,20 @@ const API_KLINES: &str = "/api/v1/klines";
 const API_FUNDING: &str = "/api/v1/fundingRates";
 const API_MARK_PRICES: &str = "/api/v1/markPrices";
 
+/// Maximum number of candles returned by [`BpxClient::get_k_lines_limited`].
+pub const MAX_KLINES_LIMIT: u16 = 1000;
+
+/// The state of a market a strategy needs to start trading it, see
+/// [`BpxClient::warm_up_symbol`].
+#[derive(Debug)]
+pub struct SymbolContext {
+    pub market: Market,
+    pub depth: OrderBookDepth,
+    pub recent_trades: Vec<Trade>,
+    /// The mark price of a futures market, `None` for other markets.
+    pub mark_price: Option<MarkPrice>,
+}
+
 impl BpxClient {
     /// Fetches available assets and their associated tokens.
     pub async fn get_assets(&self) -> Result<Vec<Asset>> {
@@ -23,13 +45,57 @@ impl BpxClient {
         res.json().await.map_err(Into::into)
     }
 
+    /// Fetches the assets that can be deposited on at least one blockchain.
+    pub async fn get_depositable_assets(&self) -> Result<Vec<Asset>> {
+        let mut assets = self.get_assets().await?;
+        assets.retain(|asset| asset.depositable_chains().next().is_some());
+        Ok(assets)
+    }
+
+    /// Fetches the assets that can be withdrawn to at least one blockchain.
+    pub async fn get_withdrawable_assets(&self) -> Result<Vec<Asset>> {
+        let mut assets = self.get_assets().await?;
+        assets.retain(|asset| asset.withdrawable_chains().next().is_some());
+        Ok(assets)
+    }
+
     /// Retrieves the market for a given symbol.
     pub async fn get_market(&self, symbol: &str) -> Result<Market> {
-        let url = format!("{}{}?symbol={}", self.base_url, API_MARKET, symbol);
-        let res = self.get(url).await?;
+        let res = self
+            .get_with_query(API_MARKET, &[("symbol", symbol.to_string())])
+            .await?;
         res.json().await.map_err(Into::into)
     }
 
+    /// Retrieves the market for a given symbol, serving it from the client's cache after the
+    /// first fetch.
+    pub async fn get_market_cached(&self, symbol: &str) -> Result<Market> {
+        if let Some(market) = self.markets.read().expect("market cache poisoned").get(symbol) {
+            return Ok(market.clone());
+        }
+        let market = self.get_market(symbol).await?;
+        self.markets
+            .write()
+            .expect("market cache poisoned")
+            .insert(symbol.to_string(), market.clone());
+        Ok(market)
+    }
+
+    /// Returns a snapshot of the market's price and quantity filters, served from the market
+    /// cache like [`BpxClient::get_market_cached`].
+    ///
+    /// Take the snapshot once and validate or round orders with it on the hot path, which
+    /// doesn't lock or allocate. It isn't refreshed: call [`BpxClient::clear_market_cache`]
+    /// and take a new one when the market's filters change.
+    pub async fn market_filters(&self, symbol: &str) -> Result<FilterSnapshot> {
+        Ok(self.get_market_cached(symbol).await?.filter_snapshot())
+    }
+
+    /// Drops all cached markets, so the next lookup fetches fresh filters.
+    pub fn clear_market_cache(&self) {
+        self.markets.write().expect("market cache poisoned").clear();
+    }
+
     /// Retrieves a list of available markets.
     pub async fn get_markets(&self) -> Result<Vec<Market>> {
         let url = format!("{}{}", self.base_url, API_MARKETS);
@@ -37,6 +103,38 @@ impl BpxClient {
         res.json().await.map_err(Into::into)
     }
 
+    /// Retrieves the available markets keyed by symbol.
+    ///
+    /// The markets also replace the client's market cache, so later
+    /// [`BpxClient::get_market_cached`] lookups are served without a request.
+    ///
+    /// ```no_run
+    /// # async fn example(client: bpx_api_client::BpxClient) -> bpx_api_client::Result<()> {
+    /// let markets = client.get_markets_map().await?;
+    /// if let Some(market) = markets.get("SOL_USDC") {
+    ///     println!("tick size {}, step size {}", market.tick_size(), market.step_size());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_markets_map(&self) -> Result<HashMap<String, Market>> {
+        let markets: HashMap<String, Market> = self
+            .get_markets()
+            .await?
+            .into_iter()
+            .map(|market| (market.symbol.clone(), market))
+            .collect();
+        *self.markets.write().expect("market cache poisoned") = markets.clone();
+        Ok(markets)
+    }
+
+    /// Retrieves the prediction markets, including their resolution status.
+    pub async fn get_prediction_markets(&self) -> Result<Vec<Market>> {
+        let mut markets = self.get_markets().await?;
+        markets.retain(|market| market.market_type == MarketType::Prediction);
+        Ok(markets)
+    }
+
     /// Retrieves mark price, index price and the funding rate for the current interval for all symbols, or the symbol specified.
     pub async fn get_all_mark_prices(&self) -> Result<Vec<MarkPrice>> {
         let url = format!("{}{}", self.base_url, API_MARK_PRICES);
@@ -44,13 +142,69 @@ impl BpxClient {
         res.json().await.map_err(Into::into)
     }
 
+    /// Retrieves mark price, index price and the funding rate for the current interval for the
+    /// given symbol.
+    pub async fn get_mark_price(&self, symbol: &str) -> Result<MarkPrice> {
+        let res = self
+            .get_with_query(API_MARK_PRICES, &[("symbol", symbol.to_string())])
+            .await?;
+        let mark_prices: Vec<MarkPrice> = res.json().await?;
+        mark_prices
+            .into_iter()
+            .find(|mark_price| mark_price.symbol == symbol)
+            .ok_or_else(|| Error::InvalidRequest(format!("no mark price for {symbol}").into()))
+    }
+
+    /// Retrieves the index price of every symbol, keyed by symbol.
+    pub async fn get_index_prices(&self) -> Result<HashMap<String, Decimal>> {
+        let mark_prices = self.get_all_mark_prices().await?;
+        Ok(mark_prices.into_iter().map(|m| (m.symbol, m.index_price)).collect())
+    }
+
+    /// Builds a funding table for all symbols, sorted by funding rate from highest to lowest.
+    pub async fn funding_table(&self) -> Result<Vec<FundingSnapshot>> {
+        let mut table: Vec<FundingSnapshot> = self.get_all_mark_prices().await?.into_iter().map(Into::into).collect();
+        table.sort_by(|a, b| {
+            b.funding_rate
+                .cmp(&a.funding_rate)
+                .then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        Ok(table)
+    }
+
     /// Fetches the ticker information for a given symbol.
     pub async fn get_ticker(&self, symbol: &str) -> Result<Ticker> {
-        let url = format!("{}{}?symbol={}", self.base_url, API_TICKER, symbol);
-        let res = self.get(url).await?;
+        let res = self
+            .get_with_query(API_TICKER, &[("symbol", symbol.to_string())])
+            .await?;
         res.json().await.map_err(Into::into)
     }
 
+    /// Fetches the ticker information for a given symbol, along with the time the exchange
+    /// served it.
+    ///
+    /// REST tickers carry no timestamp, so the time is read from the response's `Date`
+    /// header, which only has a one-second resolution, or from the local clock if the header
+    /// is missing. It tells how old the response is, not when the market last traded: a
+    /// thinly traded market serves fresh responses with an old last price. The ticker stream
+    /// carries event times instead, see [`TickerStatisticsUpdate::is_stale`].
+    ///
+    /// [`TickerStatisticsUpdate::is_stale`]: bpx_api_types::markets::TickerStatisticsUpdate::is_stale
+    pub async fn get_ticker_with_time(&self, symbol: &str) -> Result<(Ticker, DateTime<Utc>)> {
+        let res = self
+            .get_with_query(API_TICKER, &[("symbol", symbol.to_string())])
+            .await?;
+        let served_at = res
+            .headers()
+            .get(DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+            .map(|date| date.to_utc())
+            .or_else(|| DateTime::from_timestamp_millis(now_millis() as i64))
+            .expect("the current time is in range");
+        Ok((res.json().await?, served_at))
+    }
+
     /// Fetches the ticker information for all symbols.
     pub async fn get_tickers(&self) -> Result<Vec<Ticker>> {
         let url = format!("{}{}", self.base_url, API_TICKERS);
@@ -60,15 +214,68 @@ impl BpxClient {
 
     /// Retrieves the order book depth for a given symbol.
     pub async fn get_order_book_depth(&self, symbol: &str) -> Result<OrderBookDepth> {
-        let url = format!("{}{}?symbol={}", self.base_url, API_DEPTH, symbol);
-        let res = self.get(url).await?;
+        let res = self
+            .get_with_query(API_DEPTH, &[("symbol", symbol.to_string())])
+            .await?;
         res.json().await.map_err(Into::into)
     }
 
+    /// Returns the price a market order on `side` is likely to get: the best ask for a buy
+    /// and the best bid for a sell, from the current order book.
+    ///
+    /// If that side of the book is empty, futures markets fall back to the mark price, and
+    /// other markets fail with [`Error::InvalidRequest`]. This is the reference to use for
+    /// slippage bounds and notional estimates of orders that don't carry a price.
+    pub async fn reference_price(&self, symbol: &str, side: Side) -> Result<Decimal> {
+        let depth = self.get_order_book_depth(symbol).await?;
+        if let Some(price) = depth.touch(side) {
+            return Ok(price);
+        }
+        if self.get_market_cached(symbol).await?.market_type.is_futures() {
+            return Ok(self.get_mark_price(symbol).await?.mark_price);
+        }
+        let book_side = match side {
+            Side::Bid => "asks",
+            Side::Ask => "bids",
+        };
+        Err(Error::InvalidRequest(
+            format!("no {book_side} in the {symbol} order book").into(),
+        ))
+    }
+
+    /// Fetches the market, order book depth, recent trades and, for futures markets, the mark
+    /// price of a symbol at once, for a strategy starting on it.
+    ///
+    /// The requests are sent concurrently, except for the mark price, which is only
+    /// requested once the market shows it is a futures market.
+    pub async fn warm_up_symbol(&self, symbol: &str) -> Result<SymbolContext> {
+        let market = async {
+            let market = self.get_market(symbol).await?;
+            let mark_price = if market.market_type.is_futures() {
+                Some(self.get_mark_price(symbol).await?)
+            } else {
+                None
+            };
+            Ok::<_, Error>((market, mark_price))
+        };
+        let ((market, mark_price), depth, recent_trades) = tokio::try_join!(
+            market,
+            self.get_order_book_depth(symbol),
+            self.get_recent_trades(symbol, None),
+        )?;
+        Ok(SymbolContext {
+            market,
+            depth,
+            recent_trades,
+            mark_price,
+        })
+    }
+
     /// Funding interval rate history for futures.
     pub async fn get_funding_interval_rates(&self, symbol: &str) -> Result<Vec<FundingRate>> {
-        let url = format!("{}{}?symbol={}", self.base_url, API_FUNDING, symbol);
-        let res = self.get(url).await?;
+        let res = self
+            .get_with_query(API_FUNDING, &[("symbol", symbol.to_string())])
+            .await?;
         res.json().await.map_err(Into::into)
     }
 
@@ -81,18 +288,266 @@ impl BpxClient {
         end_time: Option<i64>,
         price_type: Option<KlinePriceType>,
     ) -> Result<Vec<Kline>> {
-        let mut url = format!(
-            "{}{}?symbol={}&interval={}&startTime={}",
-            self.base_url, API_KLINES, symbol, interval, start_time
-        );
-
+        let mut params = vec![
+            ("symbol", symbol.to_string()),
+            ("interval", interval.to_string()),
+            ("startTime", start_time.to_string()),
+        ];
         if let Some(end_time) = end_time {
-            url.push_str(&format!("&endTime={end_time}"));
+            params.push(("endTime", end_time.to_string()));
         }
         if let Some(price_type) = price_type {
-            url.push_str(&format!("&priceType={price_type}"));
+            params.push(("priceType", price_type.to_string()));
         }
-        let res = self.get(url).await?;
+        let res = self.get_with_query(API_KLINES, &params).await?;
         res.json().await.map_err(Into::into)
     }
+
+    /// Fetches the last `limit` K-lines for a given symbol and interval, up to
+    /// [`MAX_KLINES_LIMIT`]. The last candle may still be open.
+    pub async fn get_k_lines_limited(
+        &self,
+        symbol: &str,
+        interval: KlineInterval,
+        limit: u16,
+        price_type: Option<KlinePriceType>,
+    ) -> Result<Vec<Kline>> {
+        if limit == 0 || limit > MAX_KLINES_LIMIT {
+            return Err(Error::InvalidRequest(
+                format!("`limit` must be between 1 and {MAX_KLINES_LIMIT}, got {limit}").into(),
+            ));
+        }
+        // Months are at most 31 days long, extra candles are dropped below.
+        let interval_secs = interval.duration_secs().unwrap_or(31 * 24 * 60 * 60);
+        let start_time = (now_millis() / 1000).saturating_sub(interval_secs * u64::from(limit));
+
+        let mut klines = self
+            .get_k_lines(symbol, interval, start_time as i64, None, price_type)
+            .await?;
+        let excess = klines.len().saturating_sub(usize::from(limit));
+        klines.drain(..excess);
+        Ok(klines)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{test_client_builder, MockResponse, MockServer, TEST_MARKET_JSON};
+    use bpx_api_types::markets::PredictionOutcome;
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn test_get_assets_by_capability() {
+        let token = |blockchain: &str, deposit: bool, withdraw: bool| {
+            format!(
+                r#"{{"blockchain":"{blockchain}","depositEnabled":{deposit},"minimumDeposit":"0","withdrawEnabled":{withdraw},"minimumWithdrawal":"0","maximumWithdrawal":null,"withdrawalFee":"0"}}"#
+            )
+        };
+        let body = format!(
+            r#"[{{"symbol":"SOL","tokens":[{},{}]}},{{"symbol":"ETH","tokens":[{}]}}]"#,
+            token("Solana", false, true),
+            token("Polygon", true, false),
+            token("Ethereum", false, false),
+        );
+        let server = MockServer::start(vec![MockResponse::new(200, body.clone()), MockResponse::new(200, body)]).await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let depositable = client.get_depositable_assets().await.unwrap();
+        assert_eq!(depositable.len(), 1);
+        assert_eq!(depositable[0].symbol, "SOL");
+        assert_eq!(depositable[0].depositable_chains().count(), 1);
+
+        let withdrawable = client.get_withdrawable_assets().await.unwrap();
+        assert_eq!(withdrawable.len(), 1);
+        assert_eq!(
+            withdrawable[0].withdrawable_chains().collect::<Vec<_>>(),
+            [&bpx_api_types::Blockchain::Solana]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_markets_map_fills_cache() {
+        let server = MockServer::start(vec![MockResponse::new(200, format!("[{TEST_MARKET_JSON}]"))]).await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let markets = client.get_markets_map().await.unwrap();
+        assert_eq!(markets.len(), 1);
+        assert_eq!(markets["SOL_USDC"].step_size(), dec!(0.01));
+        assert_eq!(client.get_market_cached("SOL_USDC").await.unwrap().symbol, "SOL_USDC");
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_market_filters() {
+        let server = MockServer::start(vec![MockResponse::new(200, TEST_MARKET_JSON)]).await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let filters = client.market_filters("SOL_USDC").await.unwrap();
+        assert_eq!((filters.tick_size, filters.step_size), (dec!(0.01), dec!(0.01)));
+        assert!(filters.validate_price(dec!(150.5)).is_ok());
+        assert!(filters.validate_price(dec!(150.505)).is_err());
+        // Served from the cache afterwards.
+        assert_eq!(client.market_filters("SOL_USDC").await.unwrap(), filters);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_prediction_markets() {
+        let prediction = r#"{
+            "symbol": "ELECTION_YES_USDC",
+            "baseSymbol": "ELECTION_YES",
+            "quoteSymbol": "USDC",
+            "marketType": "PREDICTION",
+            "filters": {
+                "price": { "minPrice": "0.01", "tickSize": "0.01" },
+                "quantity": { "minQuantity": "1", "stepSize": "1" }
+            },
+            "orderBookState": "Open",
+            "createdAt": "2025-01-01T00:00:00",
+            "resolutionSource": "https://example.com/results",
+            "resolutionTime": "2025-11-05T00:00:00",
+            "outcome": "Unresolved"
+        }"#;
+        let body = format!("[{TEST_MARKET_JSON},{prediction}]");
+        let server = MockServer::start(vec![MockResponse::new(200, body)]).await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let markets = client.get_prediction_markets().await.unwrap();
+        assert_eq!(markets.len(), 1);
+        assert_eq!(markets[0].outcome, Some(PredictionOutcome::Unresolved));
+        assert!(markets[0].resolution_time.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_k_lines_limited_keeps_last_candles() {
+        let kline = |start: &str| {
+            format!(
+                r#"{{"start":"{start}","end":"","open":"1","high":"1","low":"1","close":"1","volume":"0","quoteVolume":"0","trades":"0"}}"#
+            )
+        };
+        let body = format!("[{},{},{}]", kline("a"), kline("b"), kline("c"));
+        let server = MockServer::start(vec![MockResponse::new(200, body)]).await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let klines = client
+            .get_k_lines_limited("SOL_USDC", KlineInterval::OneHour, 2, None)
+            .await
+            .unwrap();
+        assert_eq!(klines.iter().map(|k| k.start.as_str()).collect::<Vec<_>>(), ["b", "c"]);
+
+        let request = &server.requests()[0];
+        assert!(request.starts_with("GET /api/v1/klines?symbol=SOL_USDC&interval=1h&startTime="));
+    }
+
+    #[tokio::test]
+    async fn test_get_k_lines_limited_rejects_out_of_range_limit() {
+        let server = MockServer::start(vec![]).await;
+        let client = test_client_builder(&server).build().unwrap();
+        for limit in [0, MAX_KLINES_LIMIT + 1] {
+            let result = client
+                .get_k_lines_limited("SOL_USDC", KlineInterval::OneHour, limit, None)
+                .await;
+            assert!(matches!(result, Err(Error::InvalidRequest(_))));
+        }
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_symbol() {
+        let server = MockServer::start_with(|request| {
+            let path = request.split(' ').nth(1).unwrap();
+            let body = match path.split('?').next().unwrap() {
+                API_MARKET if path.contains("SOL_USDC_PERP") => {
+                    TEST_MARKET_JSON.replace(r#""SOL_USDC""#, r#""SOL_USDC_PERP""#).replace("SPOT", "PERP")
+                }
+                API_MARKET => TEST_MARKET_JSON.to_string(),
+                API_DEPTH => r#"{"asks":[["151","1"]],"bids":[["150","2"]],"lastUpdateId":"7","timestamp":1}"#.to_string(),
+                "/api/v1/trades" => {
+                    r#"[{"id":1,"price":"150.5","quantity":"1","quoteQuantity":"150.5","timestamp":1,"isBuyerMaker":true}]"#
+                        .to_string()
+                }
+                API_MARK_PRICES => r#"[{"symbol":"SOL_USDC_PERP","fundingRate":"0.0001","indexPrice":"150.1","markPrice":"150.2","nextFundingTimestamp":1}]"#
+                    .to_string(),
+                _ => return MockResponse::new(404, ""),
+            };
+            MockResponse::new(200, body)
+        })
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let spot = client.warm_up_symbol("SOL_USDC").await.unwrap();
+        assert_eq!(spot.market.symbol, "SOL_USDC");
+        assert_eq!(spot.depth.last_update_id, 7);
+        assert_eq!(spot.recent_trades[0].price, dec!(150.5));
+        assert!(spot.mark_price.is_none());
+        assert_eq!(server.requests().len(), 3);
+
+        let perp = client.warm_up_symbol("SOL_USDC_PERP").await.unwrap();
+        assert_eq!(perp.market.market_type, MarketType::Perp);
+        assert_eq!(perp.mark_price.unwrap().mark_price, dec!(150.2));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 7);
+        assert!(requests
+            .iter()
+            .any(|request| request.starts_with("GET /api/v1/markPrices?symbol=SOL_USDC_PERP ")));
+    }
+
+    #[tokio::test]
+    async fn test_reference_price() {
+        let server = MockServer::start_with(|request| {
+            let path = request.split(' ').nth(1).unwrap();
+            let body = match path.split('?').next().unwrap() {
+                API_MARKET => TEST_MARKET_JSON
+                    .replace(r#""SOL_USDC""#, r#""SOL_USDC_PERP""#)
+                    .replace("SPOT", "PERP"),
+                API_DEPTH if path.contains("SOL_USDC_PERP") => {
+                    r#"{"asks":[],"bids":[["150","2"]],"lastUpdateId":"7","timestamp":1}"#.to_string()
+                }
+                API_DEPTH => {
+                    r#"{"asks":[["152","1"],["151","1"]],"bids":[["149","2"],["150","2"]],"lastUpdateId":"7","timestamp":1}"#
+                        .to_string()
+                }
+                API_MARK_PRICES => r#"[{"symbol":"SOL_USDC_PERP","fundingRate":"0.0001","indexPrice":"150.1","markPrice":"150.2","nextFundingTimestamp":1}]"#
+                    .to_string(),
+                _ => return MockResponse::new(404, ""),
+            };
+            MockResponse::new(200, body)
+        })
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        assert_eq!(client.reference_price("SOL_USDC", Side::Bid).await.unwrap(), dec!(151));
+        assert_eq!(client.reference_price("SOL_USDC", Side::Ask).await.unwrap(), dec!(150));
+        assert_eq!(server.requests().len(), 2);
+
+        // The perp book has no asks, so a buy falls back to the mark price.
+        assert_eq!(
+            client.reference_price("SOL_USDC_PERP", Side::Ask).await.unwrap(),
+            dec!(150)
+        );
+        assert_eq!(
+            client.reference_price("SOL_USDC_PERP", Side::Bid).await.unwrap(),
+            dec!(150.2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_ticker_with_time_reads_date_header() {
+        let ticker = r#"{"symbol":"SOL_USDC","firstPrice":"140","lastPrice":"142.1","priceChange":"2.1","priceChangePercent":"0.015","high":"143","low":"139.9","volume":"1000","trades":"42"}"#;
+        let server = MockServer::start(vec![
+            MockResponse::new(200, ticker).with_header("Date", "Wed, 15 Oct 2025 07:28:00 GMT"),
+            MockResponse::new(200, ticker),
+        ])
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let (ticker, served_at) = client.get_ticker_with_time("SOL_USDC").await.unwrap();
+        assert_eq!(ticker.last_price, dec!(142.1));
+        assert_eq!(served_at.naive_utc().to_string(), "2025-10-15 07:28:00");
+
+        let before = now_millis() as i64;
+        let (_, served_at) = client.get_ticker_with_time("SOL_USDC").await.unwrap();
+        assert!(served_at.timestamp_millis() >= before);
+    }
 }
@@ -2,10 +2,11 @@ pub mod account;
 pub mod borrow_lend;
 pub mod capital;
 pub mod futures;
-pub mod strategies;
+pub mod history;
 pub mod markets;
 pub mod order;
 pub mod rfq;
+pub mod strategies;
+pub mod system;
 pub mod trades;
 pub mod user;
-pub mod history;
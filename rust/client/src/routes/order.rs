@@ -1,15 +1,48 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bpx_api_types::history::{OrderHistorySearchParams, MAX_HISTORY_LIMIT};
 use bpx_api_types::order::{
-    BulkOrderResponse, BulkOrdersResponse, CancelOpenOrdersPayload, CancelOrderPayload, ExecuteOrderPayload, Order,
+    BulkOrderResponse, BulkOrdersResponse, CancelAllFilter, CancelOpenOrdersPayload, CancelOrderPayload,
+    ExecuteOrderPayload, Order, OrderLike, OrderType,
 };
+use bpx_api_types::SortDirection;
+
+use reqwest::Method;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
 
 use crate::error::{Error, Result};
-use crate::BpxClient;
+use crate::{BpxClient, RoundingConfig, API_KEY_HEADER};
 
 #[doc(hidden)]
 pub const API_ORDER: &str = "/api/v1/order";
 #[doc(hidden)]
 pub const API_ORDERS: &str = "/api/v1/orders";
 
+/// Maximum number of cancellations in flight in [`BpxClient::cancel_orders_by_client_ids`]
+/// and [`BpxClient::cancel_open_orders_filtered`].
+pub const MAX_CONCURRENT_CANCELS: usize = 5;
+
+/// Whether an order of a batch submitted with [`BpxClient::execute_orders_with_recovery`]
+/// reached the exchange.
+#[derive(Debug)]
+pub enum OrderVerdict {
+    /// The order was accepted by the exchange, with the given order ID. It may since have
+    /// been filled, cancelled or expired.
+    Landed(String),
+    /// The order was not placed: it was rejected with the given error, or it was not found
+    /// after the submission failed, by a lookup made once the receive window had passed and
+    /// covering every recent order of its market.
+    NotLanded(Option<Error>),
+    /// The order was not found after the submission failed, but the lookup was incomplete:
+    /// it failed, or the market has more orders than one page of history. It may or may
+    /// not have been placed.
+    Unknown,
+}
+
 impl BpxClient {
     /// Fetches a specific open order by symbol and either order ID or client ID.
     pub async fn get_open_order(&self, symbol: &str, order_id: Option<&str>, client_id: Option<u32>) -> Result<Order> {
@@ -27,16 +60,115 @@ impl BpxClient {
     }
 
     /// Executes a new order with the given payload.
+    ///
+    /// If the client was built with a [`RoundingConfig`], the price and quantity are first
+    /// snapped to the market's tick and step sizes and formatted to their scale, see
+    /// [`ExecuteOrderPayload::normalize_scale`].
+    ///
+    /// Standalone stop and take profit orders are placed by setting a trigger price, see
+    /// [`ExecuteOrderPayload::with_trigger`]. Their trigger fields are checked with
+    /// [`ExecuteOrderPayload::validate_trigger`] before sending, failing with
+    /// [`Error::InvalidRequest`].
     pub async fn execute_order(&self, payload: ExecuteOrderPayload) -> Result<Order> {
+        let payload = self.maybe_round_order(payload).await?;
+        self.execute_order_exact(payload).await
+    }
+
+    /// Executes a new order like [`BpxClient::execute_order`], recovering it if the
+    /// submission doesn't complete within `timeout`.
+    ///
+    /// Dropping an `execute_order` future, for example when a `select!` timeout fires, does
+    /// not withdraw a request that already left the client: the order may still be placed,
+    /// with the caller never learning about it. To avoid such orphans the payload must carry a
+    /// `client_id`, and on timeout the order is looked up among the open orders by that ID.
+    /// If it is open it is returned as if the submission had completed. Otherwise the request
+    /// may still be on its way, so it is looked up once more after the client's receive
+    /// window, past which the exchange rejects it, and [`Error::OrderUnconfirmed`] is
+    /// returned if it is still not open. Even then the order may have landed: orders that
+    /// fill immediately are never open, and a client clock running behind the exchange's
+    /// extends the window. Check the open orders and fills before resubmitting on that error.
+    /// The second lookup adds the receive window to the time it takes to give up.
+    pub async fn execute_order_with_recovery(&self, payload: ExecuteOrderPayload, timeout: Duration) -> Result<Order> {
+        let client_id = payload
+            .client_id
+            .ok_or_else(|| Error::InvalidRequest("client_id is required to recover an order".into()))?;
+        let symbol = payload.symbol.clone();
+        match tokio::time::timeout(timeout, self.execute_order(payload)).await {
+            Ok(res) => res,
+            Err(_) => {
+                tracing::warn!("order with client ID {client_id} timed out, looking it up");
+                let res = self.get_open_order(&symbol, None, Some(client_id)).await;
+                if !is_not_found(&res) {
+                    return res;
+                }
+                tokio::time::sleep(self.recv_window()).await;
+                let res = self.get_open_order(&symbol, None, Some(client_id)).await;
+                if is_not_found(&res) {
+                    return Err(Error::OrderUnconfirmed { client_id });
+                }
+                res
+            }
+        }
+    }
+
+    /// Executes a new order with the given payload, sending it exactly as given regardless
+    /// of the client's rounding configuration.
+    pub async fn execute_order_exact(&self, payload: ExecuteOrderPayload) -> Result<Order> {
+        validate_order(&payload)?;
+        self.maybe_check_post_only(&payload).await?;
+        let payload = self.with_order_defaults(payload);
         let endpoint = format!("{}{}", self.base_url, API_ORDER);
+        self.throttle_orders(1).await;
         let res = self.post(endpoint, payload).await?;
         res.json().await.map_err(Into::into)
     }
 
+    /// Builds and signs the request `execute_order` would send for the given payload,
+    /// without sending it.
+    ///
+    /// Returns a JSON object with the `method`, `url`, signed `headers` and the exact `body`
+    /// string. The client's default headers (such as the user agent) are added by the HTTP
+    /// client when sending and are not included.
+    pub async fn execute_order_dry_run(&self, payload: ExecuteOrderPayload) -> Result<Value> {
+        validate_order(&payload)?;
+        let payload = self.with_order_defaults(self.maybe_round_order(payload).await?);
+        let endpoint = format!("{}{}", self.base_url, API_ORDER);
+        let req = self.build_and_maybe_sign_request(endpoint, Method::POST, Some(&payload))?;
+
+        let mut headers = Map::new();
+        headers.insert(API_KEY_HEADER.to_string(), STANDARD.encode(self.verifier).into());
+        for (name, value) in req.headers() {
+            if name.as_str().eq_ignore_ascii_case(API_KEY_HEADER) {
+                continue;
+            }
+            headers.insert(name.to_string(), value.to_str().unwrap_or_default().into());
+        }
+        let body = req
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+            .unwrap_or_default();
+
+        Ok(json!({
+            "method": req.method().as_str(),
+            "url": req.url().as_str(),
+            "headers": headers,
+            "body": body,
+        }))
+    }
+
     /// Submits a set of orders to the matching engine for execution in a batch.
+    ///
+    /// Orders are rounded the same way as in [`BpxClient::execute_order`].
     pub async fn execute_orders(&self, payload: Vec<ExecuteOrderPayload>) -> Result<Vec<Result<Order>>> {
+        let mut orders = Vec::with_capacity(payload.len());
+        for order in payload {
+            validate_order(&order)?;
+            orders.push(self.with_order_defaults(self.maybe_round_order(order).await?));
+        }
         let endpoint = format!("{}{}", self.base_url, API_ORDERS);
-        let res = self.post(endpoint, payload).await?;
+        self.throttle_orders(orders.len()).await;
+        let res = self.post(endpoint, orders).await?;
         match res.json().await.map_err(Into::<Error>::into)? {
             BulkOrdersResponse::Results(items) => {
                 let mut results = Vec::with_capacity(items.len());
@@ -61,6 +193,60 @@ impl BpxClient {
         }
     }
 
+    /// Submits a batch like [`BpxClient::execute_orders`], reconciling which orders were
+    /// placed if the submission fails without a response from the exchange.
+    ///
+    /// Every order must carry a `client_id`, unique within the batch and not reused from
+    /// recent orders on the same market. A batch that times out after `timeout`, or fails
+    /// with a connection error, a server error or an unreadable response may have been
+    /// placed in full, in part or not at all. The orders are then looked up by client ID and
+    /// a verdict is returned for each, in the order of `payload`, so only the orders that
+    /// did not land need to be resubmitted. Errors the exchange returns for the whole batch,
+    /// such as a rejected signature, are returned as is.
+    ///
+    /// The orders are looked up among the open orders and the [`MAX_HISTORY_LIMIT`] most
+    /// recent orders of the order history of their market, two requests per market. Orders
+    /// not found may still be on their way, so their markets are looked up again after the
+    /// client's receive window, past which the exchange rejects the batch. Orders still not
+    /// found are reported as not landed if that lookup covered every order of the market,
+    /// and as unknown otherwise. A client clock running behind the exchange's extends the
+    /// window, so an order reported as not landed may still land in that case.
+    pub async fn execute_orders_with_recovery(
+        &self,
+        payload: Vec<ExecuteOrderPayload>,
+        timeout: Duration,
+    ) -> Result<Vec<OrderVerdict>> {
+        let mut tags: Vec<(String, u32)> = Vec::with_capacity(payload.len());
+        for order in &payload {
+            let client_id = order.client_id.ok_or_else(|| {
+                Error::InvalidRequest("client_id is required on every order to recover a batch".into())
+            })?;
+            if tags.iter().any(|(_, id)| *id == client_id) {
+                return Err(Error::InvalidRequest(
+                    format!("client_id {client_id} is used by more than one order").into(),
+                ));
+            }
+            tags.push((order.symbol.clone(), client_id));
+        }
+
+        let failure = match tokio::time::timeout(timeout, self.execute_orders(payload)).await {
+            Ok(Ok(results)) => {
+                return Ok(results
+                    .into_iter()
+                    .map(|result| match result {
+                        Ok(order) => OrderVerdict::Landed(order.id().to_string()),
+                        Err(err) => OrderVerdict::NotLanded(Some(err)),
+                    })
+                    .collect());
+            }
+            Ok(Err(err)) if !may_have_landed(&err) => return Err(err),
+            Ok(Err(err)) => err.to_string(),
+            Err(_) => "timed out".to_string(),
+        };
+        tracing::warn!("order batch failed ({failure}), looking up its {} orders", tags.len());
+        Ok(self.reconcile_orders(&tags).await)
+    }
+
     /// Cancels a specific order by symbol and either order ID or client ID.
     pub async fn cancel_order(&self, symbol: &str, order_id: Option<&str>, client_id: Option<u32>) -> Result<Order> {
         let url = format!("{}{}", self.base_url, API_ORDER);
@@ -74,7 +260,28 @@ impl BpxClient {
         res.json().await.map_err(Into::into)
     }
 
+    /// Cancels the orders with the given client IDs on `symbol`.
+    ///
+    /// Each order is cancelled with its own request, with at most [`MAX_CONCURRENT_CANCELS`]
+    /// in flight at once. The outcome of each cancellation is returned in the order of
+    /// `client_ids`, so an ID that is not found does not hide the others.
+    pub async fn cancel_orders_by_client_ids(&self, symbol: &str, client_ids: &[u32]) -> Vec<Result<Order>> {
+        let targets = client_ids.iter().map(|&client_id| (None, Some(client_id))).collect();
+        self.cancel_each(symbol, targets).await
+    }
+
     /// Retrieves all open orders, optionally filtered by symbol.
+    ///
+    /// The result mixes two kinds of orders, told apart by their status. Resting orders are
+    /// on the book with [`OrderStatus::New`] or [`OrderStatus::PartiallyFilled`]. Trigger
+    /// orders that haven't fired yet, such as standalone stops, have
+    /// [`OrderStatus::TriggerPending`] and their `trigger_price`, `trigger_by` and
+    /// `trigger_quantity` set; they are always included, the endpoint has no flag to hide or
+    /// request them.
+    ///
+    /// [`OrderStatus::New`]: bpx_api_types::order::OrderStatus::New
+    /// [`OrderStatus::PartiallyFilled`]: bpx_api_types::order::OrderStatus::PartiallyFilled
+    /// [`OrderStatus::TriggerPending`]: bpx_api_types::order::OrderStatus::TriggerPending
     pub async fn get_open_orders(&self, symbol: Option<&str>) -> Result<Vec<Order>> {
         let mut url = format!("{}{}", self.base_url, API_ORDERS);
         if let Some(s) = symbol {
@@ -90,4 +297,715 @@ impl BpxClient {
         let res = self.delete(url, payload).await?;
         res.json().await.map_err(Into::into)
     }
+
+    /// Cancels the open orders of the payload's market that pass `filter`, for example only
+    /// the post-only quotes while keeping reduce-only stops in place.
+    ///
+    /// The exchange can only cancel all the orders of a market at once. A filter matching
+    /// every order therefore cancels them with a single request, like
+    /// [`BpxClient::cancel_open_orders`]. Any other filter fetches the open orders and cancels
+    /// the matching ones by order ID, with at most [`MAX_CONCURRENT_CANCELS`] in flight at
+    /// once; orders placed in between are left alone. Returns the outcome of each
+    /// cancellation, or an error if the open orders could not be fetched.
+    pub async fn cancel_open_orders_filtered(
+        &self,
+        payload: CancelOpenOrdersPayload,
+        filter: CancelAllFilter,
+    ) -> Result<Vec<Result<Order>>> {
+        if filter.matches_all() {
+            let cancelled = self.cancel_open_orders(payload).await?;
+            return Ok(cancelled.into_iter().map(Ok).collect());
+        }
+        let targets = self
+            .get_open_orders(Some(&payload.symbol))
+            .await?
+            .iter()
+            .filter(|order| filter.matches(order))
+            .map(|order| (Some(order.id().to_string()), None))
+            .collect();
+        Ok(self.cancel_each(&payload.symbol, targets).await)
+    }
+}
+
+// Private functions.
+impl BpxClient {
+    /// Cancels each `(order_id, client_id)` target on `symbol` with its own request, with at
+    /// most [`MAX_CONCURRENT_CANCELS`] in flight, returning the outcomes in the targets' order.
+    async fn cancel_each(&self, symbol: &str, targets: Vec<(Option<String>, Option<u32>)>) -> Vec<Result<Order>> {
+        let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_CANCELS));
+        let mut cancels = JoinSet::new();
+        let count = targets.len();
+        for (index, (order_id, client_id)) in targets.into_iter().enumerate() {
+            let client = self.clone();
+            let symbol = symbol.to_string();
+            let permits = permits.clone();
+            cancels.spawn(async move {
+                let _permit = permits.acquire_owned().await.expect("semaphore is never closed");
+                (
+                    index,
+                    client.cancel_order(&symbol, order_id.as_deref(), client_id).await,
+                )
+            });
+        }
+
+        let mut results: Vec<Option<Result<Order>>> = (0..count).map(|_| None).collect();
+        while let Some(result) = cancels.join_next().await {
+            let (index, result) = result.expect("order cancellation task panicked");
+            results[index] = Some(result);
+        }
+        results
+            .into_iter()
+            .map(|r| r.expect("every cancellation completes"))
+            .collect()
+    }
+
+    /// Looks up the orders with the given `(symbol, client_id)` among the open orders and the
+    /// order history of their market, looking up the orders not found a second time once
+    /// the receive window has passed.
+    async fn reconcile_orders(&self, tags: &[(String, u32)]) -> Vec<OrderVerdict> {
+        let mut verdicts: Vec<Option<OrderVerdict>> = tags.iter().map(|_| None).collect();
+        self.find_orders(tags, &mut verdicts, false).await;
+        if verdicts.iter().any(Option::is_none) {
+            tokio::time::sleep(self.recv_window()).await;
+            self.find_orders(tags, &mut verdicts, true).await;
+        }
+        verdicts
+            .into_iter()
+            .map(|verdict| verdict.unwrap_or(OrderVerdict::Unknown))
+            .collect()
+    }
+
+    /// Looks up the orders of `tags` without a verdict yet, marking those found as landed.
+    /// If `last`, the orders not found are marked as not landed when the lookup covered
+    /// every order of their market.
+    async fn find_orders(&self, tags: &[(String, u32)], verdicts: &mut [Option<OrderVerdict>], last: bool) {
+        let mut symbols: Vec<&str> = Vec::new();
+        for ((symbol, _), verdict) in tags.iter().zip(verdicts.iter()) {
+            if verdict.is_none() && !symbols.contains(&symbol.as_str()) {
+                symbols.push(symbol);
+            }
+        }
+
+        for symbol in symbols {
+            let (found, complete) = self.client_orders(symbol).await;
+            for ((tag_symbol, client_id), verdict) in tags.iter().zip(verdicts.iter_mut()) {
+                if tag_symbol != symbol || verdict.is_some() {
+                    continue;
+                }
+                match found.iter().find(|(id, _)| id == client_id) {
+                    Some((_, order_id)) => *verdict = Some(OrderVerdict::Landed(order_id.clone())),
+                    None if last && complete => *verdict = Some(OrderVerdict::NotLanded(None)),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    /// Returns the `(client_id, order_id)` of the open and recent orders of a market, and
+    /// whether they are all of its orders: both requests succeeded and the history fit in
+    /// one page.
+    async fn client_orders(&self, symbol: &str) -> (Vec<(u32, String)>, bool) {
+        let mut found = Vec::new();
+        let mut complete = true;
+        match self.get_open_orders(Some(symbol)).await {
+            Ok(orders) => found.extend(orders.iter().filter_map(|order| {
+                let client_id = match order {
+                    Order::Market(o) => o.client_id,
+                    Order::Limit(o) => o.client_id,
+                };
+                Some((client_id?, order.id().to_string()))
+            })),
+            Err(err) => {
+                tracing::warn!("{symbol}: failed to fetch the open orders: {err}");
+                complete = false;
+            }
+        }
+        let params = OrderHistorySearchParams::builder()
+            .symbol(symbol)
+            .limit(MAX_HISTORY_LIMIT)
+            .sort_direction(SortDirection::Desc)
+            .build()
+            .expect("the history params are valid");
+        match self.get_order_history(params).await {
+            Ok(orders) => {
+                complete &= (orders.len() as u64) < MAX_HISTORY_LIMIT;
+                found.extend(
+                    orders
+                        .into_iter()
+                        .filter_map(|order| Some((order.client_id?, order.id))),
+                );
+            }
+            Err(err) => {
+                tracing::warn!("{symbol}: failed to fetch the order history: {err}");
+                complete = false;
+            }
+        }
+        (found, complete)
+    }
+
+    /// Waits until the client's order rate limit, if any, allows placing `orders` orders.
+    async fn throttle_orders(&self, orders: usize) {
+        let Some(bucket) = &self.order_bucket else {
+            return;
+        };
+        for _ in 0..orders {
+            loop {
+                let taken = bucket
+                    .lock()
+                    .expect("order rate limit bucket poisoned")
+                    .try_take(Instant::now());
+                match taken {
+                    Ok(()) => break,
+                    Err(retry_in) => tokio::time::sleep(retry_in).await,
+                }
+            }
+        }
+    }
+
+    /// Fills in the client's defaults for order fields the payload leaves unset.
+    fn with_order_defaults(&self, mut payload: ExecuteOrderPayload) -> ExecuteOrderPayload {
+        payload.self_trade_prevention = payload.self_trade_prevention.or(self.self_trade_prevention);
+        if payload.order_type == OrderType::Limit {
+            payload.time_in_force = payload.time_in_force.or(self.time_in_force);
+        }
+        payload
+    }
+
+    /// Checks a post-only order against the current best bid and ask if the client was built
+    /// with [`crate::BpxClientBuilder::post_only_guard`].
+    async fn maybe_check_post_only(&self, payload: &ExecuteOrderPayload) -> Result<()> {
+        if !self.post_only_guard || payload.post_only != Some(true) || payload.price.is_none() {
+            return Ok(());
+        }
+        let depth = self.get_order_book_depth(&payload.symbol).await?;
+        let best_bid = depth.bids.iter().map(|(price, _)| *price).max();
+        let best_ask = depth.asks.iter().map(|(price, _)| *price).min();
+        payload.check_post_only(best_bid, best_ask).map_err(Into::into)
+    }
+
+    /// Applies the client's [`RoundingConfig`], if any, to the order's price and quantity,
+    /// and formats them to the market's scale so the signature and body carry the same
+    /// canonical strings.
+    async fn maybe_round_order(&self, mut payload: ExecuteOrderPayload) -> Result<ExecuteOrderPayload> {
+        let Some(RoundingConfig { price: rounding }) = self.rounding else {
+            return Ok(payload);
+        };
+        if payload.price.is_none() && payload.quantity.is_none() {
+            return Ok(payload);
+        }
+        let market = self.get_market_cached(&payload.symbol).await?;
+        payload.price = payload.price.map(|p| market.round_price(p, payload.side, rounding));
+        payload.quantity = payload.quantity.map(|q| market.round_quantity(q));
+        Ok(payload.normalize_scale(&market))
+    }
+}
+
+/// Checks the order locally for mistakes the exchange would reject it for.
+fn validate_order(payload: &ExecuteOrderPayload) -> Result<()> {
+    payload
+        .validate_trigger()
+        .map_err(|e| Error::InvalidRequest(e.to_string().into()))
+}
+
+/// Returns whether an order lookup found no such order.
+fn is_not_found(res: &Result<Order>) -> bool {
+    matches!(res, Err(Error::BpxApiError { status_code, .. }) if *status_code == reqwest::StatusCode::NOT_FOUND)
+}
+
+/// Returns whether a batch submission that failed with `err` may still have been placed.
+fn may_have_landed(err: &Error) -> bool {
+    match err {
+        Error::BpxApiError { status_code, .. } => status_code.is_server_error(),
+        Error::IncompleteResponse { .. } | Error::Reqwest(_) | Error::SerdeJson(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{test_client_builder, MockResponse, MockServer, TEST_MARKET_JSON, TEST_SECRET};
+    use bpx_api_types::order::{OrderStatus, SelfTradePrevention, Side, TimeInForce, TriggerBy, TriggerQuantity};
+    use ed25519_dalek::{Signature, Verifier};
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn test_execute_order_dry_run_matches_live_request() {
+        let client = BpxClient::builder().secret(TEST_SECRET).build().unwrap();
+        let payload = ExecuteOrderPayload {
+            symbol: "SOL_USDC".to_string(),
+            side: Side::Bid,
+            order_type: OrderType::Limit,
+            price: Some(dec!(150.5)),
+            quantity: Some(dec!(2)),
+            ..Default::default()
+        };
+
+        let dry_run = client.execute_order_dry_run(payload.clone()).await.unwrap();
+        assert_eq!(dry_run["method"], "POST");
+        assert_eq!(dry_run["url"], "https://api.backpack.exchange/api/v1/order");
+        assert_eq!(dry_run["body"], serde_json::to_string(&payload).unwrap());
+
+        let headers = &dry_run["headers"];
+        let timestamp = headers["x-timestamp"].as_str().unwrap();
+        assert_eq!(headers["x-window"], "5000");
+        let signee = format!(
+            "instruction=orderExecute&orderType=Limit&price=150.5&quantity=2&side=Bid&symbol=SOL_USDC\
+             &timestamp={timestamp}&window=5000"
+        );
+        let signature = STANDARD.decode(headers["x-signature"].as_str().unwrap()).unwrap();
+        let signature = Signature::from_slice(&signature).unwrap();
+        assert!(client.verifier().verify(signee.as_bytes(), &signature).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_self_trade_prevention_default_and_override() {
+        let payload = ExecuteOrderPayload {
+            symbol: "SOL_USDC".to_string(),
+            side: Side::Bid,
+            order_type: OrderType::Market,
+            quantity: Some(dec!(1)),
+            ..Default::default()
+        };
+        let body = |request: Value| request["body"].as_str().unwrap().to_string();
+
+        let client = BpxClient::builder().secret(TEST_SECRET).build().unwrap();
+        let request = client.execute_order_dry_run(payload.clone()).await.unwrap();
+        assert!(!body(request).contains("selfTradePrevention"));
+
+        let client = BpxClient::builder()
+            .secret(TEST_SECRET)
+            .self_trade_prevention(SelfTradePrevention::RejectBoth)
+            .build()
+            .unwrap();
+        let request = client.execute_order_dry_run(payload.clone()).await.unwrap();
+        assert!(body(request).contains(r#""selfTradePrevention":"RejectBoth""#));
+
+        let payload = ExecuteOrderPayload {
+            self_trade_prevention: Some(SelfTradePrevention::Allow),
+            ..payload
+        };
+        let request = client.execute_order_dry_run(payload).await.unwrap();
+        assert!(body(request).contains(r#""selfTradePrevention":"Allow""#));
+    }
+
+    #[tokio::test]
+    async fn test_time_in_force_default_only_applies_to_limit_orders() {
+        let market = ExecuteOrderPayload {
+            symbol: "SOL_USDC".to_string(),
+            side: Side::Bid,
+            order_type: OrderType::Market,
+            quantity: Some(dec!(1)),
+            ..Default::default()
+        };
+        let limit = ExecuteOrderPayload {
+            order_type: OrderType::Limit,
+            price: Some(dec!(150)),
+            ..market.clone()
+        };
+        let body = |request: Value| request["body"].as_str().unwrap().to_string();
+
+        let client = BpxClient::builder().secret(TEST_SECRET).build().unwrap();
+        let request = client.execute_order_dry_run(limit.clone()).await.unwrap();
+        assert!(!body(request).contains("timeInForce"));
+
+        let client = BpxClient::builder()
+            .secret(TEST_SECRET)
+            .time_in_force(TimeInForce::IOC)
+            .build()
+            .unwrap();
+        let request = client.execute_order_dry_run(limit.clone()).await.unwrap();
+        assert!(body(request).contains(r#""timeInForce":"IOC""#));
+        let request = client.execute_order_dry_run(market.clone()).await.unwrap();
+        assert!(!body(request).contains("timeInForce"));
+
+        let request = client
+            .execute_order_dry_run(limit.with_time_in_force(TimeInForce::GTC))
+            .await
+            .unwrap();
+        assert!(body(request).contains(r#""timeInForce":"GTC""#));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_orders_by_client_ids_preserves_order() {
+        let server = MockServer::start_with(|request| {
+            let client_id = request.rsplit(r#""clientId":"#).next().unwrap().trim_end_matches('}');
+            if client_id == "2" {
+                return MockResponse::new(404, r#"{"code":"RESOURCE_NOT_FOUND","message":"Order not found"}"#);
+            }
+            let order = format!(
+                r#"{{"orderType":"Limit","id":"{client_id}00","clientId":{client_id},"symbol":"SOL_USDC","side":"Bid",
+                "quantity":"2","executedQuantity":"0","executedQuoteQuantity":"0","price":"150","timeInForce":"GTC",
+                "selfTradePrevention":"RejectTaker","postOnly":false,"status":"Cancelled","createdAt":0}}"#
+            );
+            MockResponse::new(200, order)
+        })
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let ids: Vec<u32> = (1..=8).collect();
+        let results = client.cancel_orders_by_client_ids("SOL_USDC", &ids).await;
+
+        assert_eq!(results.len(), ids.len());
+        for (id, result) in ids.iter().zip(&results) {
+            match result {
+                Ok(order) => assert_eq!(order.id(), format!("{id}00")),
+                Err(Error::BpxApiError { status_code, .. }) => {
+                    assert_eq!(*id, 2);
+                    assert_eq!(*status_code, reqwest::StatusCode::NOT_FOUND);
+                }
+                Err(err) => panic!("unexpected error: {err}"),
+            }
+        }
+        assert!(results[1].is_err());
+        assert_eq!(server.requests().len(), ids.len());
+    }
+
+    #[tokio::test]
+    async fn test_signed_decimals_match_body() {
+        let server = MockServer::start(vec![MockResponse::new(200, TEST_MARKET_JSON)]).await;
+        let client = test_client_builder(&server)
+            .rounding(RoundingConfig::default())
+            .build()
+            .unwrap();
+        let payload = ExecuteOrderPayload {
+            symbol: "SOL_USDC".to_string(),
+            side: Side::Bid,
+            order_type: OrderType::Limit,
+            price: Some(dec!(150.5)),
+            quantity: Some(dec!(2)),
+            ..Default::default()
+        };
+
+        let request = client.execute_order_dry_run(payload).await.unwrap();
+        let body: Value = serde_json::from_str(request["body"].as_str().unwrap()).unwrap();
+        let (price, quantity) = (body["price"].as_str().unwrap(), body["quantity"].as_str().unwrap());
+        assert_eq!((price, quantity), ("150.50", "2.00"));
+
+        let headers = &request["headers"];
+        let timestamp = headers["x-timestamp"].as_str().unwrap();
+        let signee = format!(
+            "instruction=orderExecute&orderType=Limit&price={price}&quantity={quantity}&side=Bid&symbol=SOL_USDC\
+             &timestamp={timestamp}&window=5000"
+        );
+        let signature = STANDARD.decode(headers["x-signature"].as_str().unwrap()).unwrap();
+        let signature = Signature::from_slice(&signature).unwrap();
+        assert!(client.verifier().verify(signee.as_bytes(), &signature).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_execute_order_with_recovery_finds_orphaned_order() {
+        let order = r#"{"orderType":"Limit","id":"111","clientId":7,"symbol":"SOL_USDC","side":"Bid","quantity":"2",
+            "executedQuantity":"0","executedQuoteQuantity":"0","price":"150","timeInForce":"GTC",
+            "selfTradePrevention":"RejectTaker","postOnly":false,"status":"New","createdAt":0}"#;
+        let not_found = || MockResponse::new(404, r#"{"code":"RESOURCE_NOT_FOUND","message":"Order not found"}"#);
+        let server = MockServer::start(vec![
+            MockResponse::new(200, order).delayed(Duration::from_secs(5)),
+            MockResponse::new(200, order),
+            // The order only shows up once the receive window has passed.
+            MockResponse::new(200, order).delayed(Duration::from_secs(5)),
+            not_found(),
+            MockResponse::new(200, order),
+            MockResponse::new(200, order).delayed(Duration::from_secs(5)),
+            not_found(),
+            not_found(),
+        ])
+        .await;
+        let client = test_client_builder(&server)
+            .recv_window(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let payload = ExecuteOrderPayload {
+            symbol: "SOL_USDC".to_string(),
+            side: Side::Bid,
+            order_type: OrderType::Limit,
+            price: Some(dec!(150)),
+            quantity: Some(dec!(2)),
+            ..Default::default()
+        };
+        let timeout = Duration::from_millis(100);
+
+        let err = client.execute_order_with_recovery(payload.clone(), timeout).await;
+        assert!(matches!(err, Err(Error::InvalidRequest(_))));
+
+        let payload = ExecuteOrderPayload {
+            client_id: Some(7),
+            ..payload
+        };
+        let order = client
+            .execute_order_with_recovery(payload.clone(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(order.id(), "111");
+        let requests = server.requests();
+        assert!(requests[0].starts_with("POST /api/v1/order "));
+        assert!(requests[1].starts_with("GET /api/v1/order?symbol=SOL_USDC&clientId=7 "));
+
+        let order = client
+            .execute_order_with_recovery(payload.clone(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(order.id(), "111");
+        assert_eq!(server.requests().len(), 5);
+
+        let err = client.execute_order_with_recovery(payload, timeout).await;
+        assert!(matches!(err, Err(Error::OrderUnconfirmed { client_id: 7 })));
+        assert_eq!(server.requests().len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_execute_orders_with_recovery_after_timeout() {
+        let open = |id: &str, client_id: u32| {
+            format!(
+                r#"[{{"orderType":"Limit","id":"{id}","clientId":{client_id},"symbol":"SOL_USDC","side":"Bid",
+                "quantity":"2","executedQuantity":"0","executedQuoteQuantity":"0","price":"150","timeInForce":"GTC",
+                "selfTradePrevention":"RejectTaker","postOnly":false,"status":"New","createdAt":0}}]"#
+            )
+        };
+        let history = r#"[{"id":"222","createdAt":"2025-01-01T00:00:00","executedQuantity":"2","orderType":"Limit",
+            "price":"151","quantity":"2","selfTradePrevention":"RejectTaker","status":"Filled","side":"Bid",
+            "symbol":"SOL_USDC","clientId":2},{"id":"100","createdAt":"2025-01-01T00:00:00","orderType":"Market",
+            "selfTradePrevention":"RejectTaker","status":"Filled","side":"Ask","symbol":"SOL_USDC"}]"#;
+        let unavailable = || MockResponse::new(503, r#"{"code":"SERVICE_UNAVAILABLE","message":"Unavailable"}"#);
+        let server = MockServer::start(vec![
+            MockResponse::new(200, "[]").delayed(Duration::from_secs(5)),
+            MockResponse::new(200, open("111", 1)),
+            MockResponse::new(200, history),
+            unavailable(),
+            MockResponse::new(200, "[]"),
+            // Looked up again after the receive window, when order 3 has landed.
+            unavailable(),
+            MockResponse::new(200, "[]"),
+            MockResponse::new(200, open("333", 3)),
+            MockResponse::new(200, history),
+        ])
+        .await;
+        let client = test_client_builder(&server)
+            .recv_window(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let order = |symbol: &str, client_id: u32| ExecuteOrderPayload {
+            symbol: symbol.to_string(),
+            side: Side::Bid,
+            order_type: OrderType::Limit,
+            price: Some(dec!(150)),
+            quantity: Some(dec!(2)),
+            client_id: Some(client_id),
+            ..Default::default()
+        };
+        let timeout = Duration::from_millis(100);
+
+        let err = client
+            .execute_orders_with_recovery(vec![order("SOL_USDC", 1), order("SOL_USDC", 1)], timeout)
+            .await;
+        assert!(matches!(err, Err(Error::InvalidRequest(_))));
+
+        let verdicts = client
+            .execute_orders_with_recovery(
+                vec![
+                    order("SOL_USDC", 1),
+                    order("BTC_USDC", 4),
+                    order("SOL_USDC", 2),
+                    order("SOL_USDC", 3),
+                    order("SOL_USDC", 5),
+                ],
+                timeout,
+            )
+            .await
+            .unwrap();
+        assert!(matches!(&verdicts[0], OrderVerdict::Landed(id) if id == "111"));
+        assert!(matches!(&verdicts[1], OrderVerdict::Unknown));
+        assert!(matches!(&verdicts[2], OrderVerdict::Landed(id) if id == "222"));
+        assert!(matches!(&verdicts[3], OrderVerdict::Landed(id) if id == "333"));
+        assert!(matches!(&verdicts[4], OrderVerdict::NotLanded(None)));
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("POST /api/v1/orders "));
+        assert!(requests[1].starts_with("GET /api/v1/orders?symbol=SOL_USDC "));
+        assert!(requests[2].starts_with("GET /wapi/v1/history/orders?symbol=SOL_USDC&limit=1000&sortDirection=Desc "));
+        assert!(requests[3].starts_with("GET /api/v1/orders?symbol=BTC_USDC "));
+        assert!(requests[4].starts_with("GET /wapi/v1/history/orders?symbol=BTC_USDC&limit=1000&sortDirection=Desc "));
+        assert!(requests[5].starts_with("GET /api/v1/orders?symbol=BTC_USDC "));
+        assert!(requests[7].starts_with("GET /api/v1/orders?symbol=SOL_USDC "));
+        assert_eq!(requests.len(), 9);
+    }
+
+    #[tokio::test]
+    async fn test_post_only_guard_rejects_crossing_bid() {
+        let depth = r#"{"asks":[["150.5","1"],["151","2"]],"bids":[["149","3"]],"lastUpdateId":"1","timestamp":1}"#;
+        let server = MockServer::start(vec![MockResponse::new(200, depth)]).await;
+        let client = test_client_builder(&server).post_only_guard(true).build().unwrap();
+        let payload = ExecuteOrderPayload {
+            symbol: "SOL_USDC".to_string(),
+            side: Side::Bid,
+            order_type: OrderType::Limit,
+            price: Some(dec!(150.75)),
+            quantity: Some(dec!(1)),
+            post_only: Some(true),
+            ..Default::default()
+        };
+
+        let err = client.execute_order(payload).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::PostOnlyWouldCross(cross) if cross.opposite == dec!(150.5)
+        ));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("GET /api/v1/depth?symbol=SOL_USDC "));
+    }
+
+    #[tokio::test]
+    async fn test_get_open_orders_includes_pending_triggers() {
+        let orders = r#"[{"orderType":"Limit","id":"1","clientId":null,"symbol":"SOL_USDC","side":"Bid","quantity":"2",
+            "executedQuantity":"0","executedQuoteQuantity":"0","price":"150","timeInForce":"GTC",
+            "selfTradePrevention":"RejectTaker","postOnly":false,"status":"New","createdAt":0},
+            {"orderType":"Market","id":"2","clientId":9,"symbol":"SOL_USDC","side":"Ask","quantity":"2",
+            "executedQuantity":"0","executedQuoteQuantity":"0","triggerPrice":"140","triggerBy":"MarkPrice",
+            "triggerQuantity":"50%","timeInForce":"GTC","selfTradePrevention":"RejectTaker","reduceOnly":true,
+            "status":"TriggerPending","createdAt":0}]"#;
+        let server = MockServer::start(vec![MockResponse::new(200, orders)]).await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let orders = client.get_open_orders(Some("SOL_USDC")).await.unwrap();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].status(), OrderStatus::New);
+        assert_eq!(orders[1].status(), OrderStatus::TriggerPending);
+        let Order::Market(stop) = &orders[1] else {
+            panic!("expected a market order");
+        };
+        assert_eq!(stop.trigger_price, Some(dec!(140)));
+        assert_eq!(stop.trigger_by, Some(TriggerBy::MarkPrice));
+        assert_eq!(stop.trigger_quantity, Some(TriggerQuantity::Percent(dec!(50))));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_open_orders_filtered() {
+        let order = |id: &str, post_only: bool, reduce_only: bool| {
+            format!(
+                r#"{{"orderType":"Limit","id":"{id}","clientId":null,"symbol":"SOL_USDC","side":"Ask","quantity":"2",
+                "executedQuantity":"0","executedQuoteQuantity":"0","price":"150","timeInForce":"GTC",
+                "selfTradePrevention":"RejectTaker","postOnly":{post_only},"reduceOnly":{reduce_only},"status":"New","createdAt":0}}"#
+            )
+        };
+        let open_orders = format!(
+            "[{},{},{}]",
+            order("1", true, false),
+            order("2", false, true),
+            order("3", false, false)
+        );
+        let server = MockServer::start_with(
+            move |request| match request.split(' ').take(2).collect::<Vec<_>>()[..] {
+                ["GET", "/api/v1/orders?symbol=SOL_USDC"] => MockResponse::new(200, open_orders.clone()),
+                ["DELETE", API_ORDER] => {
+                    let id = request
+                        .split(r#""orderId":""#)
+                        .nth(1)
+                        .unwrap()
+                        .split('"')
+                        .next()
+                        .unwrap();
+                    MockResponse::new(200, order(id, false, false))
+                }
+                ["DELETE", API_ORDERS] => MockResponse::new(200, "[]"),
+                _ => MockResponse::new(404, ""),
+            },
+        )
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+        let payload = CancelOpenOrdersPayload {
+            symbol: "SOL_USDC".to_string(),
+        };
+        let cancelled_ids = |results: Vec<Result<Order>>| -> Vec<String> {
+            results.into_iter().map(|r| r.unwrap().id().to_string()).collect()
+        };
+
+        // Clear the passive quotes, keeping the reduce-only order.
+        let quotes = CancelAllFilter {
+            post_only: Some(true),
+            ..Default::default()
+        };
+        let results = client
+            .cancel_open_orders_filtered(payload.clone(), quotes)
+            .await
+            .unwrap();
+        assert_eq!(cancelled_ids(results), ["1"]);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("DELETE /api/v1/order "));
+        assert!(requests[1].ends_with(r#"{"symbol":"SOL_USDC","orderId":"1"}"#));
+
+        let keep_stops = CancelAllFilter {
+            reduce_only: Some(false),
+            ..Default::default()
+        };
+        let results = client
+            .cancel_open_orders_filtered(payload.clone(), keep_stops)
+            .await
+            .unwrap();
+        assert_eq!(cancelled_ids(results), ["1", "3"]);
+
+        // Without a filter, everything is cancelled with a single request.
+        assert!(client
+            .cancel_open_orders_filtered(payload, CancelAllFilter::default())
+            .await
+            .unwrap()
+            .is_empty());
+        let requests = server.requests();
+        let last = requests.last().unwrap();
+        assert!(last.starts_with("DELETE /api/v1/orders "));
+        assert!(last.ends_with(r#"{"symbol":"SOL_USDC"}"#));
+        assert_eq!(requests.len(), 6);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_order_rate_limit_throttles_orders_independently_of_reads() {
+        const ORDER: &str = r#"{"orderType":"Limit","id":"100","clientId":null,"symbol":"SOL_USDC","side":"Bid",
+            "quantity":"1","executedQuantity":"0","executedQuoteQuantity":"0","price":"150","timeInForce":"GTC",
+            "selfTradePrevention":"RejectTaker","postOnly":false,"status":"New","createdAt":0}"#;
+        let server = MockServer::start_with(|request| {
+            if request.starts_with("GET") {
+                MockResponse::new(200, "[]")
+            } else if request.starts_with(&format!("POST {API_ORDERS} ")) {
+                MockResponse::new(200, format!("[{ORDER},{ORDER}]"))
+            } else {
+                MockResponse::new(200, ORDER)
+            }
+        })
+        .await;
+        // A burst of two orders, then one every 100 ms.
+        let client = test_client_builder(&server)
+            .order_rate_limit(crate::RateLimit {
+                requests: 2,
+                interval: Duration::from_millis(200),
+            })
+            .build()
+            .unwrap();
+        let order = ExecuteOrderPayload {
+            symbol: "SOL_USDC".to_string(),
+            side: Side::Bid,
+            order_type: OrderType::Limit,
+            price: Some(dec!(150)),
+            quantity: Some(dec!(1)),
+            ..Default::default()
+        };
+
+        let start = Instant::now();
+        client.execute_order(order.clone()).await.unwrap();
+        client.execute_order(order.clone()).await.unwrap();
+        // The order bucket is empty, but reads and cancels go through at once.
+        for _ in 0..5 {
+            client.get_open_orders(Some("SOL_USDC")).await.unwrap();
+        }
+        client.cancel_order("SOL_USDC", Some("100"), None).await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        client.execute_order(order.clone()).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        // Each order of a batch counts.
+        let results = client.execute_orders(vec![order.clone(), order]).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        assert_eq!(server.requests().len(), 10);
+    }
 }
@@ -5,6 +5,10 @@ use bpx_api_types::rfq::RequestForQuoteUpdate;
 #[cfg(feature = "ws")]
 use tokio::sync::mpsc::Sender;
 
+#[cfg(feature = "ws")]
+use crate::WsStream;
+
+use crate::capabilities::{unsupported_if_missing, Capability};
 use crate::error::Result;
 use crate::BpxClient;
 
@@ -18,19 +22,81 @@ const API_RFQ_STREAM: &str = "account.rfqUpdate";
 
 impl BpxClient {
     pub async fn submit_rfq(&self, payload: RequestForQuotePayload) -> Result<RequestForQuote> {
+        self.require(Capability::Rfq)?;
         let endpoint = format!("{}{}", self.base_url, API_RFQ);
-        let res = self.post(endpoint, payload).await?;
+        let res = self
+            .post(endpoint, payload)
+            .await
+            .map_err(|err| unsupported_if_missing(err, Capability::Rfq))?;
         res.json().await.map_err(Into::into)
     }
 
+    /// Submits a quote answering an open request for quote, as a maker.
+    ///
+    /// A quote is only accepted while the request is open: it must reach the exchange before
+    /// the request's expiry time (`W` in the [`bpx_api_types::rfq::RequestForQuoteUpdate`] that
+    /// announced it), and requests are often refreshed with a new expiry when no quote was
+    /// accepted. Makers should budget their pricing and round trip latency against that window
+    /// rather than a fixed timeout.
     pub async fn submit_quote(&self, payload: QuotePayload) -> Result<Quote> {
+        self.require(Capability::Rfq)?;
         let endpoint = format!("{}{}", self.base_url, API_RFQ_QUOTE);
-        let res = self.post(endpoint, payload).await?;
+        let res = self
+            .post(endpoint, payload)
+            .await
+            .map_err(|err| unsupported_if_missing(err, Capability::Rfq))?;
         res.json().await.map_err(Into::into)
     }
 
+    /// Subscribes to the request for quote updates of the account and returns them as a
+    /// [`WsStream`], for makers reacting to new, refreshed, accepted and expired requests.
+    ///
+    /// Answer requests with [`BpxClient::submit_quote`].
+    #[cfg(feature = "ws")]
+    pub async fn subscribe_rfq(&self) -> Result<WsStream<RequestForQuoteUpdate>> {
+        self.subscribe_stream(API_RFQ_STREAM).await
+    }
+
     #[cfg(feature = "ws")]
-    pub async fn subscribe_to_rfqs(&self, tx: Sender<RequestForQuoteUpdate>) {
-        self.subscribe(API_RFQ_STREAM, tx).await;
+    pub async fn subscribe_to_rfqs(&self, tx: Sender<RequestForQuoteUpdate>) -> Result<()> {
+        self.subscribe(API_RFQ_STREAM, tx).await?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "ws"))]
+mod test {
+    use bpx_api_types::rfq::RequestForQuoteUpdate;
+    use futures_util::StreamExt;
+
+    use crate::test_utils::{MockWsServer, TEST_SECRET};
+    use crate::BpxClient;
+
+    #[tokio::test]
+    async fn test_subscribe_rfq_signs_and_streams_updates() {
+        let server = MockWsServer::start().await;
+        let client = BpxClient::builder()
+            .secret(TEST_SECRET)
+            .ws_url(server.url())
+            .build()
+            .unwrap();
+
+        let mut rfqs = client.subscribe_rfq().await.unwrap();
+        let frames = server.wait_for_frames(1).await;
+        assert_eq!(frames[0]["params"][0], "account.rfqUpdate");
+        assert!(frames[0]["signature"].is_array());
+
+        server.send(
+            r#"{"stream":"account.rfqUpdate","data":{"e":"rfqActive","E":1,"R":42,"s":"SOL_USDC","q":"10","w":1,"W":31,"X":"New","T":1}}"#,
+        );
+        match rfqs.next().await.unwrap() {
+            RequestForQuoteUpdate::RfqActive {
+                rfq_id, expiry_time, ..
+            } => {
+                assert_eq!(rfq_id, 42);
+                assert_eq!(expiry_time, 31);
+            }
+            update => panic!("unexpected update: {update:?}"),
+        }
     }
 }
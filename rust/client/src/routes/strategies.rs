@@ -1,4 +1,5 @@
-use crate::{BpxClient, Result};
+use crate::capabilities::{unsupported_if_missing, Capability};
+use crate::{BpxClient, Error, Result};
 use bpx_api_types::strategies::{Strategy, StrategyHistorySearchParams};
 
 #[doc(hidden)]
@@ -9,6 +10,10 @@ impl BpxClient {
     /// active as they have either been completed, cancelled by the user or cancelled by the
     /// system.
     pub async fn get_strategy_history(&self, search_params: StrategyHistorySearchParams) -> Result<Vec<Strategy>> {
+        self.require(Capability::Strategies)?;
+        search_params
+            .validate()
+            .map_err(|e| Error::InvalidRequest(e.to_string().into()))?;
         let mut query_vec = Vec::new();
 
         if let Some(strategy_id) = search_params.strategy_id {
@@ -35,7 +40,10 @@ impl BpxClient {
             format!("?{}", query_vec.join("&"))
         };
         let url = format!("{}{}{}", self.base_url, API_STRATEGY_HISTORY, query_string);
-        let res = self.get(url).await?;
+        let res = self
+            .get(url)
+            .await
+            .map_err(|err| unsupported_if_missing(err, Capability::Strategies))?;
         res.json().await.map_err(Into::into)
     }
 }
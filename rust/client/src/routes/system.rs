@@ -0,0 +1,153 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use bpx_api_types::system::{Status, StatusType};
+
+use crate::error::{Error, Result};
+use crate::BpxClient;
+
+const API_STATUS: &str = "/api/v1/status";
+const API_PING: &str = "/api/v1/ping";
+const API_TIME: &str = "/api/v1/time";
+
+/// The outcome of one check of [`BpxClient::health_check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentHealth {
+    /// The error that made the check fail, if any.
+    pub error: Option<String>,
+    /// How long the check took.
+    pub latency: Duration,
+}
+
+impl ComponentHealth {
+    /// Returns `true` if the check passed.
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    async fn measure<F: Future<Output = Result<()>>>(check: F) -> Self {
+        let start = Instant::now();
+        let result = check.await;
+        Self {
+            error: result.err().map(|e| e.to_string()),
+            latency: start.elapsed(),
+        }
+    }
+}
+
+/// The result of [`BpxClient::health_check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Health {
+    /// Whether the API is reachable.
+    pub ping: ComponentHealth,
+    /// Whether the exchange is out of maintenance.
+    pub status: ComponentHealth,
+    /// Whether the client's credentials are accepted, if checked.
+    pub auth: Option<ComponentHealth>,
+}
+
+impl Health {
+    /// Returns `true` if every check passed.
+    pub fn is_healthy(&self) -> bool {
+        self.ping.is_ok() && self.status.is_ok() && self.auth.as_ref().is_none_or(ComponentHealth::is_ok)
+    }
+}
+
+impl BpxClient {
+    /// Fetches the system status of the exchange.
+    pub async fn get_status(&self) -> Result<Status> {
+        let url = format!("{}{}", self.base_url, API_STATUS);
+        let res = self.get(url).await?;
+        res.json().await.map_err(Into::into)
+    }
+
+    /// Checks that the API is reachable.
+    pub async fn ping(&self) -> Result<()> {
+        let url = format!("{}{}", self.base_url, API_PING);
+        let res = self.get(url).await?;
+        match res.text().await?.as_str() {
+            "pong" => Ok(()),
+            other => Err(Error::InvalidRequest(
+                format!("unexpected ping response: {other}").into(),
+            )),
+        }
+    }
+
+    /// Fetches the exchange's current time, in milliseconds since the Unix epoch.
+    pub async fn get_system_time(&self) -> Result<u64> {
+        let url = format!("{}{}", self.base_url, API_TIME);
+        let res = self.get(url).await?;
+        res.text()
+            .await?
+            .trim()
+            .parse()
+            .map_err(|e| Error::InvalidRequest(format!("invalid system time: {e}").into()))
+    }
+
+    /// Answers whether the client can trade right now, for readiness probes and supervisors.
+    ///
+    /// Pings the API, checks that the exchange is not in maintenance and, if `check_auth` is
+    /// set, that the client's credentials are accepted by fetching the account settings.
+    /// Read-only clients should pass `false`. Failures are reported in the returned [`Health`]
+    /// rather than as an error.
+    pub async fn health_check(&self, check_auth: bool) -> Health {
+        let ping = ComponentHealth::measure(self.ping()).await;
+        let status = ComponentHealth::measure(async {
+            let status = self.get_status().await?;
+            match status.status {
+                StatusType::Ok => Ok(()),
+                StatusType::Maintenance => Err(Error::InvalidRequest(
+                    format!("exchange in maintenance: {}", status.message.unwrap_or_default()).into(),
+                )),
+            }
+        })
+        .await;
+        let auth = match check_auth {
+            true => Some(ComponentHealth::measure(async { self.get_account().await.map(|_| ()) }).await),
+            false => None,
+        };
+        Health { ping, status, auth }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::{test_client_builder, MockResponse, MockServer};
+
+    #[tokio::test]
+    async fn test_health_check_reports_each_component() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, "pong"),
+            MockResponse::new(200, r#"{"status":"Maintenance","message":"upgrade"}"#),
+            MockResponse::new(401, "invalid signature"),
+        ])
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let health = client.health_check(true).await;
+        assert!(!health.is_healthy());
+        assert!(health.ping.is_ok());
+        assert!(health.status.error.as_ref().unwrap().contains("upgrade"));
+        assert!(!health.auth.unwrap().is_ok());
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("GET /api/v1/ping"));
+        assert!(requests[1].starts_with("GET /api/v1/status"));
+        assert!(requests[2].starts_with("GET /api/v1/account"));
+    }
+
+    #[tokio::test]
+    async fn test_health_check_without_auth() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, "pong"),
+            MockResponse::new(200, r#"{"status":"Ok","message":null}"#),
+        ])
+        .await;
+        let client = test_client_builder(&server).build().unwrap();
+
+        let health = client.health_check(false).await;
+        assert!(health.is_healthy());
+        assert_eq!(health.auth, None);
+        assert_eq!(server.requests().len(), 2);
+    }
+}
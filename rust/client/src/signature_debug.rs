@@ -0,0 +1,85 @@
+//! Diagnostics for requests rejected with an invalid or expired signature.
+
+use std::collections::BTreeMap;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ed25519_dalek::Signer;
+
+use crate::{now_millis, BpxClient};
+
+/// Everything that goes into signing a request, as returned by
+/// [`BpxClient::diagnose_signature`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureDebug {
+    /// The exact string that is signed.
+    pub signee: String,
+    /// The request parameters, in the order they appear in the signee.
+    pub params: Vec<(String, String)>,
+    /// The `X-Timestamp` header, in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The `X-Window` header, in milliseconds.
+    pub window: u32,
+    /// The base64 encoded `X-Signature` header.
+    pub signature: String,
+    /// The base64 encoded `X-API-Key` header.
+    pub api_key: String,
+}
+
+impl BpxClient {
+    /// Signs the given instruction and parameters the way a request would be signed, and
+    /// returns every intermediate value to compare against the exchange's expected format.
+    ///
+    /// The parameters are the request's JSON body fields and query parameters, with values
+    /// formatted as they are sent. The returned signee is a valid signature input for this
+    /// client's key, so keep it out of production logs.
+    pub fn diagnose_signature(&self, instruction: &str, params: &BTreeMap<String, String>) -> SignatureDebug {
+        let timestamp = now_millis();
+        let window = self.recv_window;
+
+        let mut signee = format!("instruction={instruction}");
+        for (key, value) in params {
+            signee.push_str(&format!("&{key}={value}"));
+        }
+        signee.push_str(&format!("&timestamp={timestamp}&window={window}"));
+
+        SignatureDebug {
+            signature: STANDARD.encode(self.signer.sign(signee.as_bytes()).to_bytes()),
+            api_key: STANDARD.encode(self.verifier.to_bytes()),
+            params: params.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            signee,
+            timestamp,
+            window,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ed25519_dalek::{Signature, Verifier};
+
+    use super::*;
+    use crate::test_utils::TEST_SECRET;
+
+    #[test]
+    fn test_diagnose_signature() {
+        let client = BpxClient::builder().secret(TEST_SECRET).build().unwrap();
+        let params = BTreeMap::from([
+            ("symbol".to_string(), "SOL_USDC".to_string()),
+            ("orderId".to_string(), "1".to_string()),
+        ]);
+
+        let debug = client.diagnose_signature("orderCancel", &params);
+        assert_eq!(
+            debug.signee,
+            format!(
+                "instruction=orderCancel&orderId=1&symbol=SOL_USDC&timestamp={}&window=5000",
+                debug.timestamp
+            )
+        );
+        assert_eq!(debug.params[0].0, "orderId");
+
+        let signature = STANDARD.decode(&debug.signature).unwrap();
+        let signature = Signature::from_slice(&signature).unwrap();
+        assert!(client.verifier().verify(debug.signee.as_bytes(), &signature).is_ok());
+    }
+}
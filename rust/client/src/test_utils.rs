@@ -0,0 +1,337 @@
+//! Helpers for testing the client against a local HTTP server.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::BpxClientBuilder;
+
+/// A base64 encoded ED25519 secret used by tests.
+pub(crate) const TEST_SECRET: &str = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=";
+
+/// A `/api/v1/market` response body for `SOL_USDC`.
+pub(crate) const TEST_MARKET_JSON: &str = r#"{
+    "symbol": "SOL_USDC",
+    "baseSymbol": "SOL",
+    "quoteSymbol": "USDC",
+    "marketType": "SPOT",
+    "filters": {
+        "price": { "minPrice": "0.01", "tickSize": "0.01" },
+        "quantity": { "minQuantity": "0.01", "stepSize": "0.01" }
+    },
+    "orderBookState": "Open",
+    "createdAt": "2025-01-01T00:00:00"
+}"#;
+
+/// Returns a builder targeting the given mock server.
+pub(crate) fn test_client_builder(server: &MockServer) -> BpxClientBuilder {
+    BpxClientBuilder::new().base_url(server.url()).secret(TEST_SECRET)
+}
+
+/// A canned HTTP response served by [`MockServer`].
+pub(crate) struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+    sent: Option<usize>,
+    content_length: Option<u64>,
+    delay: Duration,
+}
+
+impl MockResponse {
+    pub(crate) fn new(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+            sent: None,
+            content_length: None,
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Waits for `delay` before sending the response.
+    pub(crate) fn delayed(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Closes the connection after sending only the first `sent` bytes of the body, while
+    /// still announcing its full length.
+    pub(crate) fn truncated(mut self, sent: usize) -> Self {
+        self.sent = Some(sent);
+        self
+    }
+
+    /// Announces a `Content-Length` of `length` instead of the body's actual length.
+    pub(crate) fn with_content_length(mut self, length: u64) -> Self {
+        self.content_length = Some(length);
+        self
+    }
+
+    pub(crate) fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    fn to_http(&self) -> String {
+        let mut res = format!(
+            "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            self.content_length.unwrap_or(self.body.len() as u64)
+        );
+        for (name, value) in &self.headers {
+            res.push_str(&format!("{name}: {value}\r\n"));
+        }
+        res.push_str("\r\n");
+        res.push_str(&self.body[..self.sent.unwrap_or(self.body.len())]);
+        res
+    }
+}
+
+/// A minimal HTTP server serving canned responses in order and recording the requests it
+/// receives. Unexpected requests get a `500` response.
+pub(crate) struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+/// Picks the response to a raw request.
+type Responder = Arc<dyn Fn(&str) -> MockResponse + Send + Sync>;
+
+impl MockServer {
+    pub(crate) async fn start(responses: Vec<MockResponse>) -> Self {
+        Self::start_with(in_order(responses)).await
+    }
+
+    /// Starts a server answering each request with the response `responder` picks for it,
+    /// for requests sent concurrently.
+    pub(crate) async fn start_with(responder: impl Fn(&str) -> MockResponse + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let responder: Responder = Arc::new(responder);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, responder.clone(), recorded.clone()));
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Starts a server listening on a Unix domain socket at `path`, serving canned responses
+    /// in order. Its URL is `http://localhost`, for clients that connect through the socket.
+    #[cfg(unix)]
+    pub(crate) async fn start_unix(path: &std::path::Path, responses: Vec<MockResponse>) -> Self {
+        let listener = tokio::net::UnixListener::bind(path).unwrap();
+        let responder: Responder = Arc::new(in_order(responses));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, responder.clone(), recorded.clone()));
+            }
+        });
+
+        Self {
+            url: "http://localhost".to_string(),
+            requests,
+        }
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the raw requests received so far.
+    pub(crate) fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Returns a responder answering with `responses` in order, then with `500` errors.
+fn in_order(responses: Vec<MockResponse>) -> impl Fn(&str) -> MockResponse + Send + Sync + 'static {
+    let responses = Mutex::new(VecDeque::from(responses));
+    move |_| {
+        responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| MockResponse::new(500, "no mock response"))
+    }
+}
+
+async fn serve<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    responder: Responder,
+    requests: Arc<Mutex<Vec<String>>>,
+) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+    let response = responder(&request);
+    requests.lock().unwrap().push(request);
+    tokio::time::sleep(response.delay).await;
+    let _ = stream.write_all(response.to_http().as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn read_request<S: AsyncRead + Unpin>(stream: &mut S) -> Option<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        let text = String::from_utf8_lossy(&buf);
+        if let Some(head_end) = text.find("\r\n\r\n") {
+            let content_length = text[..head_end]
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())
+                        .flatten()
+                })
+                .unwrap_or(0);
+            if buf.len() >= head_end + 4 + content_length {
+                return Some(String::from_utf8_lossy(&buf).into_owned());
+            }
+        }
+    }
+}
+
+/// A minimal WebSocket server recording the text frames it receives and broadcasting frames
+/// to every open connection.
+#[cfg(feature = "ws")]
+pub(crate) struct MockWsServer {
+    url: String,
+    frames: Arc<Mutex<Vec<String>>>,
+    user_agents: Arc<Mutex<Vec<String>>>,
+    connections: Arc<std::sync::atomic::AtomicUsize>,
+    /// Frames to send to every connection, `None` closing them.
+    outgoing: tokio::sync::broadcast::Sender<Option<String>>,
+    listener: tokio::task::JoinHandle<()>,
+}
+
+#[cfg(feature = "ws")]
+impl MockWsServer {
+    pub(crate) async fn start() -> Self {
+        use futures_util::{SinkExt, StreamExt};
+        use std::sync::atomic::Ordering;
+        use tokio_tungstenite::tungstenite::handshake::server::Request;
+        use tokio_tungstenite::tungstenite::http::header::USER_AGENT;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let user_agents = Arc::new(Mutex::new(Vec::new()));
+        let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (outgoing, _) = tokio::sync::broadcast::channel::<Option<String>>(64);
+
+        let (recorded, counter, sender) = (frames.clone(), connections.clone(), outgoing.clone());
+        let agents = user_agents.clone();
+        let listener = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                // The callback's signature is dictated by `tungstenite`.
+                #[allow(clippy::result_large_err)]
+                let record_user_agent = |request: &Request, response| {
+                    let user_agent = request.headers().get(USER_AGENT).and_then(|v| v.to_str().ok());
+                    agents.lock().unwrap().push(user_agent.unwrap_or_default().to_string());
+                    Ok(response)
+                };
+                let Ok(mut socket) = tokio_tungstenite::accept_hdr_async(stream, record_user_agent).await else {
+                    continue;
+                };
+                counter.fetch_add(1, Ordering::SeqCst);
+                let (recorded, mut outgoing) = (recorded.clone(), sender.subscribe());
+                tokio::spawn(async move {
+                    loop {
+                        tokio::select! {
+                            message = socket.next() => match message {
+                                Some(Ok(Message::Text(text))) => recorded.lock().unwrap().push(text.to_string()),
+                                Some(Ok(_)) => {}
+                                _ => break,
+                            },
+                            message = outgoing.recv() => match message {
+                                Ok(Some(text)) => {
+                                    if socket.send(Message::Text(text.into())).await.is_err() {
+                                        break;
+                                    }
+                                }
+                                _ => {
+                                    let _ = socket.close(None).await;
+                                    break;
+                                }
+                            },
+                        }
+                    }
+                });
+            }
+        });
+
+        Self {
+            url,
+            frames,
+            user_agents,
+            connections,
+            outgoing,
+            listener,
+        }
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Sends a text frame to every open connection.
+    pub(crate) fn send(&self, text: &str) {
+        self.outgoing.send(Some(text.to_string())).unwrap();
+    }
+
+    /// Closes every open connection while still accepting new ones.
+    pub(crate) fn drop_connections(&self) {
+        let _ = self.outgoing.send(None);
+    }
+
+    /// Stops accepting connections and closes the open ones.
+    pub(crate) fn shutdown(&self) {
+        self.listener.abort();
+        self.drop_connections();
+    }
+
+    /// Returns the `User-Agent` of each connection accepted so far.
+    pub(crate) fn user_agents(&self) -> Vec<String> {
+        self.user_agents.lock().unwrap().clone()
+    }
+
+    /// Returns the number of connections accepted so far.
+    pub(crate) fn connections(&self) -> usize {
+        self.connections.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Waits until at least `count` frames were received and returns them parsed as JSON.
+    pub(crate) async fn wait_for_frames(&self, count: usize) -> Vec<serde_json::Value> {
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                let frames = self.frames.lock().unwrap().clone();
+                if frames.len() >= count {
+                    return frames.iter().map(|f| serde_json::from_str(f).unwrap()).collect();
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("timed out waiting for frames")
+    }
+}
@@ -0,0 +1,119 @@
+//! Splitting a stream carrying several markets into one stream per market.
+
+use std::collections::HashMap;
+
+use bpx_api_types::SymbolUpdate;
+use futures_util::StreamExt;
+use tokio::sync::mpsc::{self, error::TrySendError, Sender};
+
+use super::{WsStream, STREAM_CAPACITY};
+
+impl<T: SymbolUpdate + Send + 'static> WsStream<T> {
+    /// Splits the stream by market, returning a stream that yields each symbol with its own
+    /// stream of updates the first time an update for it arrives.
+    ///
+    /// Each per-symbol stream buffers up to `capacity` updates (at least one). When a
+    /// consumer falls behind and its buffer is full, further updates for that symbol are
+    /// dropped with a warning rather than holding up the other symbols. Updates for a symbol
+    /// whose stream was dropped are discarded, and so are updates for new symbols once the
+    /// returned stream is dropped.
+    ///
+    /// ```no_run
+    /// # async fn example(client: bpx_api_client::BpxClient) -> bpx_api_client::Result<()> {
+    /// use bpx_api_client::types::markets::TickerUpdate;
+    /// use futures_util::StreamExt;
+    ///
+    /// let tickers = client
+    ///     .subscribe_streams::<TickerUpdate>(&["bookTicker.SOL_USDC", "bookTicker.BTC_USDC"])
+    ///     .await?;
+    /// let mut symbols = tickers.demux_by_symbol(64);
+    /// while let Some((symbol, mut updates)) = symbols.next().await {
+    ///     tokio::spawn(async move {
+    ///         while let Some(update) = updates.next().await {
+    ///             println!("{symbol}: {}", update.bid_price);
+    ///         }
+    ///     });
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn demux_by_symbol(self, capacity: usize) -> WsStream<(String, WsStream<T>)> {
+        let (tx, rx) = mpsc::channel(STREAM_CAPACITY);
+        tokio::spawn(demux(self, tx, capacity.max(1)));
+        WsStream { rx }
+    }
+}
+
+/// Routes the updates of `source` to one channel per symbol until the source ends or every
+/// receiver is gone.
+async fn demux<T: SymbolUpdate>(mut source: WsStream<T>, symbols: Sender<(String, WsStream<T>)>, capacity: usize) {
+    // `None` once the symbol's receiver is dropped, or if it could not be handed out.
+    let mut routes: HashMap<String, Option<Sender<T>>> = HashMap::new();
+    while let Some(update) = source.next().await {
+        if !routes.contains_key(update.symbol()) {
+            let symbol = update.symbol().to_string();
+            let (tx, rx) = mpsc::channel(capacity);
+            let route = symbols.send((symbol.clone(), WsStream { rx })).await.ok().map(|_| tx);
+            routes.insert(symbol, route);
+        }
+        let route = routes.get_mut(update.symbol()).expect("route was just inserted");
+        let Some(tx) = route else {
+            continue;
+        };
+        match tx.try_send(update) {
+            Ok(()) => {}
+            Err(TrySendError::Full(update)) => {
+                tracing::warn!("{}: subscriber lagged behind, dropped an update", update.symbol());
+            }
+            Err(TrySendError::Closed(_)) => *route = None,
+        }
+        if symbols.is_closed() && routes.values().all(Option::is_none) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bpx_api_types::markets::TickerUpdate;
+
+    use super::*;
+    use crate::test_utils::{MockWsServer, TEST_SECRET};
+    use crate::BpxClient;
+
+    fn ticker(symbol: &str, update_id: u64) -> String {
+        format!(
+            r#"{{"stream":"bookTicker.{symbol}","data":{{"e":"bookTicker","E":1,"s":"{symbol}","a":"101","A":"1","b":"100","B":"2","u":{update_id},"T":1}}}}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_demux_routes_updates_by_symbol() {
+        let server = MockWsServer::start().await;
+        let client = BpxClient::builder()
+            .secret(TEST_SECRET)
+            .ws_url(server.url())
+            .build()
+            .unwrap();
+        let tickers = client
+            .subscribe_streams::<TickerUpdate>(&["bookTicker.SOL_USDC", "bookTicker.BTC_USDC"])
+            .await
+            .unwrap();
+        let mut symbols = tickers.demux_by_symbol(8);
+        server.wait_for_frames(1).await;
+
+        server.send(&ticker("SOL_USDC", 1));
+        server.send(&ticker("BTC_USDC", 2));
+        server.send(&ticker("SOL_USDC", 3));
+
+        let (symbol, mut sol) = symbols.next().await.unwrap();
+        assert_eq!(symbol, "SOL_USDC");
+        let (symbol, mut btc) = symbols.next().await.unwrap();
+        assert_eq!(symbol, "BTC_USDC");
+
+        assert_eq!(sol.next().await.unwrap().update_id, 1);
+        assert_eq!(sol.next().await.unwrap().update_id, 3);
+        assert_eq!(btc.next().await.unwrap().update_id, 2);
+        assert!(btc.rx.try_recv().is_err());
+    }
+}
@@ -1,79 +1,781 @@
+//! WebSocket streams.
+//!
+//! All clones of a [`BpxClient`] share a single connection, opened on the first subscription.
+//! Each stream is subscribed once on that connection and its messages are fanned out to every
+//! channel subscribed to it. If the connection drops it is re-established according to the
+//! client's [`ReconnectPolicy`] and the streams are subscribed again.
+//!
+//! Subscribing changes the shared connection: once one clone subscribes to a stream, other
+//! clones subscribing to it get its messages without a second subscription being sent, and
+//! [`BpxClient::unsubscribe`] from any clone ends it for all of them. Each [`WsStream`] or
+//! channel, on the other hand, is an independent receiver: every consumer gets every message
+//! of its streams, and dropping one consumer doesn't affect the others.
+
+use std::any::Any;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
-use ed25519_dalek::Signer;
-use futures_util::{SinkExt, StreamExt};
+use bpx_api_types::markets::{OrderBookDepthUpdate, TickerStatisticsUpdate};
+use ed25519_dalek::{Signer, SigningKey};
+use futures_util::{SinkExt, Stream, StreamExt};
 use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
-use tokio::sync::mpsc::Sender;
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc::{self, Sender, UnboundedReceiver, UnboundedSender, WeakSender};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::USER_AGENT;
 use tokio_tungstenite::tungstenite::protocol::Message;
-use tokio_tungstenite::{connect_async, tungstenite::Utf8Bytes};
+use tokio_tungstenite::tungstenite::{self, Utf8Bytes};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+use crate::error::{Error, Result};
+use crate::{now_millis, BpxClient};
+
+mod demux;
+mod order_book;
+mod validate;
+
+pub use order_book::OrderBookOptions;
+pub use validate::Misaligned;
+
+/// Number of messages buffered per stream for subscribers that fall behind.
+const STREAM_CAPACITY: usize = 1024;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Controls how the WebSocket connection is re-established after it drops.
+///
+/// Reconnection attempts wait for an exponential backoff with full jitter: a random delay
+/// between zero and `base_delay * 2^attempt`, capped at `max_delay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Maximum number of consecutive reconnection attempts, or `None` to retry forever.
+    /// Once exhausted, every subscription is closed.
+    pub max_reconnect_attempts: Option<usize>,
+    /// Upper bound of the delay before the first attempt.
+    pub base_delay: Duration,
+    /// Upper bound of the delay before any attempt.
+    pub max_delay: Duration,
+}
+
+impl ReconnectPolicy {
+    /// Returns a random delay before the given (zero based) reconnection attempt.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let cap = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let cap_nanos = u64::try_from(cap.as_nanos()).unwrap_or(u64::MAX);
+        let random = RandomState::new().hash_one(attempt);
+        Duration::from_nanos(random % cap_nanos.saturating_add(1))
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_reconnect_attempts: None,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of the shared WebSocket connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// No connection has been opened yet, or it was closed.
+    Disconnected,
+    /// The connection is open.
+    Connected,
+    /// The connection dropped and is being re-established.
+    Reconnecting {
+        /// The current attempt, starting at 1.
+        attempt: usize,
+    },
+    /// The connection could not be re-established within
+    /// [`ReconnectPolicy::max_reconnect_attempts`] and every subscription was closed.
+    Failed {
+        /// The number of attempts made.
+        attempts: usize,
+    },
+}
+
+/// WebSocket state shared by all clones of a client.
+pub(crate) struct WsState {
+    connection: tokio::sync::Mutex<Option<UnboundedSender<Message>>>,
+    streams: Mutex<HashMap<String, Subscription>>,
+    pub(crate) reconnect: ReconnectPolicy,
+    state: watch::Sender<ConnectionState>,
+    /// Latency of the last message carrying an event time.
+    latency: Mutex<Option<Duration>>,
+}
+
+impl fmt::Debug for WsState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let streams = self.streams.lock().expect("ws streams poisoned");
+        f.debug_struct("WsState").field("streams", &streams.keys()).finish()
+    }
+}
+
+impl WsState {
+    pub(crate) fn new(reconnect: ReconnectPolicy) -> Self {
+        Self {
+            connection: Default::default(),
+            streams: Default::default(),
+            reconnect,
+            state: watch::Sender::new(ConnectionState::Disconnected),
+            latency: Mutex::new(None),
+        }
+    }
+
+    /// Routes a message received on the connection to the subscribers of its stream.
+    fn dispatch(&self, text: &str) {
+        let Ok(value) = serde_json::from_str::<Value>(text) else {
+            return;
+        };
+        if let Some(payload) = value.get("error") {
+            tracing::error!("Websocket Error Response: {}", payload);
+            return;
+        }
+        let (Some(stream), Some(data)) = (value.get("stream").and_then(Value::as_str), value.get("data")) else {
+            return;
+        };
+        if let Some(event_time) = data.get("E").and_then(Value::as_i64) {
+            *self.latency.lock().expect("ws latency poisoned") = Some(latency_since(event_time));
+        }
+        if let Some(subscription) = self.streams.lock().expect("ws streams poisoned").get(stream) {
+            let _ = subscription.data.send(data.clone());
+        }
+    }
+}
+
+/// A stream subscribed on the shared connection.
+struct Subscription {
+    data: broadcast::Sender<Value>,
+    forwarders: Vec<Forwarder>,
+}
+
+impl Subscription {
+    fn new() -> Self {
+        Self {
+            data: broadcast::channel(STREAM_CAPACITY).0,
+            forwarders: Vec::new(),
+        }
+    }
 
-use crate::{now_millis, BpxClient, BACKPACK_WS_URL, DEFAULT_WINDOW};
+    /// Returns whether messages are already forwarded to `tx`.
+    fn forwards_to<T: 'static>(&self, tx: &Sender<T>) -> bool {
+        self.forwarders.iter().any(|f| {
+            !f.task.is_finished()
+                && f.channel
+                    .downcast_ref::<WeakSender<T>>()
+                    .and_then(WeakSender::upgrade)
+                    .is_some_and(|sender| sender.same_channel(tx))
+        })
+    }
+}
+
+/// Messages of a single WebSocket stream, returned by the typed `subscribe_*` methods.
+///
+/// Ends once the stream is unsubscribed or the connection is closed for good. Dropping it
+/// stops delivery to this subscriber, while the stream stays subscribed on the connection.
+#[derive(Debug)]
+pub struct WsStream<T> {
+    rx: mpsc::Receiver<T>,
+}
+
+impl<T> Stream for WsStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// A task deserializing a stream's messages into a subscriber's channel.
+struct Forwarder {
+    channel: Box<dyn Any + Send + Sync>,
+    task: JoinHandle<()>,
+}
 
 impl BpxClient {
     /// Subscribes to a private WebSocket stream and sends messages of type `T` through a transmitter channel.
-    pub async fn subscribe<T>(&self, stream: &str, tx: Sender<T>)
+    ///
+    /// The private streams are `account.orderUpdate`, `account.positionUpdate` and
+    /// `account.rfqUpdate`. Balance, deposit and withdrawal changes are not streamed, use
+    /// [`BpxClient::get_balances`], [`BpxClient::get_deposits`] and
+    /// [`BpxClient::get_withdrawals`] to track them. Neither is the progress of strategies:
+    /// the exchange has no strategy stream, and [`BpxClient::get_strategy_history`] only
+    /// returns strategies once they have stopped.
+    ///
+    /// See [`BpxClient::subscribe_multiple`].
+    pub async fn subscribe<T>(&self, stream: &str, tx: Sender<T>) -> Result<Vec<String>>
     where
         T: DeserializeOwned + Send + 'static,
     {
-        self.internal_subscribe(&[stream], tx).await
+        self.subscribe_multiple(&[stream], tx).await
     }
 
     /// Subscribes to multiple private WebSocket streams and sends messages of type `T` through a transmitter channel.
-    pub async fn subscribe_multiple<T>(&self, stream: &[&str], tx: Sender<T>)
+    ///
+    /// Streams already subscribed on the connection are not subscribed again, and a channel
+    /// already receiving a stream is not added twice. Returns the streams that were newly
+    /// subscribed on the connection. The channel stops receiving messages once the stream is
+    /// unsubscribed or the connection closes.
+    pub async fn subscribe_multiple<T>(&self, streams: &[&str], tx: Sender<T>) -> Result<Vec<String>>
     where
         T: DeserializeOwned + Send + 'static,
     {
-        self.internal_subscribe(stream, tx).await
+        let mut added = Vec::new();
+        {
+            let mut subscriptions = self.ws.streams.lock().expect("ws streams poisoned");
+            for &stream in streams {
+                let subscription = subscriptions.entry(stream.to_string()).or_insert_with(|| {
+                    added.push(stream.to_string());
+                    Subscription::new()
+                });
+                subscription.forwarders.retain(|f| !f.task.is_finished());
+                if subscription.forwards_to(&tx) {
+                    continue;
+                }
+                subscription.forwarders.push(Forwarder {
+                    channel: Box::new(tx.downgrade()),
+                    task: tokio::spawn(forward(subscription.data.subscribe(), tx.clone())),
+                });
+            }
+        }
+
+        if !added.is_empty() {
+            if let Err(err) = self
+                .ws_send(subscribe_message(&self.signer, self.recv_window, &added))
+                .await
+            {
+                let mut subscriptions = self.ws.streams.lock().expect("ws streams poisoned");
+                for stream in &added {
+                    subscriptions.remove(stream);
+                }
+                return Err(err);
+            }
+            tracing::debug!("Subscribed to {added:#?} streams...");
+        }
+        Ok(added)
     }
 
-    async fn internal_subscribe<T>(&self, stream: &[&str], tx: Sender<T>)
+    /// Subscribes to a stream and returns its messages as a [`WsStream`].
+    ///
+    /// Like every subscription, the stream shares the client's single connection.
+    pub async fn subscribe_stream<T>(&self, stream: &str) -> Result<WsStream<T>>
     where
         T: DeserializeOwned + Send + 'static,
     {
-        let timestamp = now_millis();
-        let window = DEFAULT_WINDOW;
-        let message = format!("instruction=subscribe&timestamp={timestamp}&window={window}");
-
-        let verifying_key = STANDARD.encode(self.verifier.to_bytes());
-        let signature = STANDARD.encode(self.signer.sign(message.as_bytes()).to_bytes());
-
-        let subscribe_message = json!({
-            "method": "SUBSCRIBE",
-            "params": stream,
-            "signature": [verifying_key, signature, timestamp.to_string(), window.to_string()],
-        });
-
-        let ws_url = self.ws_url.as_deref().unwrap_or(BACKPACK_WS_URL);
-        let (mut ws_stream, _) = connect_async(ws_url).await.expect("Error connecting to WebSocket");
-        ws_stream
-            .send(Message::Text(Utf8Bytes::from(subscribe_message.to_string())))
-            .await
-            .expect("Error subscribing to WebSocket");
-
-        tracing::debug!("Subscribed to {stream:#?} streams...");
-
-        while let Some(message) = ws_stream.next().await {
-            match message {
-                Ok(msg) => match msg {
-                    Message::Text(text) => {
-                        if let Ok(value) = serde_json::from_str::<Value>(&text) {
-                            if let Some(payload) = value.get("data") {
-                                if let Ok(data) = T::deserialize(payload) {
-                                    if tx.send(data).await.is_err() {
-                                        tracing::error!("Failed to send message through the channel");
-                                    }
-                                }
-                            } else if let Some(payload) = value.get("error") {
-                                tracing::error!("Websocket Error Response: {}", payload);
-                            }
-                        }
+        let (tx, rx) = mpsc::channel(STREAM_CAPACITY);
+        self.subscribe(stream, tx).await?;
+        Ok(WsStream { rx })
+    }
+
+    /// Subscribes to several streams of the same type and returns their messages merged into
+    /// one [`WsStream`], for example the tickers of several markets. Use
+    /// [`WsStream::demux_by_symbol`] to split them by market again.
+    pub async fn subscribe_streams<T>(&self, streams: &[&str]) -> Result<WsStream<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(STREAM_CAPACITY);
+        self.subscribe_multiple(streams, tx).await?;
+        Ok(WsStream { rx })
+    }
+
+    /// Subscribes to the 24h ticker statistics of a market.
+    pub async fn subscribe_ticker(&self, symbol: &str) -> Result<WsStream<TickerStatisticsUpdate>> {
+        self.subscribe_stream(&format!("ticker.{symbol}")).await
+    }
+
+    /// Subscribes to the order book depth updates of a market.
+    pub async fn subscribe_depth(&self, symbol: &str) -> Result<WsStream<OrderBookDepthUpdate>> {
+        self.subscribe_stream(&format!("depth.{symbol}")).await
+    }
+
+    /// Subscribes to any stream and returns its messages as untyped JSON, the `data` field of
+    /// each frame.
+    ///
+    /// This is the forward-compatibility hatch for streams the crate doesn't model yet: new
+    /// venue streams can be consumed as soon as they are available, and parsed with
+    /// [`serde_json::from_value`] into user-defined types. The stream shares the client's
+    /// connection and is subscribed again after a reconnect like any other.
+    pub async fn subscribe_raw(&self, stream: &str) -> Result<WsStream<Value>> {
+        self.subscribe_stream(stream).await
+    }
+
+    /// Unsubscribes from the given streams, closing them for every channel receiving them.
+    ///
+    /// Only streams currently subscribed are sent to the server. Returns the streams that were
+    /// unsubscribed.
+    pub async fn unsubscribe(&self, streams: &[&str]) -> Result<Vec<String>> {
+        let removed: Vec<String> = {
+            let mut subscriptions = self.ws.streams.lock().expect("ws streams poisoned");
+            streams
+                .iter()
+                .filter_map(|&stream| subscriptions.remove_entry(stream).map(|(stream, _)| stream))
+                .collect()
+        };
+        if !removed.is_empty() {
+            let message = json!({ "method": "UNSUBSCRIBE", "params": removed });
+            self.ws_send(message).await?;
+        }
+        Ok(removed)
+    }
+
+    /// Returns the streams currently subscribed on the connection.
+    pub fn subscriptions(&self) -> Vec<String> {
+        self.ws
+            .streams
+            .lock()
+            .expect("ws streams poisoned")
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns how long before it was received the last message carrying an event time was
+    /// emitted by the exchange, or `None` if no such message was received yet.
+    ///
+    /// The latency is the local receive time minus the message's `E` event time, so it is
+    /// only as accurate as the local clock's synchronization with the exchange's. A clock
+    /// running behind can make messages appear to arrive before they were emitted, in which
+    /// case the latency is reported as zero. Compare with [`BpxClient::get_system_time`] to
+    /// estimate the offset.
+    pub fn last_latency(&self) -> Option<Duration> {
+        *self.ws.latency.lock().expect("ws latency poisoned")
+    }
+
+    /// Returns a receiver tracking the state of the shared connection.
+    pub fn connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.ws.state.subscribe()
+    }
+
+    /// Sends a message on the shared connection, connecting first if needed.
+    async fn ws_send(&self, message: Value) -> Result<()> {
+        let mut connection = self.ws.connection.lock().await;
+        let sender = match connection.as_ref() {
+            Some(sender) if !sender.is_closed() => sender,
+            _ => {
+                let socket = connect(self.ws_url(), self.user_agent()).await.map_err(Box::new)?;
+                self.ws.state.send_replace(ConnectionState::Connected);
+                let (tx, rx) = mpsc::unbounded_channel();
+                let endpoint = Endpoint {
+                    url: self.ws_url().to_string(),
+                    user_agent: self.user_agent().to_string(),
+                    signer: self.signer.clone(),
+                    window: self.recv_window,
+                    state: Arc::downgrade(&self.ws),
+                };
+                tokio::spawn(run(endpoint, socket, rx));
+                connection.insert(tx)
+            }
+        };
+        sender
+            .send(Message::Text(Utf8Bytes::from(message.to_string())))
+            .map_err(|_| Error::WebSocketClosed)
+    }
+}
+
+/// Opens a WebSocket connection, sending the client's `User-Agent` with the handshake.
+async fn connect(url: &str, user_agent: &str) -> std::result::Result<Socket, tungstenite::Error> {
+    let mut request = url.into_client_request()?;
+    let user_agent = user_agent.parse().map_err(tungstenite::http::Error::from)?;
+    request.headers_mut().insert(USER_AGENT, user_agent);
+    Ok(connect_async(request).await?.0)
+}
+
+/// Builds a signed `SUBSCRIBE` request for the given streams.
+fn subscribe_message(signer: &SigningKey, window: u32, streams: &[String]) -> Value {
+    let timestamp = now_millis();
+    let message = format!("instruction=subscribe&timestamp={timestamp}&window={window}");
+
+    let verifying_key = STANDARD.encode(signer.verifying_key().to_bytes());
+    let signature = STANDARD.encode(signer.sign(message.as_bytes()).to_bytes());
+
+    json!({
+        "method": "SUBSCRIBE",
+        "params": streams,
+        "signature": [verifying_key, signature, timestamp.to_string(), window.to_string()],
+    })
+}
+
+/// What the connection task needs to reconnect, without keeping the client alive.
+struct Endpoint {
+    url: String,
+    user_agent: String,
+    signer: SigningKey,
+    window: u32,
+    state: Weak<WsState>,
+}
+
+/// Drives the shared connection, reconnecting when it drops, until every client handle is
+/// dropped or the reconnection attempts are exhausted.
+async fn run(endpoint: Endpoint, mut socket: Socket, mut outgoing: UnboundedReceiver<Message>) {
+    while drive(&mut socket, &mut outgoing, &endpoint.state).await {
+        let Some(state) = endpoint.state.upgrade() else {
+            return;
+        };
+        match reconnect(&endpoint, &state).await {
+            Some(reconnected) => socket = reconnected,
+            None => {
+                // Dropping the subscriptions closes the subscribers' channels.
+                state.streams.lock().expect("ws streams poisoned").clear();
+                return;
+            }
+        }
+    }
+}
+
+/// Pumps messages in both directions. Returns `true` if the connection was lost, or `false`
+/// once every client handle is dropped.
+async fn drive(socket: &mut Socket, outgoing: &mut UnboundedReceiver<Message>, state: &Weak<WsState>) -> bool {
+    loop {
+        tokio::select! {
+            message = outgoing.recv() => match message {
+                Some(message) => {
+                    if let Err(error) = socket.send(message).await {
+                        tracing::error!("WebSocket error: {}", error);
+                        return true;
                     }
-                    Message::Close(_) => break,
-                    _ => {}
+                }
+                None => {
+                    let _ = socket.close(None).await;
+                    return false;
+                }
+            },
+            message = socket.next() => match message {
+                Some(Ok(Message::Text(text))) => match state.upgrade() {
+                    Some(state) => state.dispatch(&text),
+                    None => return false,
                 },
-                Err(error) => tracing::error!("WebSocket error: {}", error),
+                Some(Ok(Message::Close(_))) | None => return true,
+                Some(Ok(_)) => {}
+                Some(Err(error)) => {
+                    tracing::error!("WebSocket error: {}", error);
+                    return true;
+                }
+            },
+        }
+    }
+}
+
+/// Re-establishes the connection and subscribes to the current streams again. Returns `None`
+/// once the policy's attempts are exhausted.
+async fn reconnect(endpoint: &Endpoint, state: &WsState) -> Option<Socket> {
+    let policy = state.reconnect;
+    let mut attempt = 0;
+    let mut socket = loop {
+        if policy.max_reconnect_attempts.is_some_and(|max| attempt >= max) {
+            tracing::error!("WebSocket reconnection failed after {attempt} attempts");
+            state.state.send_replace(ConnectionState::Failed { attempts: attempt });
+            return None;
+        }
+        attempt += 1;
+        state.state.send_replace(ConnectionState::Reconnecting { attempt });
+        let delay = policy.delay(u32::try_from(attempt - 1).unwrap_or(u32::MAX));
+        tracing::warn!("WebSocket disconnected, reconnecting in {delay:?} (attempt {attempt})");
+        tokio::time::sleep(delay).await;
+        match connect(&endpoint.url, &endpoint.user_agent).await {
+            Ok(socket) => break socket,
+            Err(error) => tracing::warn!("WebSocket reconnection failed: {}", error),
+        }
+    };
+
+    let streams: Vec<String> = state
+        .streams
+        .lock()
+        .expect("ws streams poisoned")
+        .keys()
+        .cloned()
+        .collect();
+    if !streams.is_empty() {
+        let message = subscribe_message(&endpoint.signer, endpoint.window, &streams).to_string();
+        if let Err(error) = socket.send(Message::Text(Utf8Bytes::from(message))).await {
+            tracing::warn!("WebSocket resubscription failed: {}", error);
+        }
+    }
+    state.state.send_replace(ConnectionState::Connected);
+    Some(socket)
+}
+
+/// Returns the time elapsed since `event_time`, in microseconds since UNIX epoch, or zero if
+/// it is in the future.
+fn latency_since(event_time: i64) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_micros();
+    let event_time = u128::try_from(event_time).unwrap_or(0);
+    Duration::from_micros(u64::try_from(now.saturating_sub(event_time)).unwrap_or(u64::MAX))
+}
+
+/// Forwards a stream's messages to a subscriber until either side goes away.
+async fn forward<T: DeserializeOwned>(mut rx: broadcast::Receiver<Value>, tx: Sender<T>) {
+    loop {
+        match rx.recv().await {
+            Ok(value) => {
+                if let Ok(data) = T::deserialize(&value) {
+                    if tx.send(data).await.is_err() {
+                        break;
+                    }
+                }
             }
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Subscriber lagged behind, skipped {skipped} messages");
+            }
+            Err(RecvError::Closed) => break,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_utils::{MockWsServer, TEST_SECRET};
+
+    #[tokio::test]
+    async fn test_subscribe_twice_sends_one_frame() {
+        let server = MockWsServer::start().await;
+        let client = BpxClient::builder()
+            .secret(TEST_SECRET)
+            .ws_url(server.url())
+            .build()
+            .unwrap();
+        let (tx, mut rx) = mpsc::channel::<Value>(8);
+
+        let added = client.subscribe("trade.SOL_USDC", tx.clone()).await.unwrap();
+        assert_eq!(added, vec!["trade.SOL_USDC"]);
+        let added = client.subscribe("trade.SOL_USDC", tx).await.unwrap();
+        assert!(added.is_empty());
+
+        server.wait_for_frames(1).await;
+        server.send(r#"{"stream":"trade.SOL_USDC","data":{"t":1}}"#);
+        assert_eq!(rx.recv().await.unwrap(), json!({"t": 1}));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(rx.try_recv().is_err());
+
+        assert_eq!(
+            client.unsubscribe(&["trade.SOL_USDC"]).await.unwrap(),
+            vec!["trade.SOL_USDC"]
+        );
+        assert!(client.unsubscribe(&["trade.SOL_USDC"]).await.unwrap().is_empty());
+
+        let frames = server.wait_for_frames(2).await;
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0]["method"], "SUBSCRIBE");
+        assert_eq!(
+            frames[1],
+            json!({"method": "UNSUBSCRIBE", "params": ["trade.SOL_USDC"]})
+        );
+        assert_eq!(server.connections(), 1);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_subscriptions() {
+        let server = MockWsServer::start().await;
+        let a = BpxClient::builder()
+            .secret(TEST_SECRET)
+            .ws_url(server.url())
+            .build()
+            .unwrap();
+        let b = a.clone();
+
+        let mut ticker_a = a.subscribe_ticker("SOL_USDC").await.unwrap();
+        let mut ticker_b = b.subscribe_ticker("SOL_USDC").await.unwrap();
+        assert_eq!(b.subscriptions(), ["ticker.SOL_USDC"]);
+        server.wait_for_frames(1).await;
+
+        server.send(
+            r#"{"stream":"ticker.SOL_USDC","data":{"e":"ticker","E":2,"s":"SOL_USDC","o":"140","c":"142.1","h":"143","l":"139.9","v":"1000","V":"141000","n":42}}"#,
+        );
+        assert_eq!(ticker_a.next().await.unwrap().number_of_trades, 42);
+        assert_eq!(ticker_b.next().await.unwrap().number_of_trades, 42);
+
+        // Only clone A's subscription was sent.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(server.wait_for_frames(1).await.len(), 1);
+        assert_eq!(server.connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_sends_user_agent() {
+        let server = MockWsServer::start().await;
+        let client = BpxClient::builder()
+            .secret(TEST_SECRET)
+            .ws_url(server.url())
+            .user_agent("my-bot/1.0")
+            .build()
+            .unwrap();
+        let (tx, _rx) = mpsc::channel::<Value>(8);
+        client.subscribe("trade.SOL_USDC", tx).await.unwrap();
+        assert_eq!(server.user_agents(), ["my-bot/1.0"]);
+    }
+
+    #[tokio::test]
+    async fn test_typed_streams_share_one_connection() {
+        let server = MockWsServer::start().await;
+        let client = BpxClient::builder()
+            .secret(TEST_SECRET)
+            .ws_url(server.url())
+            .build()
+            .unwrap();
+
+        let mut ticker = client.subscribe_ticker("SOL_USDC").await.unwrap();
+        let mut depth = client.subscribe_depth("SOL_USDC").await.unwrap();
+        server.wait_for_frames(2).await;
+
+        server.send(
+            r#"{"stream":"depth.SOL_USDC","data":{"e":"depth","E":1,"s":"SOL_USDC","T":1,"U":5,"u":6,"a":[["142.2","3"]],"b":[]}}"#,
+        );
+        server.send(
+            r#"{"stream":"ticker.SOL_USDC","data":{"e":"ticker","E":2,"s":"SOL_USDC","o":"140","c":"142.1","h":"143","l":"139.9","v":"1000","V":"141000","n":42}}"#,
+        );
+
+        let update = depth.next().await.unwrap();
+        assert_eq!((update.first_update_id, update.last_update_id), (5, 6));
+        assert_eq!(ticker.next().await.unwrap().number_of_trades, 42);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(depth.rx.try_recv().is_err());
+        assert!(ticker.rx.try_recv().is_err());
+        assert_eq!(server.connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_raw_yields_untyped_values() {
+        let server = MockWsServer::start().await;
+        let client = BpxClient::builder()
+            .secret(TEST_SECRET)
+            .ws_url(server.url())
+            .build()
+            .unwrap();
+
+        let mut raw = client.subscribe_raw("liquidation.SOL_USDC_PERP").await.unwrap();
+        let mut ticker = client.subscribe_ticker("SOL_USDC").await.unwrap();
+        let frames = server.wait_for_frames(2).await;
+        assert_eq!(frames[0]["params"], json!(["liquidation.SOL_USDC_PERP"]));
+
+        server.send(r#"{"stream":"liquidation.SOL_USDC_PERP","data":{"e":"liquidation","q":"2.5","p":"150"}}"#);
+        server.send(
+            r#"{"stream":"ticker.SOL_USDC","data":{"e":"ticker","E":2,"s":"SOL_USDC","o":"140","c":"142.1","h":"143","l":"139.9","v":"1000","V":"141000","n":42}}"#,
+        );
+        assert_eq!(
+            raw.next().await.unwrap(),
+            json!({"e": "liquidation", "q": "2.5", "p": "150"})
+        );
+        assert_eq!(ticker.next().await.unwrap().number_of_trades, 42);
+        assert_eq!(server.connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_last_latency_from_event_time() {
+        let server = MockWsServer::start().await;
+        let client = BpxClient::builder()
+            .secret(TEST_SECRET)
+            .ws_url(server.url())
+            .build()
+            .unwrap();
+        let (tx, mut rx) = mpsc::channel::<Value>(8);
+        client.subscribe("trade.SOL_USDC", tx).await.unwrap();
+        server.wait_for_frames(1).await;
+        assert_eq!(client.last_latency(), None);
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_micros() as i64;
+        server.send(&format!(
+            r#"{{"stream":"trade.SOL_USDC","data":{{"E":{}}}}}"#,
+            now - 2_000_000
+        ));
+        rx.recv().await.unwrap();
+        let latency = client.last_latency().unwrap();
+        assert!(latency >= Duration::from_secs(2) && latency < Duration::from_secs(10));
+
+        // A message from the future, as seen by a clock running behind.
+        server.send(&format!(
+            r#"{{"stream":"trade.SOL_USDC","data":{{"E":{}}}}}"#,
+            now + 60_000_000
+        ));
+        rx.recv().await.unwrap();
+        assert_eq!(client.last_latency(), Some(Duration::ZERO));
+    }
+
+    fn reconnecting_client(server: &MockWsServer, max_reconnect_attempts: Option<usize>) -> BpxClient {
+        BpxClient::builder()
+            .secret(TEST_SECRET)
+            .ws_url(server.url())
+            .reconnect_policy(ReconnectPolicy {
+                max_reconnect_attempts,
+                base_delay: Duration::from_millis(10),
+                max_delay: Duration::from_millis(50),
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reconnect_delay_is_bounded() {
+        let policy = ReconnectPolicy::default();
+        for attempt in 0..40 {
+            let cap = policy.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+            assert!(policy.delay(attempt) <= cap.min(policy.max_delay));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_resubscribes() {
+        let server = MockWsServer::start().await;
+        let client = reconnecting_client(&server, None);
+        let (tx, mut rx) = mpsc::channel::<Value>(8);
+        client.subscribe("trade.SOL_USDC", tx).await.unwrap();
+        server.wait_for_frames(1).await;
+
+        server.drop_connections();
+        let frames = server.wait_for_frames(2).await;
+        assert_eq!(frames[1]["method"], "SUBSCRIBE");
+        assert_eq!(frames[1]["params"], json!(["trade.SOL_USDC"]));
+        assert_eq!(server.connections(), 2);
+        assert_eq!(*client.connection_state().borrow(), ConnectionState::Connected);
+
+        server.send(r#"{"stream":"trade.SOL_USDC","data":{"t":2}}"#);
+        assert_eq!(rx.recv().await.unwrap(), json!({"t": 2}));
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_gives_up_after_max_attempts() {
+        let server = MockWsServer::start().await;
+        let client = reconnecting_client(&server, Some(2));
+        let mut state = client.connection_state();
+        let (tx, mut rx) = mpsc::channel::<Value>(8);
+        client.subscribe("trade.SOL_USDC", tx).await.unwrap();
+        server.wait_for_frames(1).await;
+
+        server.shutdown();
+        let failed = tokio::time::timeout(
+            Duration::from_secs(5),
+            state.wait_for(|s| matches!(s, ConnectionState::Failed { .. })),
+        )
+        .await
+        .unwrap()
+        .unwrap()
+        .to_owned();
+        assert_eq!(failed, ConnectionState::Failed { attempts: 2 });
+        assert!(rx.recv().await.is_none());
+        assert!(client.subscriptions().is_empty());
+    }
+}
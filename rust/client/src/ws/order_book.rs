@@ -0,0 +1,277 @@
+//! An order book maintained from the depth stream.
+//!
+//! The book is built from a REST snapshot and kept up to date with the depth updates. When an
+//! update doesn't follow the previous one, updates were missed and the book is rebuilt from a
+//! fresh snapshot. Optionally, a snapshot is also fetched periodically to catch a book that
+//! drifted without any gap being detected.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bpx_api_types::markets::OrderBookDepthUpdate;
+use bpx_api_types::order_book::OrderBook;
+use futures_util::StreamExt;
+use tokio::sync::mpsc::{self, Sender};
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+
+use super::{WsStream, STREAM_CAPACITY};
+use crate::error::Result;
+use crate::BpxClient;
+
+/// Number of recent updates kept to bring a refresh snapshot older than the book up to date.
+const REPLAY_CAPACITY: usize = 256;
+
+/// Delay before fetching another snapshot when the last one was older than the stream.
+const RESYNC_DELAY: Duration = Duration::from_millis(500);
+
+/// Options of [`BpxClient::subscribe_order_book`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrderBookOptions {
+    /// How often to fetch a fresh snapshot and reconcile the book with it. `None` by default,
+    /// in which case a snapshot is only fetched when a gap in the updates is detected.
+    pub snapshot_refresh: Option<Duration>,
+}
+
+impl OrderBookOptions {
+    /// Reconciles the book with a fresh snapshot every `interval`.
+    pub fn with_snapshot_refresh(mut self, interval: Duration) -> Self {
+        self.snapshot_refresh = Some(interval);
+        self
+    }
+}
+
+impl BpxClient {
+    /// Subscribes to the depth stream of a market and returns the order book maintained from
+    /// it, yielding the whole book after each update.
+    ///
+    /// The book is rebuilt from a REST snapshot whenever a gap in the updates is detected.
+    /// With [`OrderBookOptions::snapshot_refresh`] set, a snapshot is also fetched at that
+    /// interval and compared with the book; any divergence is logged as a warning with the
+    /// number of differing levels on each side, and the book is replaced by the snapshot.
+    ///
+    /// ```no_run
+    /// # async fn example(client: bpx_api_client::BpxClient) -> bpx_api_client::Result<()> {
+    /// use std::time::Duration;
+    /// use bpx_api_client::OrderBookOptions;
+    ///
+    /// let options = OrderBookOptions::default().with_snapshot_refresh(Duration::from_secs(300));
+    /// let books = client.subscribe_order_book("SOL_USDC", options).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn subscribe_order_book(&self, symbol: &str, options: OrderBookOptions) -> Result<WsStream<OrderBook>> {
+        let updates = self.subscribe_depth(symbol).await?;
+        let (tx, rx) = mpsc::channel(STREAM_CAPACITY);
+        let maintainer = Maintainer {
+            client: self.clone(),
+            symbol: symbol.to_string(),
+            book: None,
+            pending: None,
+            recent: VecDeque::with_capacity(REPLAY_CAPACITY),
+        };
+        tokio::spawn(maintainer.run(updates, tx, options));
+        Ok(WsStream { rx })
+    }
+}
+
+struct Maintainer {
+    client: BpxClient,
+    symbol: String,
+    /// The maintained book, `None` until synchronized with a snapshot.
+    book: Option<OrderBook>,
+    /// A refresh snapshot waiting for the book to reach its update ID to be compared.
+    pending: Option<OrderBook>,
+    /// The updates most recently applied to the book, oldest first.
+    recent: VecDeque<OrderBookDepthUpdate>,
+}
+
+impl Maintainer {
+    async fn run(
+        mut self,
+        mut updates: WsStream<OrderBookDepthUpdate>,
+        tx: Sender<OrderBook>,
+        options: OrderBookOptions,
+    ) {
+        let mut refresh = options.snapshot_refresh.map(|period| {
+            let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        let mut last_sent = None;
+        loop {
+            tokio::select! {
+                update = updates.next() => {
+                    let Some(update) = update else { return };
+                    self.apply(update).await;
+                }
+                _ = tick(&mut refresh) => self.refresh().await,
+                _ = tx.closed() => return,
+            }
+            let Some(book) = &self.book else { continue };
+            if last_sent != Some(book.last_update_id) {
+                last_sent = Some(book.last_update_id);
+                if tx.send(book.clone()).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Applies an update, first synchronizing the book with a snapshot if needed.
+    async fn apply(&mut self, update: OrderBookDepthUpdate) {
+        let mut resyncing = false;
+        loop {
+            let Some(book) = &mut self.book else {
+                if resyncing {
+                    tokio::time::sleep(RESYNC_DELAY).await;
+                }
+                self.book = Some(self.snapshot().await);
+                resyncing = true;
+                continue;
+            };
+            match book.apply_update(&update) {
+                Ok(()) => break,
+                Err(gap) => {
+                    tracing::warn!("{}: {gap}, fetching a new snapshot", self.symbol);
+                    self.book = None;
+                    self.pending = None;
+                    self.recent.clear();
+                }
+            }
+        }
+
+        if let Some(pending) = &mut self.pending {
+            if pending.apply_update(&update).is_err() {
+                self.pending = None;
+            }
+        }
+        if self.recent.len() == REPLAY_CAPACITY {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(update);
+        self.reconcile();
+    }
+
+    /// Fetches a snapshot to compare with the book once both reflect the same update.
+    async fn refresh(&mut self) {
+        if self.book.is_none() {
+            return;
+        }
+        let mut snapshot = match self.client.get_order_book_depth(&self.symbol).await {
+            Ok(depth) => OrderBook::from(depth),
+            Err(err) => {
+                tracing::warn!("{}: failed to refresh the order book snapshot: {err}", self.symbol);
+                return;
+            }
+        };
+        // Bring a snapshot older than the book up to date with the updates already applied.
+        for update in &self.recent {
+            if snapshot.apply_update(update).is_err() {
+                tracing::debug!("{}: refresh snapshot is too old to be compared", self.symbol);
+                return;
+            }
+        }
+        self.pending = Some(snapshot);
+        self.reconcile();
+    }
+
+    /// Replaces the book with the pending snapshot once they reflect the same update, logging
+    /// any divergence between them.
+    fn reconcile(&mut self) {
+        let (Some(book), Some(snapshot)) = (&mut self.book, &self.pending) else {
+            return;
+        };
+        if snapshot.last_update_id != book.last_update_id {
+            return;
+        }
+        let (bids, asks) = book.diverging_levels(snapshot);
+        if bids > 0 || asks > 0 {
+            tracing::warn!(
+                "{}: order book diverged from the snapshot at update {} ({bids} bid and {asks} ask levels differ), \
+                 reconciling",
+                self.symbol,
+                book.last_update_id
+            );
+        }
+        if let Some(snapshot) = self.pending.take() {
+            *book = snapshot;
+        }
+    }
+
+    /// Fetches a snapshot, retrying until one is received.
+    async fn snapshot(&self) -> OrderBook {
+        loop {
+            match self.client.get_order_book_depth(&self.symbol).await {
+                Ok(depth) => return OrderBook::from(depth),
+                Err(err) => {
+                    tracing::warn!("{}: failed to fetch the order book snapshot: {err}", self.symbol);
+                    tokio::time::sleep(RESYNC_DELAY).await;
+                }
+            }
+        }
+    }
+}
+
+/// Waits for the next refresh, or forever if refreshing is disabled.
+async fn tick(refresh: &mut Option<Interval>) {
+    match refresh {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::test_utils::{test_client_builder, MockResponse, MockServer, MockWsServer};
+
+    fn depth_update(first_update_id: u64, last_update_id: u64, bids: &str) -> String {
+        format!(
+            r#"{{"stream":"depth.SOL_USDC","data":{{"e":"depth","E":1,"s":"SOL_USDC","T":1,"U":{first_update_id},"u":{last_update_id},"a":[],"b":{bids}}}}}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_order_book_refresh_and_gap_resync() {
+        let snapshot = |id: u64, bids: &str| {
+            MockResponse::new(
+                200,
+                format!(r#"{{"asks":[["101","1"]],"bids":{bids},"lastUpdateId":"{id}","timestamp":1}}"#),
+            )
+        };
+        let server = MockServer::start(vec![
+            snapshot(10, r#"[["100","1"]]"#),
+            snapshot(11, r#"[["100","2"],["99","5"]]"#),
+            snapshot(20, r#"[["98","3"]]"#),
+        ])
+        .await;
+        let ws = MockWsServer::start().await;
+        let client = test_client_builder(&server).ws_url(ws.url()).build().unwrap();
+
+        let options = OrderBookOptions::default().with_snapshot_refresh(Duration::from_millis(300));
+        let mut books = client.subscribe_order_book("SOL_USDC", options).await.unwrap();
+        ws.wait_for_frames(1).await;
+
+        ws.send(&depth_update(11, 11, r#"[["100","2"]]"#));
+        let book = books.next().await.unwrap();
+        assert_eq!(book.last_update_id, 11);
+        assert_eq!(book.best_bid(), Some((dec!(100), dec!(2))));
+
+        // The refresh snapshot has a level the book missed, and replaces it.
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        ws.send(&depth_update(12, 12, r#"[["100","0"]]"#));
+        let book = books.next().await.unwrap();
+        assert_eq!(book.last_update_id, 12);
+        assert_eq!(book.best_bid(), Some((dec!(99), dec!(5))));
+
+        ws.send(&depth_update(15, 20, r#"[["98","3"]]"#));
+        let book = books.next().await.unwrap();
+        assert_eq!(book.last_update_id, 20);
+        assert_eq!(book.best_bid(), Some((dec!(98), dec!(3))));
+        assert_eq!(server.requests().len(), 3);
+    }
+}
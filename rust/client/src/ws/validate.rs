@@ -0,0 +1,90 @@
+//! Checking stream prices and quantities against a market's filters.
+
+use bpx_api_types::markets::{Market, ScaleMismatch, ScaledUpdate};
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+
+use super::{WsStream, STREAM_CAPACITY};
+
+/// An update with a price or quantity that isn't a multiple of its market's tick or step
+/// size, see [`WsStream::validate_against`].
+#[derive(Debug)]
+pub struct Misaligned<T> {
+    /// The update, as received.
+    pub update: T,
+    /// The first value found not to conform.
+    pub mismatch: ScaleMismatch,
+}
+
+impl<T: ScaledUpdate + Send + 'static> WsStream<T> {
+    /// Checks every update against the filters of `market`, yielding the updates that
+    /// conform as `Ok` and flagging the others as [`Misaligned`] errors.
+    ///
+    /// Flagged updates are handed over rather than dropped, so the caller decides whether to
+    /// skip them, apply them anyway or resynchronize, and the subscription stays open either
+    /// way. Updates for other symbols than the market's are passed through unchecked.
+    pub fn validate_against(self, market: &Market) -> WsStream<Result<T, Misaligned<T>>> {
+        let (tx, rx) = mpsc::channel(STREAM_CAPACITY);
+        tokio::spawn(validate(self, market.clone(), tx));
+        WsStream { rx }
+    }
+}
+
+async fn validate<T: ScaledUpdate>(
+    mut source: WsStream<T>,
+    market: Market,
+    tx: mpsc::Sender<Result<T, Misaligned<T>>>,
+) {
+    while let Some(update) = source.next().await {
+        let checked = match market.check_scale(&update) {
+            Err(mismatch) if update.symbol() == market.symbol => {
+                tracing::warn!("stream update not aligned to the market: {mismatch}");
+                Err(Misaligned { update, mismatch })
+            }
+            _ => Ok(update),
+        };
+        if tx.send(checked).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bpx_api_types::markets::OrderBookDepthUpdate;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::test_utils::TEST_MARKET_JSON;
+
+    fn depth(symbol: &str, ask: &str, quantity: &str) -> OrderBookDepthUpdate {
+        serde_json::from_str(&format!(
+            r#"{{"e":"depth","E":1,"s":"{symbol}","T":1,"U":1,"u":2,"a":[["{ask}","{quantity}"]],"b":[]}}"#
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_validate_against_flags_misaligned_levels() {
+        let market: Market = serde_json::from_str(TEST_MARKET_JSON).unwrap();
+        let (tx, rx) = mpsc::channel(8);
+        let mut validated = WsStream { rx }.validate_against(&market);
+
+        // The tick and step sizes are both 0.01.
+        tx.send(depth("SOL_USDC", "150.01", "1")).await.unwrap();
+        tx.send(depth("SOL_USDC", "150.015", "1")).await.unwrap();
+        tx.send(depth("SOL_USDC", "150", "0.005")).await.unwrap();
+        tx.send(depth("BTC_USDC", "0.005", "1")).await.unwrap();
+        drop(tx);
+
+        assert!(validated.next().await.unwrap().is_ok());
+        let misaligned = validated.next().await.unwrap().unwrap_err();
+        assert!(matches!(misaligned.mismatch, ScaleMismatch::Price { .. }));
+        assert_eq!(misaligned.update.asks[0].0, dec!(150.015));
+        let misaligned = validated.next().await.unwrap().unwrap_err();
+        assert!(matches!(misaligned.mismatch, ScaleMismatch::Quantity { .. }));
+        // Other markets aren't checked.
+        assert!(validated.next().await.unwrap().is_ok());
+        assert!(validated.next().await.is_none());
+    }
+}
@@ -22,7 +22,8 @@ async fn main() -> Result<()> {
         }
     });
 
-    client.subscribe_to_rfqs(tx).await;
+    client.subscribe_to_rfqs(tx).await?;
+    tokio::signal::ctrl_c().await?;
 
     Ok(())
 }
@@ -1,9 +1,10 @@
+use crate::order::Side;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use crate::order::Side;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct AccountSettings {
     pub auto_borrow_settlements: bool,
     pub auto_lend: bool,
@@ -23,6 +24,7 @@ pub struct AccountSettings {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct AccountMaxBorrow {
     pub max_borrow_quantity: Decimal,
     pub symbol: String,
@@ -30,6 +32,7 @@ pub struct AccountMaxBorrow {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct AccountMaxOrder {
     pub auto_borrow: Option<bool>,
     pub auto_borrow_repay: Option<bool>,
@@ -41,8 +44,26 @@ pub struct AccountMaxOrder {
     pub reduce_only: Option<bool>,
 }
 
+impl AccountMaxOrder {
+    /// Returns `true` if an order of `quantity` in the base asset fits within
+    /// [`max_order_quantity`](Self::max_order_quantity), including an order of exactly the max.
+    pub fn can_afford(&self, quantity: Decimal) -> bool {
+        quantity <= self.max_order_quantity
+    }
+
+    /// Returns `desired`, a quantity in the base asset, reduced to
+    /// [`max_order_quantity`](Self::max_order_quantity) if it exceeds it.
+    ///
+    /// The result is not rounded to the market's step size; round it down with
+    /// [`Market::round_quantity`](crate::markets::Market::round_quantity) before sending it.
+    pub fn capped_to_max(&self, desired: Decimal) -> Decimal {
+        desired.min(self.max_order_quantity)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct AccountMaxWithdrawal {
     pub auto_borrow: Option<bool>,
     pub auto_lend_redeem: Option<bool>,
@@ -69,3 +90,43 @@ pub struct ConvertDustPayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub symbol: Option<String>,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn max_order(max_order_quantity: Decimal) -> AccountMaxOrder {
+        AccountMaxOrder {
+            auto_borrow: None,
+            auto_borrow_repay: None,
+            auto_lend_redeem: None,
+            max_order_quantity,
+            price: None,
+            side: Side::Bid,
+            symbol: "SOL_USDC".to_string(),
+            reduce_only: None,
+        }
+    }
+
+    #[test]
+    fn test_can_afford_at_boundary() {
+        let max = max_order(dec!(1.5));
+        assert!(max.can_afford(dec!(1.49)));
+        assert!(max.can_afford(dec!(1.5)));
+        assert!(max.can_afford(dec!(1.500)));
+        assert!(!max.can_afford(dec!(1.5000001)));
+        assert!(max_order(Decimal::ZERO).can_afford(Decimal::ZERO));
+        assert!(!max_order(Decimal::ZERO).can_afford(dec!(0.01)));
+    }
+
+    #[test]
+    fn test_capped_to_max() {
+        let max = max_order(dec!(1.5));
+        assert_eq!(max.capped_to_max(dec!(1)), dec!(1));
+        assert_eq!(max.capped_to_max(dec!(1.5)), dec!(1.5));
+        assert_eq!(max.capped_to_max(dec!(1.5000001)), dec!(1.5));
+        assert_eq!(max.capped_to_max(dec!(100)), dec!(1.5));
+        assert_eq!(max_order(Decimal::ZERO).capped_to_max(dec!(2)), Decimal::ZERO);
+    }
+}
@@ -0,0 +1,480 @@
+//! Smoothed price signals over the market data streams, candle resampling and volume
+//! profiles.
+//!
+//! Stream timestamps are in microseconds, as in the `event_time` of stream updates.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use rust_decimal::{Decimal, MathematicalOps};
+
+use crate::markets::{Kline, KlineInterval, MarkPriceUpdate, TickerUpdate, KLINE_TIME_FORMAT};
+use crate::trade::Trade;
+use crate::{checked_add, checked_mul, DecimalOverflow};
+
+/// A stream update carrying a price.
+pub trait PriceSample {
+    /// Returns the price and its timestamp in microseconds.
+    fn price_sample(&self) -> (Decimal, i64);
+}
+
+impl PriceSample for TickerUpdate {
+    /// The mid price of the best bid and ask.
+    fn price_sample(&self) -> (Decimal, i64) {
+        ((self.bid_price + self.ask_price) / Decimal::TWO, self.event_time)
+    }
+}
+
+impl PriceSample for MarkPriceUpdate {
+    fn price_sample(&self) -> (Decimal, i64) {
+        (self.mark_price, self.event_time)
+    }
+}
+
+/// An exponential moving average of prices sampled at irregular intervals.
+///
+/// A sample's weight halves every `half_life`: each new sample is blended in as
+/// `ema = price + (ema - price) * 0.5^(elapsed / half_life)`.
+///
+/// The decay factor is computed with `Decimal` fixed-point maths, whose power function is a
+/// series approximation: the average is not exact and should be rounded before being compared.
+/// Gaps longer than 64 half-lives are treated as a full reset to the latest price.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmaAccumulator {
+    half_life: Duration,
+    last: Option<(Decimal, i64)>,
+}
+
+impl EmaAccumulator {
+    /// Creates an empty average with the given half-life.
+    pub fn new(half_life: Duration) -> Self {
+        Self { half_life, last: None }
+    }
+
+    /// Adds a price sampled at `timestamp`. Samples older than the latest one are treated as
+    /// simultaneous with it.
+    pub fn push(&mut self, price: Decimal, timestamp: i64) {
+        self.last = Some(match self.last {
+            Some((ema, last)) => (
+                price + (ema - price) * self.decay(timestamp - last),
+                timestamp.max(last),
+            ),
+            None => (price, timestamp),
+        });
+    }
+
+    /// Adds the price of a stream update.
+    pub fn push_update(&mut self, update: &impl PriceSample) {
+        let (price, timestamp) = update.price_sample();
+        self.push(price, timestamp);
+    }
+
+    /// Returns the current average, or `None` before the first sample.
+    pub fn value(&self) -> Option<Decimal> {
+        self.last.map(|(ema, _)| ema)
+    }
+
+    fn decay(&self, elapsed_micros: i64) -> Decimal {
+        let half_life = self.half_life.as_micros();
+        if elapsed_micros <= 0 {
+            return Decimal::ONE;
+        }
+        if half_life == 0 {
+            return Decimal::ZERO;
+        }
+        let half_lives = Decimal::from(elapsed_micros) / Decimal::from(half_life);
+        if half_lives > Decimal::from(64) {
+            return Decimal::ZERO;
+        }
+        Decimal::new(5, 1).powd(half_lives)
+    }
+}
+
+/// Mean, variance and standard deviation of the prices sampled over a trailing time window.
+///
+/// Running sums are kept exactly in `Decimal`, so the statistics don't drift as samples are
+/// added and evicted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RollingStats {
+    window: Duration,
+    samples: VecDeque<(Decimal, i64)>,
+    sum: Decimal,
+    sum_of_squares: Decimal,
+}
+
+impl RollingStats {
+    /// Creates empty statistics over the given trailing window.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+            sum: Decimal::ZERO,
+            sum_of_squares: Decimal::ZERO,
+        }
+    }
+
+    /// Adds a price sampled at `timestamp` and evicts the samples that are `window` or more
+    /// older than it.
+    ///
+    /// Returns [`DecimalOverflow`] and leaves the statistics unchanged if the running sums
+    /// would be out of range.
+    pub fn push(&mut self, price: Decimal, timestamp: i64) -> Result<(), DecimalOverflow> {
+        let sum = checked_add(self.sum, price)?;
+        let sum_of_squares = checked_add(self.sum_of_squares, checked_mul(price, price)?)?;
+        self.samples.push_back((price, timestamp));
+        self.sum = sum;
+        self.sum_of_squares = sum_of_squares;
+
+        let window = i64::try_from(self.window.as_micros()).unwrap_or(i64::MAX);
+        let cutoff = timestamp.saturating_sub(window);
+        while let Some(&(price, sampled_at)) = self.samples.front() {
+            if sampled_at > cutoff {
+                break;
+            }
+            self.samples.pop_front();
+            self.sum -= price;
+            // Squares are in range, as they were when the sample was pushed.
+            self.sum_of_squares -= price * price;
+        }
+        Ok(())
+    }
+
+    /// Adds the price of a stream update, see [`RollingStats::push`].
+    pub fn push_update(&mut self, update: &impl PriceSample) -> Result<(), DecimalOverflow> {
+        let (price, timestamp) = update.price_sample();
+        self.push(price, timestamp)
+    }
+
+    /// Returns the number of samples in the window.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns `true` if the window holds no samples.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Returns the mean price over the window.
+    pub fn mean(&self) -> Option<Decimal> {
+        (!self.is_empty()).then(|| self.sum / Decimal::from(self.len()))
+    }
+
+    /// Returns the population variance of the prices over the window.
+    pub fn variance(&self) -> Option<Decimal> {
+        let mean = self.mean()?;
+        let variance = self.sum_of_squares / Decimal::from(self.len()) - mean * mean;
+        Some(variance.max(Decimal::ZERO))
+    }
+
+    /// Returns the population standard deviation of the prices over the window.
+    pub fn std_dev(&self) -> Option<Decimal> {
+        self.variance()?.sqrt()
+    }
+}
+
+/// Errors returned by [`resample`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ResampleError {
+    #[error("cannot resample {from} candles to {to}: not a multiple")]
+    NotAMultiple { from: KlineInterval, to: KlineInterval },
+    #[error("cannot resample to or from {0} candles: months vary in length")]
+    Monthly(KlineInterval),
+    #[error("invalid candle start time {0:?}")]
+    InvalidStart(String),
+    #[error("invalid candle trade count {0:?}")]
+    InvalidTrades(String),
+}
+
+/// Aggregates candles of the `from` interval into candles of the coarser `to` interval.
+///
+/// Candles are grouped into buckets aligned to multiples of `to` since the UNIX epoch, in
+/// UTC. Each bucket takes the open of its first candle, the close of its last, the highest
+/// high, the lowest low and the summed volumes and trade counts; candles without prices
+/// only contribute their volumes. Buckets are returned in chronological order, and only
+/// those covering at least one candle are returned, so the first and last may be partial.
+pub fn resample(klines: &[Kline], from: KlineInterval, to: KlineInterval) -> Result<Vec<Kline>, ResampleError> {
+    let from_secs = from.duration_secs().ok_or(ResampleError::Monthly(from))?;
+    let to_secs = to.duration_secs().ok_or(ResampleError::Monthly(to))?;
+    if to_secs % from_secs != 0 {
+        return Err(ResampleError::NotAMultiple { from, to });
+    }
+    let bucket_secs = to_secs as i64;
+
+    let mut starts = Vec::with_capacity(klines.len());
+    for kline in klines {
+        let start = NaiveDateTime::parse_from_str(&kline.start, KLINE_TIME_FORMAT)
+            .map_err(|_| ResampleError::InvalidStart(kline.start.clone()))?;
+        starts.push((start.and_utc().timestamp(), kline));
+    }
+    starts.sort_by_key(|(start, _)| *start);
+
+    let mut buckets: Vec<(i64, u64, Kline)> = Vec::new();
+    for (start, kline) in starts {
+        let trades: u64 = kline
+            .trades
+            .parse()
+            .map_err(|_| ResampleError::InvalidTrades(kline.trades.clone()))?;
+        let bucket = start.div_euclid(bucket_secs) * bucket_secs;
+        match buckets.last_mut() {
+            Some((last, total_trades, candle)) if *last == bucket => {
+                candle.open = candle.open.or(kline.open);
+                candle.high = combine(candle.high, kline.high, Decimal::max);
+                candle.low = combine(candle.low, kline.low, Decimal::min);
+                candle.close = kline.close.or(candle.close);
+                candle.volume += kline.volume;
+                candle.quote_volume += kline.quote_volume;
+                *total_trades += trades;
+            }
+            _ => buckets.push((
+                bucket,
+                trades,
+                Kline {
+                    start: format_time(bucket),
+                    end: format_time(bucket + bucket_secs),
+                    open: kline.open,
+                    high: kline.high,
+                    low: kline.low,
+                    close: kline.close,
+                    volume: kline.volume,
+                    quote_volume: kline.quote_volume,
+                    trades: String::new(),
+                },
+            )),
+        }
+    }
+    Ok(buckets
+        .into_iter()
+        .map(|(_, trades, candle)| Kline {
+            trades: trades.to_string(),
+            ..candle
+        })
+        .collect())
+}
+
+/// Sums the traded quantity of `trades` per price bucket of `bucket_size`, giving their
+/// volume profile.
+///
+/// Each trade falls in the bucket of its price floored to a multiple of `bucket_size`, and
+/// the map is keyed by that lower bound, so iterating it walks the buckets from the lowest
+/// price up. Bucketing is exact: a price on a bucket boundary starts that bucket. Only
+/// buckets with trades are present. Returns [`DecimalOverflow`] if a bucket's volume is out
+/// of range.
+///
+/// # Panics
+///
+/// Panics if `bucket_size` is not positive.
+pub fn volume_profile(trades: &[Trade], bucket_size: Decimal) -> Result<BTreeMap<Decimal, Decimal>, DecimalOverflow> {
+    assert!(bucket_size > Decimal::ZERO, "bucket size must be positive");
+    let mut profile = BTreeMap::new();
+    for trade in trades {
+        let bucket = trade.price - trade.price % bucket_size;
+        let volume = profile.entry(bucket).or_default();
+        *volume = checked_add(*volume, trade.quantity)?;
+    }
+    Ok(profile)
+}
+
+/// Combines two optional prices with `f`, keeping whichever is present if only one is.
+fn combine(a: Option<Decimal>, b: Option<Decimal>, f: fn(Decimal, Decimal) -> Decimal) -> Option<Decimal> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(f(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn format_time(timestamp: i64) -> String {
+    // `NaiveDateTime` displays whole seconds as `KLINE_TIME_FORMAT`.
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .naive_utc()
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    const SECOND: i64 = 1_000_000;
+
+    #[test]
+    fn test_ema_halves_weight_every_half_life() {
+        let mut ema = EmaAccumulator::new(Duration::from_secs(1));
+        assert_eq!(ema.value(), None);
+        ema.push(dec!(100), 0);
+        assert_eq!(ema.value(), Some(dec!(100)));
+        ema.push(dec!(200), SECOND);
+        assert_eq!(ema.value().unwrap().round_dp(10), dec!(150));
+        ema.push(dec!(200), 3 * SECOND);
+        assert_eq!(ema.value().unwrap().round_dp(10), dec!(187.5));
+        ema.push(dec!(0), 3 * SECOND);
+        assert_eq!(ema.value().unwrap().round_dp(10), dec!(187.5));
+        ema.push(dec!(0), 1000 * SECOND);
+        assert_eq!(ema.value(), Some(dec!(0)));
+    }
+
+    #[test]
+    fn test_rolling_stats_window() {
+        let mut stats = RollingStats::new(Duration::from_secs(3));
+        assert_eq!(stats.mean(), None);
+        for (i, price) in [dec!(1), dec!(2), dec!(3), dec!(4)].into_iter().enumerate() {
+            stats.push(price, i as i64 * SECOND).unwrap();
+        }
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats.mean(), Some(dec!(3)));
+        assert_eq!(stats.variance().unwrap().round_dp(10), dec!(0.6666666667));
+        assert_eq!(stats.std_dev().unwrap().round_dp(6), dec!(0.816497));
+    }
+
+    #[test]
+    fn test_push_mark_price_update() {
+        let update: MarkPriceUpdate = serde_json::from_str(
+            r#"{"e":"markPrice","E":1694687965941000,"s":"SOL_USDC_PERP","p":"150.2","f":"0.0001","i":"150.1","n":1694688000000,"T":1694687965940000}"#,
+        )
+        .unwrap();
+        let mut stats = RollingStats::new(Duration::from_secs(60));
+        stats.push_update(&update).unwrap();
+        assert_eq!(stats.mean(), Some(dec!(150.2)));
+    }
+
+    #[test]
+    fn test_rolling_stats_report_overflow() {
+        let mut stats = RollingStats::new(Duration::from_secs(60));
+        // The square of the price is out of range.
+        assert_eq!(stats.push(Decimal::MAX, 0), Err(DecimalOverflow));
+        assert!(stats.is_empty());
+
+        // Each square fits, but not their sum.
+        let price = dec!(200000000000000);
+        stats.push(price, 0).unwrap();
+        assert_eq!(stats.push(price, SECOND), Err(DecimalOverflow));
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats.mean(), Some(price));
+    }
+
+    fn kline(start: &str, ohlc: [Decimal; 4], volume: Decimal, trades: u64) -> Kline {
+        Kline {
+            start: start.to_string(),
+            end: String::new(),
+            open: Some(ohlc[0]),
+            high: Some(ohlc[1]),
+            low: Some(ohlc[2]),
+            close: Some(ohlc[3]),
+            volume,
+            quote_volume: volume * ohlc[3],
+            trades: trades.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_resample_five_one_minute_bars() {
+        let klines = [
+            kline(
+                "2025-01-01 00:00:00",
+                [dec!(100), dec!(101), dec!(99), dec!(100.5)],
+                dec!(1),
+                3,
+            ),
+            kline(
+                "2025-01-01 00:01:00",
+                [dec!(100.5), dec!(103), dec!(100), dec!(102)],
+                dec!(2),
+                4,
+            ),
+            kline(
+                "2025-01-01 00:02:00",
+                [dec!(102), dec!(102.5), dec!(98), dec!(99)],
+                dec!(3),
+                5,
+            ),
+            kline(
+                "2025-01-01 00:03:00",
+                [dec!(99), dec!(100), dec!(98.5), dec!(99.5)],
+                dec!(4),
+                6,
+            ),
+            kline(
+                "2025-01-01 00:04:00",
+                [dec!(99.5), dec!(101), dec!(99), dec!(100)],
+                dec!(5),
+                7,
+            ),
+            kline(
+                "2025-01-01 00:05:00",
+                [dec!(100), dec!(100), dec!(100), dec!(100)],
+                dec!(1),
+                1,
+            ),
+        ];
+        let bars = resample(&klines, KlineInterval::OneMin, KlineInterval::FiveMin).unwrap();
+
+        assert_eq!(bars.len(), 2);
+        let bar = &bars[0];
+        assert_eq!(bar.start, "2025-01-01 00:00:00");
+        assert_eq!(bar.end, "2025-01-01 00:05:00");
+        assert_eq!(bar.open, Some(dec!(100)));
+        assert_eq!(bar.high, Some(dec!(103)));
+        assert_eq!(bar.low, Some(dec!(98)));
+        assert_eq!(bar.close, Some(dec!(100)));
+        assert_eq!(bar.volume, dec!(15));
+        assert_eq!(bar.quote_volume, dec!(1499.5));
+        assert_eq!(bar.trades, "25");
+        assert_eq!(bars[1].start, "2025-01-01 00:05:00");
+    }
+
+    #[test]
+    fn test_resample_rejects_non_multiple() {
+        assert_eq!(
+            resample(&[], KlineInterval::ThreeMin, KlineInterval::FiveMin).unwrap_err(),
+            ResampleError::NotAMultiple {
+                from: KlineInterval::ThreeMin,
+                to: KlineInterval::FiveMin
+            }
+        );
+        assert!(matches!(
+            resample(&[], KlineInterval::OneDay, KlineInterval::OneMonth),
+            Err(ResampleError::Monthly(_))
+        ));
+    }
+
+    #[test]
+    fn test_volume_profile_buckets_by_price() {
+        let trade = |price, quantity| Trade {
+            id: 0,
+            price,
+            quantity,
+            // Not used by the profile, and out of range for the largest trades.
+            quote_quantity: Decimal::ZERO,
+            timestamp: 0,
+            is_buyer_maker: false,
+        };
+        let trades = [
+            trade(dec!(150.10), dec!(1)),
+            trade(dec!(150.49), dec!(2.5)),
+            // On a boundary, so the first of the next bucket.
+            trade(dec!(150.50), dec!(0.3)),
+            trade(dec!(149.99), dec!(4)),
+            trade(dec!(151.75), dec!(1.2)),
+            trade(dec!(150.2), dec!(0.05)),
+        ];
+
+        let profile = volume_profile(&trades, dec!(0.5)).unwrap();
+        assert_eq!(
+            profile.into_iter().collect::<Vec<_>>(),
+            [
+                (dec!(149.5), dec!(4)),
+                (dec!(150), dec!(3.55)),
+                (dec!(150.5), dec!(0.3)),
+                (dec!(151.5), dec!(1.2)),
+            ]
+        );
+        assert!(volume_profile(&[], dec!(1)).unwrap().is_empty());
+
+        // Near the top of the range a bucket's volume no longer fits.
+        let huge = [trade(dec!(150), Decimal::MAX), trade(dec!(150.2), dec!(1))];
+        assert_eq!(volume_profile(&huge, dec!(0.5)), Err(DecimalOverflow));
+        assert!(volume_profile(&huge, dec!(0.1)).is_ok());
+    }
+}
@@ -1,10 +1,11 @@
+use crate::margin::MarginFunction;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumString};
-use crate::margin::MarginFunction;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct BorrowLendPosition {
     pub cumulative_interest: Decimal,
     pub id: String,
@@ -30,6 +31,7 @@ pub enum BorrowLendMarketState {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct BorrowLendMarket {
     pub state: BorrowLendMarketState,
     pub asset_mark_price: Decimal,
@@ -48,4 +50,109 @@ pub struct BorrowLendMarket {
     pub throttle_update_fraction: Decimal,
     pub utilization: Decimal,
     pub step_size: Decimal,
-}
\ No newline at end of file
+}
+
+/// Ratio of the slope of the borrow rate curve above [`BorrowLendMarket::optimal_utilization`]
+/// to its slope below it, as assumed by [`BorrowLendMarket::projected_lend_rate`].
+pub const KINK_SLOPE_RATIO: Decimal = Decimal::TWO;
+
+impl BorrowLendMarket {
+    /// Returns the utilization after `delta_lend` is lent (or withdrawn, if negative), capped
+    /// at [`BorrowLendMarket::max_utilization`], beyond which withdrawals are not allowed.
+    pub fn projected_utilization(&self, delta_lend: Decimal) -> Decimal {
+        let lent = self.lent_quantity + delta_lend;
+        if lent <= Decimal::ZERO {
+            return if self.borrowed_quantity.is_zero() {
+                Decimal::ZERO
+            } else {
+                self.max_utilization
+            };
+        }
+        (self.borrowed_quantity / lent).min(self.max_utilization)
+    }
+
+    /// Returns an estimate of the lend rate after `delta_lend` is lent (or withdrawn, if
+    /// negative).
+    ///
+    /// The borrow rate is modeled as a kinked function of utilization: proportional to it up
+    /// to [`BorrowLendMarket::optimal_utilization`], then rising [`KINK_SLOPE_RATIO`] times
+    /// faster up to [`BorrowLendMarket::max_utilization`]. Lenders earn the borrow interest
+    /// spread over the lent quantity, less the exchange's share, so the lend rate is the
+    /// borrow rate times the utilization times a constant share. The exchange doesn't publish
+    /// its curve, so both the curve and the share are calibrated on the market's current
+    /// rates, and the estimate ignores the throttling that moves the rates gradually towards
+    /// their target.
+    pub fn projected_lend_rate(&self, delta_lend: Decimal) -> Decimal {
+        let curve = |utilization: Decimal| {
+            let below = utilization.min(self.optimal_utilization);
+            let above = (utilization - self.optimal_utilization).max(Decimal::ZERO);
+            below + above * KINK_SLOPE_RATIO
+        };
+        let (current, projected) = (self.utilization, self.projected_utilization(delta_lend));
+        if current.is_zero() || curve(current).is_zero() {
+            return self.lend_interest_rate;
+        }
+        self.lend_interest_rate * curve(projected) / curve(current) * projected / current
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn market(borrowed: Decimal, lent: Decimal, borrow_rate: Decimal, lend_rate: Decimal) -> BorrowLendMarket {
+        BorrowLendMarket {
+            state: BorrowLendMarketState::Open,
+            asset_mark_price: dec!(1),
+            borrow_interest_rate: borrow_rate,
+            borrowed_quantity: borrowed,
+            fee: dec!(0),
+            lend_interest_rate: lend_rate,
+            lent_quantity: lent,
+            max_utilization: dec!(0.9),
+            open_borrow_lend_limit: dec!(1000000),
+            optimal_utilization: dec!(0.8),
+            symbol: "USDC".to_string(),
+            timestamp: "2025-01-01T00:00:00Z".parse().unwrap(),
+            throttle_utilization_threshold: dec!(0.95),
+            throttle_utilization_bound: dec!(0.99),
+            throttle_update_fraction: dec!(0.1),
+            utilization: borrowed / lent,
+            step_size: dec!(0.000001),
+        }
+    }
+
+    #[test]
+    fn test_projection_from_optimal_utilization() {
+        // Lenders keep 90% of the interest: 0.1 * 0.8 * 0.9.
+        let market = market(dec!(80), dec!(100), dec!(0.1), dec!(0.072));
+        assert_eq!(market.projected_utilization(dec!(0)), dec!(0.8));
+        assert_eq!(market.projected_lend_rate(dec!(0)), dec!(0.072));
+
+        // Halving the utilization halves both the borrow rate and the lent share.
+        assert_eq!(market.projected_utilization(dec!(100)), dec!(0.4));
+        assert_eq!(market.projected_lend_rate(dec!(100)), dec!(0.018));
+
+        // Withdrawing past the optimal kink climbs the steeper slope, up to the maximum.
+        assert_eq!(market.projected_utilization(dec!(-10)), dec!(80) / dec!(90));
+        assert_eq!(market.projected_utilization(dec!(-20)), dec!(0.9));
+        assert_eq!(market.projected_utilization(dec!(-100)), dec!(0.9));
+        // Borrow rate 0.1 * (0.8 + 0.1 * 2) / 0.8 = 0.125, lent share 0.9 * 0.9.
+        assert_eq!(market.projected_lend_rate(dec!(-20)), dec!(0.10125));
+    }
+
+    #[test]
+    fn test_projection_from_above_optimal_utilization() {
+        // At the maximum utilization the curve is at 0.8 + 0.1 * 2 = 1.
+        let market = market(dec!(90), dec!(100), dec!(0.2), dec!(0.162));
+        assert_eq!(market.projected_lend_rate(dec!(-5)), dec!(0.162));
+
+        // Back to the optimal kink: the borrow rate drops to 0.2 * 0.8 = 0.16.
+        assert_eq!(market.projected_utilization(dec!(12.5)), dec!(0.8));
+        assert_eq!(
+            market.projected_lend_rate(dec!(12.5)),
+            dec!(0.16) * dec!(0.8) * dec!(0.9)
+        );
+    }
+}
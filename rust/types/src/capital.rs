@@ -2,10 +2,13 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumString};
 
+use crate::borrow_lend::{BorrowLendMarket, BorrowLendMarketState};
+use crate::history::{validate_limit, ParamError};
 use crate::Blockchain;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct Balance {
     pub available: Decimal,
     pub locked: Decimal,
@@ -20,6 +23,7 @@ impl Balance {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct Deposit {
     pub id: i32,
     pub to_address: Option<String>,
@@ -55,6 +59,7 @@ pub enum DepositStatus {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct DepositAddress {
     pub address: String,
 }
@@ -78,6 +83,7 @@ pub struct RequestWithdrawalPayload {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct Withdrawal {
     pub id: i32,
     pub blockchain: Blockchain,
@@ -107,8 +113,76 @@ pub enum WithdrawalStatus {
     Void,
 }
 
+/// Status of a deposit or withdrawal, for filtering the transfer history.
+#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, PartialEq, Eq, Hash)]
+#[strum(serialize_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub enum TransferStatus {
+    Pending,
+    Confirmed,
+    Failed,
+    Cancelled,
+}
+
+impl TransferStatus {
+    /// Returns whether a deposit with the given status has this status. Deposits are never
+    /// failed nor cancelled.
+    pub fn matches_deposit(self, status: DepositStatus) -> bool {
+        matches!(
+            (self, status),
+            (Self::Pending, DepositStatus::Pending) | (Self::Confirmed, DepositStatus::Confirmed)
+        )
+    }
+
+    /// Returns whether a withdrawal with the given status has this status. Withdrawals being
+    /// verified are pending, and voided withdrawals, which the API reports the same way
+    /// whether they were rejected or cancelled, match both `Failed` and `Cancelled`.
+    pub fn matches_withdrawal(self, status: WithdrawalStatus) -> bool {
+        matches!(
+            (self, status),
+            (Self::Pending, WithdrawalStatus::Pending | WithdrawalStatus::Verifying)
+                | (Self::Confirmed, WithdrawalStatus::Confirmed)
+                | (Self::Failed | Self::Cancelled, WithdrawalStatus::Void)
+        )
+    }
+}
+
+/// Search params of the deposit and withdrawal history.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferHistoryParams {
+    /// Filter to minimum time (milliseconds).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<i64>,
+    /// Filter to maximum time (milliseconds).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<i64>,
+    /// Maximum number to return. Default 100, maximum 1000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Offset. Default 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// Filter to the given status. The API doesn't filter by status, so this is applied to
+    /// the page selected by the other params, and is never sent.
+    #[serde(skip)]
+    pub status: Option<TransferStatus>,
+}
+
+impl TransferHistoryParams {
+    /// Checks that `limit` is within the API maximum and that `from` is not after `to`.
+    pub fn validate(&self) -> Result<(), ParamError> {
+        validate_limit(self.limit)?;
+        match (self.from, self.to) {
+            (Some(from), Some(to)) if from > to => Err(ParamError::InvalidTimeRange { from, to }),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct Collateral {
     pub assets_value: Decimal,
     pub borrow_liability: Decimal,
@@ -127,6 +201,7 @@ pub struct Collateral {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct CollateralItem {
     pub symbol: String,
     pub asset_mark_price: Decimal,
@@ -138,3 +213,182 @@ pub struct CollateralItem {
     pub lend_quantity: Decimal,
     pub available_quantity: Decimal,
 }
+
+impl Collateral {
+    /// Returns the equity counted towards margin: the sum over every asset of
+    /// `total_quantity * asset_mark_price * collateral_weight`.
+    ///
+    /// This recomputes each asset's `collateral_value` exactly from its quantity, so it can be
+    /// reused after adjusting quantities or weights locally.
+    pub fn weighted_equity(&self) -> Decimal {
+        self.collateral
+            .iter()
+            .map(|item| item.total_quantity * item.asset_mark_price * item.collateral_weight)
+            .sum()
+    }
+
+    /// Returns the weighted equity left after the margin locked by open orders and positions
+    /// (`net_equity_locked`). This is the collateral that determines buying power, and is
+    /// negative when the account is under-collateralized.
+    pub fn free_collateral(&self) -> Decimal {
+        self.weighted_equity() - self.net_equity_locked
+    }
+
+    /// Returns how much of `asset` can be borrowed from `market` against the current
+    /// collateral, rounded down to the market's step size. Zero if the market isn't open or
+    /// is for another asset.
+    ///
+    /// Each unit borrowed at the market's mark price `p` is owed in full but only counts
+    /// towards collateral at the asset's weight `w` (zero if the asset isn't collateral), and
+    /// requires the account's initial margin fraction `imf`, so it uses up `p * (1 - w + imf)`
+    /// of the [free collateral](Collateral::free_collateral). The result is the smallest of:
+    ///
+    /// - `free_collateral / (p * (1 - w + imf))`,
+    /// - `open_borrow_lend_limit - borrowed_quantity`, the room left under the market limit,
+    /// - `lent_quantity * max_utilization - borrowed_quantity`, the room left before the market
+    ///   reaches its maximum utilization.
+    pub fn borrow_capacity(&self, asset: &str, market: &BorrowLendMarket) -> Decimal {
+        if market.symbol != asset || market.state != BorrowLendMarketState::Open {
+            return Decimal::ZERO;
+        }
+        let weight = self
+            .collateral
+            .iter()
+            .find(|item| item.symbol == asset)
+            .map_or(Decimal::ZERO, |item| item.collateral_weight);
+        let mut capacity = (market.open_borrow_lend_limit - market.borrowed_quantity)
+            .min(market.lent_quantity * market.max_utilization - market.borrowed_quantity);
+        let cost = market.asset_mark_price * (Decimal::ONE - weight + self.imf);
+        if cost > Decimal::ZERO {
+            capacity = capacity.min(self.free_collateral() / cost);
+        }
+        if market.step_size > Decimal::ZERO {
+            capacity = (capacity / market.step_size).floor() * market.step_size;
+        }
+        capacity.max(Decimal::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn item(
+        symbol: &str,
+        total_quantity: Decimal,
+        asset_mark_price: Decimal,
+        collateral_weight: Decimal,
+    ) -> CollateralItem {
+        CollateralItem {
+            symbol: symbol.to_string(),
+            asset_mark_price,
+            total_quantity,
+            balance_notional: total_quantity * asset_mark_price,
+            collateral_weight,
+            collateral_value: total_quantity * asset_mark_price * collateral_weight,
+            open_order_quantity: Decimal::ZERO,
+            lend_quantity: Decimal::ZERO,
+            available_quantity: total_quantity,
+        }
+    }
+
+    fn collateral() -> Collateral {
+        Collateral {
+            assets_value: dec!(2650),
+            borrow_liability: Decimal::ZERO,
+            collateral: vec![
+                item("USDC", dec!(1000), dec!(1), dec!(1)),
+                item("SOL", dec!(10), dec!(150), dec!(0.8)),
+                item("BONK", dec!(5000000), dec!(0.00003), dec!(0.35)),
+            ],
+            imf: dec!(0.02),
+            unsettled_equity: Decimal::ZERO,
+            liabilities_value: Decimal::ZERO,
+            margin_fraction: None,
+            mmf: dec!(0.0125),
+            net_equity: dec!(2650),
+            net_equity_available: dec!(2252.5),
+            net_equity_locked: dec!(300),
+            net_exposure_futures: Decimal::ZERO,
+            pnl_unrealized: Decimal::ZERO,
+        }
+    }
+
+    fn market(symbol: &str, asset_mark_price: Decimal, open_borrow_lend_limit: Decimal) -> BorrowLendMarket {
+        BorrowLendMarket {
+            state: BorrowLendMarketState::Open,
+            asset_mark_price,
+            borrow_interest_rate: dec!(0.1),
+            borrowed_quantity: dec!(500),
+            fee: Decimal::ZERO,
+            lend_interest_rate: dec!(0.05),
+            lent_quantity: dec!(1000),
+            max_utilization: dec!(0.9),
+            open_borrow_lend_limit,
+            optimal_utilization: dec!(0.8),
+            symbol: symbol.to_string(),
+            timestamp: "2025-01-01T00:00:00Z".parse().unwrap(),
+            throttle_utilization_threshold: dec!(0.95),
+            throttle_utilization_bound: dec!(0.99),
+            throttle_update_fraction: dec!(0.1),
+            utilization: dec!(0.5),
+            step_size: dec!(0.01),
+        }
+    }
+
+    #[test]
+    fn test_transfer_status_round_trip() {
+        for (status, json) in [
+            (TransferStatus::Pending, r#""pending""#),
+            (TransferStatus::Confirmed, r#""confirmed""#),
+            (TransferStatus::Failed, r#""failed""#),
+            (TransferStatus::Cancelled, r#""cancelled""#),
+        ] {
+            assert_eq!(serde_json::to_string(&status).unwrap(), json);
+            assert_eq!(serde_json::from_str::<TransferStatus>(json).unwrap(), status);
+            assert_eq!(status.to_string().parse::<TransferStatus>().unwrap(), status);
+        }
+
+        assert!(TransferStatus::Pending.matches_withdrawal(WithdrawalStatus::Verifying));
+        assert!(TransferStatus::Failed.matches_withdrawal(WithdrawalStatus::Void));
+        assert!(TransferStatus::Cancelled.matches_withdrawal(WithdrawalStatus::Void));
+        assert!(!TransferStatus::Failed.matches_deposit(DepositStatus::Pending));
+        assert!(TransferStatus::Confirmed.matches_deposit(DepositStatus::Confirmed));
+    }
+
+    #[test]
+    fn test_weighted_equity_and_free_collateral() {
+        let collateral = collateral();
+        assert_eq!(collateral.weighted_equity(), dec!(2252.50000));
+        assert_eq!(collateral.free_collateral(), dec!(1952.5));
+    }
+
+    #[test]
+    fn test_borrow_capacity() {
+        let collateral = collateral();
+
+        // Each SOL borrowed uses 150 * (1 - 0.8 + 0.02) = 33 of the 1952.5 free collateral,
+        // so collateral allows 59.166.., below the 400 left before the maximum utilization.
+        let sol = market("SOL", dec!(150), dec!(1000000));
+        assert_eq!(collateral.borrow_capacity("SOL", &sol), dec!(59.16));
+
+        // The market limit only leaves 20 to borrow.
+        let constrained = market("SOL", dec!(150), dec!(520));
+        assert_eq!(collateral.borrow_capacity("SOL", &constrained), dec!(20));
+
+        // Here the maximum utilization binds: 1000 * 0.9 - 500.
+        let usdt = market("USDT", dec!(1), dec!(1000000));
+        assert_eq!(collateral.borrow_capacity("USDT", &usdt), dec!(400));
+        // An asset that isn't collateral counts in full against it: 1952.5 / (10 * 1.02).
+        let usdt = market("USDT", dec!(10), dec!(1000000));
+        assert_eq!(collateral.borrow_capacity("USDT", &usdt), dec!(191.42));
+
+        assert_eq!(collateral.borrow_capacity("BONK", &sol), Decimal::ZERO);
+        let closed = BorrowLendMarket {
+            state: BorrowLendMarketState::RepayOnly,
+            ..sol
+        };
+        assert_eq!(collateral.borrow_capacity("SOL", &closed), Decimal::ZERO);
+    }
+}
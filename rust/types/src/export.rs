@@ -0,0 +1,130 @@
+//! Exports of history records as CSV and JSON lines, for reporting pipelines.
+//!
+//! Columns and keys are the record's field names as returned by the API, in declaration
+//! order. Decimals are written with the exact digits and scale received, e.g. `0.10` stays
+//! `0.10`, and timestamps as ISO 8601 UTC without an offset, e.g. `2025-01-02T03:04:05.678`.
+//! Missing values are empty CSV fields and `null` in JSON.
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::history::{HistoricFill, HistoricOrder};
+use crate::strategies::Strategy;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// Writes `fills` as CSV with a header row. Nothing is written if `fills` is empty.
+pub fn fills_to_csv<W: Write>(writer: W, fills: &[HistoricFill]) -> Result<(), ExportError> {
+    to_csv(writer, fills)
+}
+
+/// Writes `orders` as CSV with a header row. Nothing is written if `orders` is empty.
+pub fn orders_to_csv<W: Write>(writer: W, orders: &[HistoricOrder]) -> Result<(), ExportError> {
+    to_csv(writer, orders)
+}
+
+/// Writes `strategies` as CSV with a header row. Nothing is written if `strategies` is empty.
+pub fn strategies_to_csv<W: Write>(writer: W, strategies: &[Strategy]) -> Result<(), ExportError> {
+    to_csv(writer, strategies)
+}
+
+/// Writes `records` as JSON lines: one JSON object per line, each terminated by `\n`.
+pub fn to_jsonl<W: Write, T: Serialize>(mut writer: W, records: &[T]) -> Result<(), ExportError> {
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n").map_err(serde_json::Error::io)?;
+    }
+    writer.flush().map_err(serde_json::Error::io)?;
+    Ok(())
+}
+
+fn to_csv<W: Write, T: Serialize>(writer: W, records: &[T]) -> Result<(), ExportError> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for record in records {
+        writer.serialize(record)?;
+    }
+    writer.flush().map_err(csv::Error::from)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FILLS: &str = r#"[
+        {"clientId":"42","fee":"0.0010","feeSymbol":"USDC","isMaker":true,"orderId":"111","price":"150.10","quantity":"2","side":"Bid","symbol":"SOL_USDC","systemOrderType":null,"timestamp":"2025-01-02T03:04:05.678","tradeId":7},
+        {"clientId":null,"fee":"0","feeSymbol":"SOL","isMaker":false,"orderId":"112","price":"150.2","quantity":"0.50","side":"Ask","symbol":"SOL_USDC","systemOrderType":null,"timestamp":"2025-01-02T03:04:06","tradeId":null}
+    ]"#;
+
+    const ORDERS: &str = r#"[
+        {"id":"111","createdAt":"2025-01-02T03:04:05.678","executedQuantity":"2","executedQuoteQuantity":"300.20","expiryReason":null,"orderType":"Limit","postOnly":true,"price":"150.10","quantity":"2","quoteQuantity":null,"selfTradePrevention":"RejectTaker","status":"Filled","side":"Bid","stopLossTriggerPrice":null,"stopLossLimitPrice":null,"stopLossTriggerBy":null,"symbol":"SOL_USDC","takeProfitTriggerPrice":null,"takeProfitLimitPrice":null,"takeProfitTriggerBy":null,"timeInForce":"GTC","triggerBy":null,"triggerPrice":null,"triggerQuantity":null,"clientId":42,"systemOrderType":null,"strategyId":null,"slippageTolerance":null,"slippageToleranceType":null}
+    ]"#;
+
+    const STRATEGIES: &str = r#"[
+        {"id":5,"createdAt":"2025-01-02T03:04:05","executedQuantity":"1.50","executedQuoteQuantity":null,"cancelReason":null,"strategyType":"Scheduled","quantity":"3","selfTradePrevention":"RejectBoth","status":"Running","side":"Ask","symbol":"SOL_USDC","timeInForce":"IOC","clientStrategyId":null,"duration":60000,"interval":1000,"randomizedIntervalQuantity":false,"slippageTolerance":"0.01","slippageToleranceType":"TickSize"}
+    ]"#;
+
+    fn export<T>(export: impl Fn(&mut Vec<u8>, &[T]) -> Result<(), ExportError>, records: &[T]) -> String {
+        let mut out = Vec::new();
+        export(&mut out, records).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_fills_to_csv() {
+        let fills: Vec<HistoricFill> = serde_json::from_str(FILLS).unwrap();
+        assert_eq!(
+            export(|w, r| fills_to_csv(w, r), &fills),
+            "clientId,fee,feeSymbol,isMaker,orderId,price,quantity,side,symbol,systemOrderType,timestamp,tradeId\n\
+             42,0.0010,USDC,true,111,150.10,2,Bid,SOL_USDC,,2025-01-02T03:04:05.678,7\n\
+             ,0,SOL,false,112,150.2,0.50,Ask,SOL_USDC,,2025-01-02T03:04:06,\n"
+        );
+        assert_eq!(export(|w, r| fills_to_csv(w, r), &[]), "");
+    }
+
+    #[test]
+    fn test_orders_to_csv() {
+        let orders: Vec<HistoricOrder> = serde_json::from_str(ORDERS).unwrap();
+        assert_eq!(
+            export(|w, r| orders_to_csv(w, r), &orders),
+            "id,createdAt,executedQuantity,executedQuoteQuantity,expiryReason,orderType,postOnly,price,quantity,\
+             quoteQuantity,selfTradePrevention,status,side,stopLossTriggerPrice,stopLossLimitPrice,stopLossTriggerBy,\
+             symbol,takeProfitTriggerPrice,takeProfitLimitPrice,takeProfitTriggerBy,timeInForce,triggerBy,\
+             triggerPrice,triggerQuantity,clientId,systemOrderType,strategyId,slippageTolerance,slippageToleranceType\n\
+             111,2025-01-02T03:04:05.678,2,300.20,,Limit,true,150.10,2,,RejectTaker,Filled,Bid,,,,SOL_USDC,,,,GTC,,,,42,,,,\n"
+        );
+    }
+
+    #[test]
+    fn test_strategies_to_csv() {
+        let strategies: Vec<Strategy> = serde_json::from_str(STRATEGIES).unwrap();
+        assert_eq!(
+            export(|w, r| strategies_to_csv(w, r), &strategies),
+            "id,createdAt,executedQuantity,executedQuoteQuantity,cancelReason,strategyType,quantity,\
+             selfTradePrevention,status,side,symbol,timeInForce,clientStrategyId,duration,interval,\
+             randomizedIntervalQuantity,slippageTolerance,slippageToleranceType\n\
+             5,2025-01-02T03:04:05,1.50,,,Scheduled,3,RejectBoth,Running,Ask,SOL_USDC,IOC,,60000,1000,false,0.01,TickSize\n"
+        );
+    }
+
+    #[test]
+    fn test_to_jsonl() {
+        let fills: Vec<HistoricFill> = serde_json::from_str(FILLS).unwrap();
+        assert_eq!(
+            export(|w, r| to_jsonl(w, r), &fills),
+            concat!(
+                r#"{"clientId":"42","fee":"0.0010","feeSymbol":"USDC","isMaker":true,"orderId":"111","price":"150.10","quantity":"2","side":"Bid","symbol":"SOL_USDC","systemOrderType":null,"timestamp":"2025-01-02T03:04:05.678","tradeId":7}"#,
+                "\n",
+                r#"{"clientId":null,"fee":"0","feeSymbol":"SOL","isMaker":false,"orderId":"112","price":"150.2","quantity":"0.50","side":"Ask","symbol":"SOL_USDC","systemOrderType":null,"timestamp":"2025-01-02T03:04:06","tradeId":null}"#,
+                "\n",
+            )
+        );
+    }
+}
@@ -1,10 +1,15 @@
+use std::collections::HashMap;
+
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 use crate::margin::MarginFunction;
+use crate::markets::MarkPriceUpdate;
+use crate::{checked_add, checked_mul, DecimalOverflow};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct FuturePosition {
     pub break_even_price: Decimal,
     pub cumulative_funding_payment: Decimal,
@@ -27,6 +32,63 @@ pub struct FuturePosition {
     pub user_id: u64,
 }
 
+/// Positions in one symbol summed across subaccounts. See [`aggregate_positions`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AggregatePosition {
+    /// Sum of the positions' net quantities.
+    pub net_quantity: Decimal,
+    /// Sum of the positions' net exposure notionals.
+    pub net_exposure_notional: Decimal,
+    /// Sum of the positions' unrealized PnL.
+    pub pnl_unrealized: Decimal,
+    /// Number of positions aggregated.
+    pub positions: usize,
+    gross_quantity: Decimal,
+    gross_cost: Decimal,
+}
+
+impl AggregatePosition {
+    /// Adds a position to the aggregate.
+    ///
+    /// Returns [`DecimalOverflow`] and leaves the aggregate unchanged if a sum is out of
+    /// range.
+    pub fn add(&mut self, position: &FuturePosition) -> Result<(), DecimalOverflow> {
+        let quantity = position.net_quantity.abs();
+        *self = Self {
+            net_quantity: checked_add(self.net_quantity, position.net_quantity)?,
+            net_exposure_notional: checked_add(self.net_exposure_notional, position.net_exposure_notional)?,
+            pnl_unrealized: checked_add(self.pnl_unrealized, position.pnl_unrealized)?,
+            positions: self.positions + 1,
+            gross_quantity: checked_add(self.gross_quantity, quantity)?,
+            gross_cost: checked_add(self.gross_cost, checked_mul(quantity, position.entry_price)?)?,
+        };
+        Ok(())
+    }
+
+    /// Entry price of the positions weighted by their absolute quantity, or `None` if they
+    /// are all flat.
+    ///
+    /// Long and short positions both count towards the weights, so when subaccounts hold
+    /// opposite positions this is their average entry rather than the cost basis of the net
+    /// position.
+    pub fn entry_price(&self) -> Option<Decimal> {
+        (!self.gross_quantity.is_zero()).then(|| self.gross_cost / self.gross_quantity)
+    }
+}
+
+/// Sums positions per symbol, typically the positions of several subaccounts.
+///
+/// Returns [`DecimalOverflow`] if a sum is out of range.
+pub fn aggregate_positions(
+    positions: &[FuturePosition],
+) -> Result<HashMap<String, AggregatePosition>, DecimalOverflow> {
+    let mut aggregates: HashMap<String, AggregatePosition> = HashMap::new();
+    for position in positions {
+        aggregates.entry(position.symbol.clone()).or_default().add(position)?;
+    }
+    Ok(aggregates)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum PositionUpdateType {
@@ -37,6 +99,7 @@ pub enum PositionUpdateType {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct PositionUpdate {
     /// Event type
     #[serde(rename = "e")]
@@ -104,9 +167,39 @@ pub struct PositionUpdate {
     pub est_liquidation_price: Option<Decimal>,
 }
 
+impl PositionUpdate {
+    /// Estimates the liquidation price of the position from the stream fields, replacing the
+    /// deprecated [`PositionUpdate::est_liquidation_price`].
+    ///
+    /// The estimate assumes the position is backed only by its initial margin, ignoring any
+    /// other collateral in the account. The position is liquidated once its loss brings the
+    /// remaining margin down to the maintenance margin:
+    ///
+    /// - long: `entry_price * (1 - imf + mmf)`
+    /// - short: `entry_price * (1 + imf - mmf)`
+    ///
+    /// where `mmf` is `maintenance_margin_function` evaluated at the current
+    /// `net_exposure_notional`. Returns `None` for a flat position or an unknown margin
+    /// function.
+    pub fn estimated_liquidation_price(&self, maintenance_margin_function: &MarginFunction) -> Option<Decimal> {
+        if self.net_quantity.is_zero() {
+            return None;
+        }
+        let mmf = maintenance_margin_function.evaluate(self.net_exposure_notional)?;
+        let margin = self.imf - mmf;
+        let price = if self.net_quantity.is_sign_positive() {
+            self.entry_price * (Decimal::ONE - margin)
+        } else {
+            self.entry_price * (Decimal::ONE + margin)
+        };
+        Some(price.max(Decimal::ZERO))
+    }
+}
+
 /// Open interest updates are pushed to the openInterest stream every 60 seconds.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct OpenInterestUpdate {
     /// Event type
     #[serde(rename = "e")]
@@ -124,3 +217,199 @@ pub struct OpenInterestUpdate {
     #[serde(rename = "o")]
     pub open_interest: Decimal,
 }
+
+impl OpenInterestUpdate {
+    /// Open interest in quote currency at the given mark price.
+    ///
+    /// Backpack perpetual contracts have a size of one unit of the base asset, so this is
+    /// `open_interest * mark_price`, or [`DecimalOverflow`] if that is out of range.
+    pub fn notional(&self, mark_price: Decimal) -> Result<Decimal, DecimalOverflow> {
+        checked_mul(self.open_interest, mark_price)
+    }
+}
+
+/// Joins the latest [`MarkPriceUpdate`] and [`OpenInterestUpdate`] of each symbol to give
+/// open interest in quote currency.
+#[derive(Debug, Clone, Default)]
+pub struct OpenInterestTracker {
+    mark_prices: HashMap<String, Decimal>,
+    open_interest: HashMap<String, Decimal>,
+}
+
+impl OpenInterestTracker {
+    /// Records the latest mark price of a symbol.
+    pub fn update_mark_price(&mut self, update: &MarkPriceUpdate) {
+        self.mark_prices.insert(update.symbol.clone(), update.mark_price);
+    }
+
+    /// Records the latest open interest of a symbol.
+    pub fn update_open_interest(&mut self, update: &OpenInterestUpdate) {
+        self.open_interest.insert(update.symbol.clone(), update.open_interest);
+    }
+
+    /// Returns the notional open interest of a symbol, once both its mark price and open
+    /// interest have been seen, or [`DecimalOverflow`] if it is out of range.
+    pub fn notional(&self, symbol: &str) -> Result<Option<Decimal>, DecimalOverflow> {
+        match (self.open_interest.get(symbol), self.mark_prices.get(symbol)) {
+            (Some(&open_interest), Some(&mark_price)) => checked_mul(open_interest, mark_price).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the notional open interest of every symbol with both a mark price and open
+    /// interest, or [`DecimalOverflow`] if any is out of range.
+    pub fn notionals(&self) -> Result<HashMap<String, Decimal>, DecimalOverflow> {
+        let mut notionals = HashMap::new();
+        for symbol in self.open_interest.keys() {
+            if let Some(notional) = self.notional(symbol)? {
+                notionals.insert(symbol.clone(), notional);
+            }
+        }
+        Ok(notionals)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn get_test_position() -> FuturePosition {
+        serde_json::from_str(
+            r#"{
+                "breakEvenPrice": "100",
+                "cumulativeFundingPayment": "0",
+                "entryPrice": "100",
+                "estLiquidationPrice": "95",
+                "imf": "0.1",
+                "imfFunction": { "base": "0.1", "factor": "0.0001", "type": "sqrt" },
+                "markPrice": "100",
+                "mmf": "0.05",
+                "mmfFunction": { "base": "0.05", "factor": "0.0001", "type": "sqrt" },
+                "netCost": "1000",
+                "netExposureNotional": "1000",
+                "netExposureQuantity": "10",
+                "netQuantity": "10",
+                "pnlRealized": "0",
+                "pnlUnrealized": "0",
+                "positionId": "1",
+                "subaccountId": null,
+                "symbol": "SOL_USDC_PERP",
+                "userId": 1
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[allow(deprecated)]
+    fn position_update_from(position: &FuturePosition) -> PositionUpdate {
+        PositionUpdate {
+            event_type: Some(PositionUpdateType::PositionAdjusted),
+            event_time: 0,
+            symbol: position.symbol.clone(),
+            break_even_price: position.break_even_price,
+            entry_price: position.entry_price,
+            imf: position.imf,
+            mark_price: position.mark_price,
+            mmf: position.mmf,
+            net_quantity: position.net_quantity,
+            net_exposure_quantity: position.net_exposure_quantity,
+            net_exposure_notional: position.net_exposure_notional,
+            position_id: 1,
+            pnl_realized: position.pnl_realized,
+            pnl_unrealized: position.pnl_unrealized,
+            timestamp: 0,
+            est_liquidation_price: None,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_positions_across_subaccounts() {
+        let main = get_test_position();
+        let mut sub = get_test_position();
+        sub.subaccount_id = Some(1);
+        sub.entry_price = dec!(130);
+        sub.net_quantity = dec!(5);
+        sub.net_exposure_notional = dec!(500);
+        sub.pnl_unrealized = dec!(-150);
+        let mut other = get_test_position();
+        other.symbol = "BTC_USDC_PERP".to_string();
+
+        let aggregates = aggregate_positions(&[main, sub, other]).unwrap();
+        assert_eq!(aggregates.len(), 2);
+        let sol = &aggregates["SOL_USDC_PERP"];
+        assert_eq!(sol.net_quantity, dec!(15));
+        assert_eq!(sol.net_exposure_notional, dec!(1500));
+        assert_eq!(sol.pnl_unrealized, dec!(-150));
+        assert_eq!(sol.positions, 2);
+        assert_eq!(sol.entry_price(), Some(dec!(110)));
+        assert_eq!(aggregates["BTC_USDC_PERP"].positions, 1);
+        assert_eq!(AggregatePosition::default().entry_price(), None);
+    }
+
+    #[test]
+    fn test_open_interest_tracker() {
+        let mut tracker = OpenInterestTracker::default();
+        let open_interest: OpenInterestUpdate =
+            serde_json::from_str(r#"{"e":"openInterest","E":1694687965941000,"s":"SOL_USDC_PERP","o":"1250.5"}"#)
+                .unwrap();
+        tracker.update_open_interest(&open_interest);
+        assert_eq!(tracker.notional("SOL_USDC_PERP"), Ok(None));
+
+        let mark_price: MarkPriceUpdate = serde_json::from_str(
+            r#"{"e":"markPrice","E":1694687965941000,"s":"SOL_USDC_PERP","p":"150.2","f":"0.0001","i":"150.1","n":1694688000000,"T":1694687965940000}"#,
+        )
+        .unwrap();
+        tracker.update_mark_price(&mark_price);
+        assert_eq!(tracker.notional("SOL_USDC_PERP"), Ok(Some(dec!(187825.1))));
+        assert_eq!(open_interest.notional(dec!(150.2)), Ok(dec!(187825.1)));
+        assert_eq!(tracker.notionals().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_notionals_report_overflow() {
+        let open_interest = OpenInterestUpdate {
+            event_type: "openInterest".to_string(),
+            event_time: 0,
+            symbol: "SOL_USDC_PERP".to_string(),
+            open_interest: Decimal::MAX,
+        };
+        assert_eq!(open_interest.notional(dec!(2)), Err(DecimalOverflow));
+        let mut tracker = OpenInterestTracker::default();
+        tracker.update_open_interest(&open_interest);
+        tracker.mark_prices.insert("SOL_USDC_PERP".to_string(), dec!(2));
+        assert_eq!(tracker.notional("SOL_USDC_PERP"), Err(DecimalOverflow));
+        assert_eq!(tracker.notionals(), Err(DecimalOverflow));
+
+        let mut position = get_test_position();
+        position.net_quantity = Decimal::MAX;
+        let mut aggregate = AggregatePosition::default();
+        assert_eq!(aggregate.add(&position), Err(DecimalOverflow));
+        assert_eq!(aggregate, AggregatePosition::default());
+        assert_eq!(aggregate_positions(&[position]), Err(DecimalOverflow));
+    }
+
+    #[test]
+    fn test_estimated_liquidation_price_matches_rest_position() {
+        let position = get_test_position();
+        let update = position_update_from(&position);
+        assert_eq!(
+            update.estimated_liquidation_price(&position.mmf_function),
+            Some(position.est_liquidation_price)
+        );
+    }
+
+    #[test]
+    fn test_estimated_liquidation_price_short_and_flat() {
+        let position = get_test_position();
+        let mut update = position_update_from(&position);
+        update.net_quantity = dec!(-10);
+        assert_eq!(
+            update.estimated_liquidation_price(&position.mmf_function),
+            Some(dec!(105))
+        );
+
+        update.net_quantity = Decimal::ZERO;
+        assert_eq!(update.estimated_liquidation_price(&position.mmf_function), None);
+    }
+}
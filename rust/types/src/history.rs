@@ -1,9 +1,35 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
 use crate::markets::MarketType;
-use crate::order::{OrderStatus, OrderType, SelfTradePrevention, Side, SlippageToleranceType, TimeInForce, TriggerBy};
-use crate::SortDirection;
+use crate::order::{OrderLike, OrderStatus, SelfTradePrevention, Side, SlippageToleranceType, TimeInForce, TriggerBy};
+use crate::{checked_add, checked_mul, DecimalOverflow, SortDirection};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use strum::{Display, EnumString};
+use strum::{Display, EnumIter, EnumString};
+
+/// Maximum `limit` accepted by the history endpoints.
+pub const MAX_HISTORY_LIMIT: u64 = 1000;
+
+/// Error returned when history search params fall outside the bounds accepted by the API.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParamError {
+    #[error("`limit` must be at most {max}, got {limit}")]
+    LimitTooLarge { limit: u64, max: u64 },
+    #[error("`from` ({from}) must not be after `to` ({to})")]
+    InvalidTimeRange { from: i64, to: i64 },
+}
+
+/// Checks a history `limit` against [`MAX_HISTORY_LIMIT`].
+pub(crate) fn validate_limit(limit: Option<u64>) -> Result<(), ParamError> {
+    match limit {
+        Some(limit) if limit > MAX_HISTORY_LIMIT => Err(ParamError::LimitTooLarge {
+            limit,
+            max: MAX_HISTORY_LIMIT,
+        }),
+        _ => Ok(()),
+    }
+}
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -57,18 +83,124 @@ impl FillHistorySearchParams {
     }
 }
 
+impl FillHistorySearchParams {
+    /// Returns a builder for the search params.
+    ///
+    /// ```
+    /// use bpx_api_types::history::FillHistorySearchParams;
+    ///
+    /// let params = FillHistorySearchParams::builder()
+    ///     .symbol("SOL_USDC")
+    ///     .time_range(1_700_000_000_000, 1_700_086_400_000)
+    ///     .limit(500)
+    ///     .build()
+    ///     .expect("params within bounds");
+    /// assert_eq!(params.limit, Some(500));
+    ///
+    /// assert!(FillHistorySearchParams::builder().limit(5000).build().is_err());
+    /// ```
+    pub fn builder() -> FillHistorySearchParamsBuilder {
+        FillHistorySearchParamsBuilder::default()
+    }
+
+    /// Checks that `limit` is within the API maximum and that `from` is not after `to`.
+    pub fn validate(&self) -> Result<(), ParamError> {
+        validate_limit(self.limit)?;
+        match (self.from, self.to) {
+            (Some(from), Some(to)) if from > to => Err(ParamError::InvalidTimeRange { from, to }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Builder for [`FillHistorySearchParams`].
+#[derive(Debug, Default, Clone)]
+pub struct FillHistorySearchParamsBuilder {
+    params: FillHistorySearchParams,
+}
+
+impl FillHistorySearchParamsBuilder {
+    /// Filter to the given order.
+    pub fn order_id(mut self, order_id: impl Into<String>) -> Self {
+        self.params.order_id = Some(order_id.into());
+        self
+    }
+
+    /// Filter to the given strategy.
+    pub fn strategy_id(mut self, strategy_id: impl Into<String>) -> Self {
+        self.params.strategy_id = Some(strategy_id.into());
+        self
+    }
+
+    /// Filter to fills between `from` and `to` (milliseconds).
+    pub fn time_range(mut self, from: i64, to: i64) -> Self {
+        self.params.from = Some(from);
+        self.params.to = Some(to);
+        self
+    }
+
+    /// Filter to the given symbol.
+    pub fn symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.params.symbol = Some(symbol.into());
+        self
+    }
+
+    /// Maximum number to return, at most [`MAX_HISTORY_LIMIT`].
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.params.limit = Some(limit);
+        self
+    }
+
+    /// Offset.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.params.offset = Some(offset);
+        self
+    }
+
+    /// Fill type.
+    pub fn fill_type(mut self, fill_type: FillType) -> Self {
+        self.params.fill_type = Some(fill_type);
+        self
+    }
+
+    /// Market type.
+    pub fn market_type(mut self, market_type: MarketType) -> Self {
+        self.params.market_type = Some(market_type);
+        self
+    }
+
+    /// Sort direction.
+    pub fn sort_direction(mut self, sort_direction: SortDirection) -> Self {
+        self.params.sort_direction = Some(sort_direction);
+        self
+    }
+
+    /// Validates and returns the search params.
+    pub fn build(self) -> Result<FillHistorySearchParams, ParamError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
+/// A fill from the `/wapi` fill history. Fields are camelCase like the rest of the API; the
+/// snake_case spellings are accepted too, as `/wapi` routes don't always follow `/api`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct HistoricFill {
     /// Client id of the order.
+    #[serde(alias = "client_id")]
     pub client_id: Option<String>,
     /// The fee charged on the fill.
     pub fee: Decimal,
     /// The asset that is charged as a fee.
+    #[serde(alias = "fee_symbol")]
     pub fee_symbol: String,
     /// Whether the fill was made by the maker.
+    #[serde(alias = "is_maker")]
     pub is_maker: bool,
     /// The order ID of the fill.
+    #[serde(alias = "order_id")]
     pub order_id: String,
     /// The price of the fill.
     pub price: Decimal,
@@ -79,10 +211,12 @@ pub struct HistoricFill {
     /// The market symbol of the fill.
     pub symbol: String,
     /// The type of system order that triggered the fill.
+    #[serde(alias = "system_order_type")]
     pub system_order_type: Option<SystemOrderType>,
     /// The timestamp of the fill (UTC).
     pub timestamp: chrono::NaiveDateTime,
     /// The trade ID of the fill.
+    #[serde(alias = "trade_id")]
     pub trade_id: Option<i64>,
 }
 
@@ -105,6 +239,10 @@ pub struct OrderHistorySearchParams {
     )]
     pub limit: Option<u64>,
     /// Offset. Default 0.
+    ///
+    /// The endpoint only supports offset pagination, there are no id based cursors. When
+    /// paging through a large history, sort with [`SortDirection::Asc`] so orders placed
+    /// mid-scan are appended at the end instead of shifting the pages already read.
     #[serde(
         skip_serializing_if = "Option::is_none",
         default = "OrderHistorySearchParams::default_offset"
@@ -129,70 +267,194 @@ impl OrderHistorySearchParams {
     }
 }
 
+impl OrderHistorySearchParams {
+    /// Returns a builder for the search params.
+    pub fn builder() -> OrderHistorySearchParamsBuilder {
+        OrderHistorySearchParamsBuilder::default()
+    }
+
+    /// Checks that `limit` is within the API maximum.
+    pub fn validate(&self) -> Result<(), ParamError> {
+        validate_limit(self.limit)
+    }
+}
+
+/// Builder for [`OrderHistorySearchParams`].
+#[derive(Debug, Default, Clone)]
+pub struct OrderHistorySearchParamsBuilder {
+    params: OrderHistorySearchParams,
+}
+
+impl OrderHistorySearchParamsBuilder {
+    /// Filter to the given order.
+    pub fn order_id(mut self, order_id: impl Into<String>) -> Self {
+        self.params.order_id = Some(order_id.into());
+        self
+    }
+
+    /// Filter to the given strategy.
+    pub fn strategy_id(mut self, strategy_id: impl Into<String>) -> Self {
+        self.params.strategy_id = Some(strategy_id.into());
+        self
+    }
+
+    /// Filter to the given symbol.
+    pub fn symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.params.symbol = Some(symbol.into());
+        self
+    }
+
+    /// Maximum number to return, at most [`MAX_HISTORY_LIMIT`].
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.params.limit = Some(limit);
+        self
+    }
+
+    /// Offset.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.params.offset = Some(offset);
+        self
+    }
+
+    /// Market type.
+    pub fn market_type(mut self, market_type: MarketType) -> Self {
+        self.params.market_type = Some(market_type);
+        self
+    }
+
+    /// Sort direction.
+    pub fn sort_direction(mut self, sort_direction: SortDirection) -> Self {
+        self.params.sort_direction = Some(sort_direction);
+        self
+    }
+
+    /// Validates and returns the search params.
+    pub fn build(self) -> Result<OrderHistorySearchParams, ParamError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
+/// An order from the `/wapi` order history. Like [`HistoricFill`], snake_case field names are
+/// accepted as well as camelCase.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 // TODO: use a HistoricOrder enum instead of this struct, similarly to `Order`
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct HistoricOrder {
     /// Unique ID of the order.
     pub id: String,
     /// Time the order was created.
+    #[serde(alias = "created_at")]
     pub created_at: chrono::NaiveDateTime,
     /// Quantity of the order that has been filled.
+    #[serde(alias = "executed_quantity")]
     pub executed_quantity: Option<Decimal>,
     /// Quantity of the order that has been filled in the quote asset.
+    #[serde(alias = "executed_quote_quantity")]
     pub executed_quote_quantity: Option<Decimal>,
     /// Order expiry reason.
+    #[serde(alias = "expiry_reason")]
     pub expiry_reason: Option<OrderExpiryReason>,
     /// Type of order.
+    #[serde(alias = "order_type")]
     pub order_type: HistoricOrderType,
     /// Whether the order is post only or not.
+    #[serde(alias = "post_only")]
     pub post_only: Option<bool>,
     /// Price that the order was submitted at (if orderType is Limit)
     pub price: Option<Decimal>,
     /// Quantity of the order.
     pub quantity: Option<Decimal>,
     /// Quantity of the order in quote the quote asset.
+    #[serde(alias = "quote_quantity")]
     pub quote_quantity: Option<Decimal>,
     /// Self trade prevention setting of the order. Default is `RejectTaker`.
+    #[serde(alias = "self_trade_prevention")]
     pub self_trade_prevention: SelfTradePrevention,
     /// Status of the order.
     pub status: OrderStatus,
     /// Side of the order.
     pub side: Side,
     /// Stop loss price (price the stop loss order will be triggered at).
+    #[serde(alias = "stop_loss_trigger_price")]
     pub stop_loss_trigger_price: Option<Decimal>,
     /// Stop loss limit price. If set the stop loss will be a limit order, otherwise it will be a market order.
+    #[serde(alias = "stop_loss_limit_price")]
     pub stop_loss_limit_price: Option<Decimal>,
     /// Reference price that should trigger the stop loss order.
+    #[serde(alias = "stop_loss_trigger_by")]
     pub stop_loss_trigger_by: Option<TriggerBy>,
     /// The market symbol of the fill.
     pub symbol: String,
     /// Take profit price (price the take profit order will be triggered at).
+    #[serde(alias = "take_profit_trigger_price")]
     pub take_profit_trigger_price: Option<Decimal>,
     /// Take profit limit price. If set the take profit will be a limit order, otherwise it will be a market order.
+    #[serde(alias = "take_profit_limit_price")]
     pub take_profit_limit_price: Option<Decimal>,
     /// Reference price that should trigger the take profit order.
+    #[serde(alias = "take_profit_trigger_by")]
     pub take_profit_trigger_by: Option<TriggerBy>,
     /// Time in force of the order.
+    #[serde(alias = "time_in_force")]
     pub time_in_force: Option<TimeInForce>,
     /// Reference price that should trigger the order.
+    #[serde(alias = "trigger_by")]
     pub trigger_by: Option<TriggerBy>,
     /// Price the order was set to trigger at.
+    #[serde(alias = "trigger_price")]
     pub trigger_price: Option<Decimal>,
     /// Trigger quantity.
+    #[serde(alias = "trigger_quantity")]
     pub trigger_quantity: Option<Decimal>,
     /// Custom order ID.
+    #[serde(alias = "client_id")]
     pub client_id: Option<u32>,
     /// The type of system order, if applicable.
+    #[serde(alias = "system_order_type")]
     pub system_order_type: Option<SystemOrderType>,
     /// Strategy ID of the order, if any.
+    #[serde(alias = "strategy_id")]
     pub strategy_id: Option<String>,
     /// Slippage tolerance allowed for the order.
+    #[serde(alias = "slippage_tolerance")]
     pub slippage_tolerance: Option<Decimal>,
     /// Slippage tolerance type.
+    #[serde(alias = "slippage_tolerance_type")]
     pub slippage_tolerance_type: Option<SlippageToleranceType>,
 }
 
+impl OrderLike for HistoricOrder {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn side(&self) -> Side {
+        self.side
+    }
+
+    fn status(&self) -> OrderStatus {
+        self.status
+    }
+
+    fn price(&self) -> Option<Decimal> {
+        self.price
+    }
+
+    fn quantity(&self) -> Option<Decimal> {
+        self.quantity
+    }
+
+    fn executed_quantity(&self) -> Option<Decimal> {
+        self.executed_quantity
+    }
+}
+
 #[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, PartialEq, Eq, Hash)]
 #[strum(serialize_all = "PascalCase")]
 #[serde(rename_all = "PascalCase")]
@@ -219,7 +481,7 @@ pub enum SystemOrderType {
     OrderBookClosed,
 }
 
-#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, PartialEq, Eq, Hash)]
+#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, EnumIter, PartialEq, Eq, Hash)]
 #[strum(serialize_all = "PascalCase")]
 #[serde(rename_all = "PascalCase")]
 pub enum OrderExpiryReason {
@@ -249,6 +511,38 @@ pub enum OrderExpiryReason {
     SlippageToleranceExceeded,
 }
 
+impl OrderExpiryReason {
+    /// Returns a plain English explanation of why the order expired, for display to users.
+    pub const fn description(&self) -> &'static str {
+        match self {
+            Self::AccountTradingSuspended => "Trading is suspended on the account",
+            Self::BorrowRequiresLendRedeem => "Borrowing for the order required redeeming lent funds",
+            Self::FillOrKill => "Fill-or-kill order could not be filled in full immediately",
+            Self::InsufficientBorrowableQuantity => "Not enough of the asset could be borrowed for the order",
+            Self::InsufficientFunds => "The account had insufficient funds for the order",
+            Self::InsufficientLiquidity => "There was not enough liquidity in the order book to fill the order",
+            Self::InvalidPrice => "The order price was invalid for the market",
+            Self::InvalidQuantity => "The order quantity was invalid for the market",
+            Self::ImmediateOrCancel => "Immediate-or-cancel order was not filled in full immediately",
+            Self::InsufficientMargin => "The account had insufficient margin for the order",
+            Self::Liquidation => "The order was cancelled because the account was liquidated",
+            Self::NegativeEquity => "The account's equity was negative",
+            Self::PostOnlyMode => "The market only accepted post-only orders",
+            Self::PostOnlyTaker => "Post-only order would have matched immediately",
+            Self::PriceOutOfBounds => "The order price was outside the market's allowed price band",
+            Self::ReduceOnlyNotReduced => "Reduce-only order would not have reduced the position",
+            Self::SelfTradePrevention => "The order would have traded against another order of the account",
+            Self::StopWithoutPosition => "Stop order triggered without an open position to close",
+            Self::PriceImpact => "The order would have moved the price too far",
+            Self::Unknown => "The order expired for an unknown reason",
+            Self::UserPermissions => "The account lacked the permissions required for the order",
+            Self::MaxStopOrdersPerPosition => "The position already had the maximum number of stop orders",
+            Self::PositionLimit => "The order would have exceeded the position limit",
+            Self::SlippageToleranceExceeded => "The order's fill price exceeded its slippage tolerance",
+        }
+    }
+}
+
 #[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, PartialEq, Eq, Hash)]
 #[strum(serialize_all = "PascalCase")]
 #[serde(rename_all = "PascalCase")]
@@ -256,3 +550,406 @@ pub enum HistoricOrderType {
     Limit,
     Market,
 }
+
+/// Groups fills by the client id of the order that produced them.
+///
+/// A fill's own `client_id` is used when present, otherwise it is looked up on the owning
+/// order, matched by order id. Fills with no client id either way are grouped under `None`
+/// rather than dropped.
+pub fn correlate_fills_by_client_id<'a>(
+    orders: &[HistoricOrder],
+    fills: &'a [HistoricFill],
+) -> HashMap<Option<String>, Vec<&'a HistoricFill>> {
+    let client_ids: HashMap<&str, u32> = orders
+        .iter()
+        .filter_map(|order| Some((order.id.as_str(), order.client_id?)))
+        .collect();
+
+    let mut grouped: HashMap<Option<String>, Vec<&HistoricFill>> = HashMap::new();
+    for fill in fills {
+        let client_id = fill
+            .client_id
+            .clone()
+            .or_else(|| client_ids.get(fill.order_id.as_str()).map(u32::to_string));
+        grouped.entry(client_id).or_default().push(fill);
+    }
+    grouped
+}
+
+/// Totals over a set of fills, see [`aggregate_fills`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FillAggregate {
+    /// Quantity filled, in the base asset.
+    pub base_quantity: Decimal,
+    /// Notional filled, the sum of each fill's `quantity * price`, in the quote asset.
+    pub quote_quantity: Decimal,
+    /// Volume-weighted average price, `quote_quantity / base_quantity`, or `None` if nothing
+    /// was filled.
+    pub average_price: Option<Decimal>,
+    /// Fees charged, keyed by the asset they were charged in.
+    pub fees: BTreeMap<String, Decimal>,
+}
+
+/// Sums the quantity, notional and fees of `fills` in one pass.
+///
+/// Quantities, notionals and fees are exact sums of the fills' values; only the average
+/// price is rounded, by the final division. Fills are summed regardless of side, so pass the
+/// fills of a single order or side, e.g. from [`correlate_fills_by_client_id`]. Returns
+/// [`DecimalOverflow`] if a sum is out of range.
+pub fn aggregate_fills<'a>(
+    fills: impl IntoIterator<Item = &'a HistoricFill>,
+) -> Result<FillAggregate, DecimalOverflow> {
+    let mut aggregate = FillAggregate::default();
+    for fill in fills {
+        aggregate.base_quantity = checked_add(aggregate.base_quantity, fill.quantity)?;
+        aggregate.quote_quantity = checked_add(aggregate.quote_quantity, checked_mul(fill.quantity, fill.price)?)?;
+        let fee = aggregate.fees.entry(fill.fee_symbol.clone()).or_default();
+        *fee = checked_add(*fee, fill.fee)?;
+    }
+    if !aggregate.base_quantity.is_zero() {
+        aggregate.average_price = Some(aggregate.quote_quantity / aggregate.base_quantity);
+    }
+    Ok(aggregate)
+}
+
+/// A field history items are sorted by with [`sort_history_by`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HistorySortKey {
+    /// Time the order was created, or the fill executed.
+    Time,
+    /// Price of the order or fill.
+    Price,
+    /// Quantity of the order or fill, in the base asset.
+    Quantity,
+}
+
+/// History items that can be sorted with [`sort_history_by`].
+pub trait HistorySortable {
+    /// Time the item was created or executed.
+    fn sort_time(&self) -> chrono::NaiveDateTime;
+    /// Price of the item, if it has one.
+    fn sort_price(&self) -> Option<Decimal>;
+    /// Quantity of the item in the base asset, if it has one.
+    fn sort_quantity(&self) -> Option<Decimal>;
+}
+
+impl HistorySortable for HistoricFill {
+    fn sort_time(&self) -> chrono::NaiveDateTime {
+        self.timestamp
+    }
+
+    fn sort_price(&self) -> Option<Decimal> {
+        Some(self.price)
+    }
+
+    fn sort_quantity(&self) -> Option<Decimal> {
+        Some(self.quantity)
+    }
+}
+
+impl HistorySortable for HistoricOrder {
+    fn sort_time(&self) -> chrono::NaiveDateTime {
+        self.created_at
+    }
+
+    /// Unset for market orders.
+    fn sort_price(&self) -> Option<Decimal> {
+        self.price
+    }
+
+    /// Unset for orders sized in the quote asset.
+    fn sort_quantity(&self) -> Option<Decimal> {
+        self.quantity
+    }
+}
+
+/// Sorts history items by `key` in the given direction.
+///
+/// Items without a value for `key`, such as market orders when sorting by price, are placed
+/// last in either direction. The sort is stable, so items with equal keys keep the order they
+/// were returned in.
+pub fn sort_history_by<T: HistorySortable>(items: &mut [T], key: HistorySortKey, direction: SortDirection) {
+    fn compare<K: Ord>(a: Option<K>, b: Option<K>, direction: SortDirection) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => match direction {
+                SortDirection::Asc => a.cmp(&b),
+                SortDirection::Desc => b.cmp(&a),
+            },
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+    match key {
+        HistorySortKey::Time => items.sort_by(|a, b| compare(Some(a.sort_time()), Some(b.sort_time()), direction)),
+        HistorySortKey::Price => items.sort_by(|a, b| compare(a.sort_price(), b.sort_price(), direction)),
+        HistorySortKey::Quantity => items.sort_by(|a, b| compare(a.sort_quantity(), b.sort_quantity(), direction)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::order::Order;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_order_like_over_live_and_historic_orders() {
+        let live: Order = serde_json::from_str(
+            r#"{"orderType":"Limit","id":"1","clientId":null,"symbol":"SOL_USDC","side":"Bid","quantity":"2",
+            "executedQuantity":"0.5","executedQuoteQuantity":"75","price":"150","timeInForce":"GTC",
+            "selfTradePrevention":"RejectTaker","postOnly":false,"status":"PartiallyFilled","createdAt":0}"#,
+        )
+        .unwrap();
+        let historic: HistoricOrder = serde_json::from_str(
+            r#"{"id":"2","createdAt":"2025-01-01T00:00:00","executedQuantity":"1","orderType":"Market",
+            "quantity":"1","selfTradePrevention":"RejectTaker","status":"Filled","side":"Ask","symbol":"SOL_USDC"}"#,
+        )
+        .unwrap();
+
+        let orders: Vec<Box<dyn OrderLike>> = vec![Box::new(live), Box::new(historic)];
+        let executed: Decimal = orders.iter().filter_map(|o| o.executed_quantity()).sum();
+        assert_eq!(executed, dec!(1.5));
+        assert_eq!(orders[0].price(), Some(dec!(150)));
+        assert_eq!(orders[1].price(), None);
+        assert_eq!(orders[1].side(), Side::Ask);
+        assert_eq!(orders[1].status(), OrderStatus::Filled);
+    }
+
+    #[test]
+    fn test_history_accepts_both_casings() {
+        // As returned by `/wapi/v1/history/fills`.
+        let camel: HistoricFill = serde_json::from_str(
+            r#"{"clientId":"42","fee":"0.0010","feeSymbol":"USDC","isMaker":true,"orderId":"111",
+            "price":"150.10","quantity":"2","side":"Bid","symbol":"SOL_USDC","systemOrderType":null,
+            "timestamp":"2025-01-02T03:04:05.678","tradeId":7}"#,
+        )
+        .unwrap();
+        let snake: HistoricFill = serde_json::from_str(
+            r#"{"client_id":"42","fee":"0.0010","fee_symbol":"USDC","is_maker":true,"order_id":"111",
+            "price":"150.10","quantity":"2","side":"Bid","symbol":"SOL_USDC","system_order_type":null,
+            "timestamp":"2025-01-02T03:04:05.678","trade_id":7}"#,
+        )
+        .unwrap();
+        for fill in [&camel, &snake] {
+            assert_eq!(fill.client_id.as_deref(), Some("42"));
+            assert_eq!(fill.fee_symbol, "USDC");
+            assert!(fill.is_maker);
+            assert_eq!(fill.order_id, "111");
+            assert_eq!(fill.trade_id, Some(7));
+        }
+        // Serialization stays camelCase.
+        assert_eq!(
+            serde_json::to_value(&snake).unwrap(),
+            serde_json::to_value(&camel).unwrap()
+        );
+        assert!(serde_json::to_string(&snake).unwrap().contains(r#""feeSymbol":"USDC""#));
+
+        let order: HistoricOrder = serde_json::from_str(
+            r#"{"id":"2","created_at":"2025-01-01T00:00:00","executed_quantity":"1","order_type":"Market",
+            "quantity":"1","self_trade_prevention":"RejectTaker","status":"Filled","side":"Ask",
+            "symbol":"SOL_USDC","client_id":7,"strategy_id":"9"}"#,
+        )
+        .unwrap();
+        assert_eq!(order.executed_quantity, Some(dec!(1)));
+        assert_eq!(order.client_id, Some(7));
+        assert_eq!(order.strategy_id.as_deref(), Some("9"));
+    }
+
+    #[test]
+    fn test_search_params_validation() {
+        let params = FillHistorySearchParams {
+            limit: Some(1000),
+            from: Some(1),
+            to: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(params.validate(), Ok(()));
+
+        let params = FillHistorySearchParams {
+            limit: Some(1001),
+            ..Default::default()
+        };
+        assert_eq!(
+            params.validate(),
+            Err(ParamError::LimitTooLarge { limit: 1001, max: 1000 })
+        );
+
+        let params = FillHistorySearchParams {
+            from: Some(2),
+            to: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(params.validate(), Err(ParamError::InvalidTimeRange { from: 2, to: 1 }));
+
+        let params = OrderHistorySearchParams {
+            limit: Some(5000),
+            ..Default::default()
+        };
+        assert!(params.validate().unwrap_err().to_string().contains("`limit`"));
+    }
+
+    #[test]
+    fn test_search_params_builders() {
+        let params = OrderHistorySearchParams::builder()
+            .symbol("SOL_USDC")
+            .sort_direction(SortDirection::Desc)
+            .limit(10)
+            .build()
+            .unwrap();
+        assert_eq!(params.symbol.as_deref(), Some("SOL_USDC"));
+        assert_eq!(params.sort_direction, Some(SortDirection::Desc));
+        assert_eq!(params.offset, None);
+
+        assert_eq!(
+            FillHistorySearchParams::builder().time_range(5, 1).build().unwrap_err(),
+            ParamError::InvalidTimeRange { from: 5, to: 1 }
+        );
+    }
+
+    #[test]
+    fn test_aggregate_fills_of_multi_fill_order() {
+        let fill = |price: &str, quantity: &str, fee: &str, fee_symbol: &str| -> HistoricFill {
+            serde_json::from_str(&format!(
+                r#"{{"clientId":null,"fee":"{fee}","feeSymbol":"{fee_symbol}","isMaker":false,"orderId":"1",
+                "price":"{price}","quantity":"{quantity}","side":"Bid","symbol":"SOL_USDC","timestamp":"2025-01-01T00:00:00"}}"#
+            ))
+            .unwrap()
+        };
+        let fills = [
+            fill("150.10", "0.5", "0.0005", "SOL"),
+            fill("150.20", "1.25", "0.00125", "SOL"),
+            fill("150.35", "0.3", "0.045", "USDC"),
+        ];
+
+        let aggregate = aggregate_fills(&fills).unwrap();
+        assert_eq!(aggregate.base_quantity, dec!(2.05));
+        // 75.05 + 187.75 + 45.105
+        assert_eq!(aggregate.quote_quantity, dec!(307.905));
+        assert_eq!(aggregate.average_price.unwrap().round_dp(6), dec!(150.197561));
+        assert_eq!(aggregate.fees["SOL"], dec!(0.00175));
+        assert_eq!(aggregate.fees["USDC"], dec!(0.045));
+
+        assert_eq!(aggregate_fills(&[]), Ok(FillAggregate::default()));
+
+        // Near the top of the range the notional no longer fits.
+        let huge = fill("1", "1", "0", "SOL");
+        let huge = HistoricFill {
+            quantity: Decimal::MAX,
+            price: dec!(2),
+            ..huge
+        };
+        assert_eq!(aggregate_fills(std::slice::from_ref(&huge)), Err(DecimalOverflow));
+        let huge = HistoricFill {
+            price: Decimal::ONE,
+            ..huge
+        };
+        assert_eq!(aggregate_fills(&[huge.clone(), huge]), Err(DecimalOverflow));
+    }
+
+    #[test]
+    fn test_correlate_fills_by_client_id() {
+        let order = |id: &str, client_id: &str| -> HistoricOrder {
+            serde_json::from_str(&format!(
+                r#"{{"id":"{id}","clientId":{client_id},"createdAt":"2025-01-01T00:00:00","orderType":"Market",
+                "selfTradePrevention":"RejectTaker","status":"Filled","side":"Ask","symbol":"SOL_USDC"}}"#
+            ))
+            .unwrap()
+        };
+        let fill = |order_id: &str, client_id: &str| -> HistoricFill {
+            serde_json::from_str(&format!(
+                r#"{{"clientId":{client_id},"fee":"0","feeSymbol":"USDC","isMaker":false,"orderId":"{order_id}",
+                "price":"150","quantity":"1","side":"Ask","symbol":"SOL_USDC","timestamp":"2025-01-01T00:00:00"}}"#
+            ))
+            .unwrap()
+        };
+        let orders = [order("1", "7"), order("2", "null")];
+        let fills = [
+            fill("1", "\"7\""),
+            fill("1", "null"),
+            fill("2", "null"),
+            fill("3", "\"9\""),
+        ];
+
+        let grouped = correlate_fills_by_client_id(&orders, &fills);
+        assert_eq!(grouped[&Some("7".to_string())].len(), 2);
+        assert_eq!(grouped[&Some("9".to_string())].len(), 1);
+        assert_eq!(grouped[&None].len(), 1);
+        assert_eq!(grouped[&None][0].order_id, "2");
+    }
+
+    #[test]
+    fn test_sort_history_by() {
+        let fill = |id: &str, price: &str, quantity: &str, time: &str| -> HistoricFill {
+            serde_json::from_str(&format!(
+                r#"{{"clientId":null,"fee":"0","feeSymbol":"USDC","isMaker":false,"orderId":"{id}",
+                "price":"{price}","quantity":"{quantity}","side":"Ask","symbol":"SOL_USDC","timestamp":"{time}"}}"#
+            ))
+            .unwrap()
+        };
+        let ids = |fills: &[HistoricFill]| fills.iter().map(|f| f.order_id.clone()).collect::<Vec<_>>();
+        let mut fills = vec![
+            fill("1", "150", "2", "2025-01-01T00:00:02"),
+            fill("2", "149", "3", "2025-01-01T00:00:01"),
+            fill("3", "151", "1", "2025-01-01T00:00:03"),
+        ];
+
+        sort_history_by(&mut fills, HistorySortKey::Time, SortDirection::Asc);
+        assert_eq!(ids(&fills), ["2", "1", "3"]);
+        sort_history_by(&mut fills, HistorySortKey::Time, SortDirection::Desc);
+        assert_eq!(ids(&fills), ["3", "1", "2"]);
+        sort_history_by(&mut fills, HistorySortKey::Price, SortDirection::Asc);
+        assert_eq!(ids(&fills), ["2", "1", "3"]);
+        sort_history_by(&mut fills, HistorySortKey::Price, SortDirection::Desc);
+        assert_eq!(ids(&fills), ["3", "1", "2"]);
+        sort_history_by(&mut fills, HistorySortKey::Quantity, SortDirection::Asc);
+        assert_eq!(ids(&fills), ["3", "1", "2"]);
+        sort_history_by(&mut fills, HistorySortKey::Quantity, SortDirection::Desc);
+        assert_eq!(ids(&fills), ["2", "1", "3"]);
+
+        let order = |id: &str, price: Option<&str>, quantity: Option<&str>, time: &str| -> HistoricOrder {
+            let field = |value: Option<&str>| value.map_or("null".to_string(), |v| format!(r#""{v}""#));
+            serde_json::from_str(&format!(
+                r#"{{"id":"{id}","createdAt":"{time}","orderType":"Limit","price":{},"quantity":{},
+                "selfTradePrevention":"RejectTaker","status":"New","side":"Bid","symbol":"SOL_USDC"}}"#,
+                field(price),
+                field(quantity),
+            ))
+            .unwrap()
+        };
+        let ids = |orders: &[HistoricOrder]| orders.iter().map(|o| o.id.clone()).collect::<Vec<_>>();
+        let mut orders = vec![
+            order("1", None, Some("2"), "2025-01-01T00:00:03"),
+            order("2", Some("150"), None, "2025-01-01T00:00:01"),
+            order("3", Some("149"), Some("5"), "2025-01-01T00:00:02"),
+            order("4", None, None, "2025-01-01T00:00:04"),
+        ];
+
+        sort_history_by(&mut orders, HistorySortKey::Time, SortDirection::Asc);
+        assert_eq!(ids(&orders), ["2", "3", "1", "4"]);
+        sort_history_by(&mut orders, HistorySortKey::Time, SortDirection::Desc);
+        assert_eq!(ids(&orders), ["4", "1", "3", "2"]);
+        // Orders without a price or quantity come last either way, in their previous order.
+        sort_history_by(&mut orders, HistorySortKey::Price, SortDirection::Asc);
+        assert_eq!(ids(&orders), ["3", "2", "4", "1"]);
+        sort_history_by(&mut orders, HistorySortKey::Price, SortDirection::Desc);
+        assert_eq!(ids(&orders), ["2", "3", "4", "1"]);
+        sort_history_by(&mut orders, HistorySortKey::Quantity, SortDirection::Asc);
+        assert_eq!(ids(&orders), ["1", "3", "2", "4"]);
+        sort_history_by(&mut orders, HistorySortKey::Quantity, SortDirection::Desc);
+        assert_eq!(ids(&orders), ["3", "1", "2", "4"]);
+    }
+
+    #[test]
+    fn test_order_expiry_reason_descriptions_are_distinct() {
+        use std::collections::HashSet;
+        use strum::IntoEnumIterator;
+
+        let mut seen = HashSet::new();
+        for reason in OrderExpiryReason::iter() {
+            let description = reason.description();
+            assert!(!description.is_empty(), "{reason} has no description");
+            assert!(seen.insert(description), "{reason} shares its description");
+        }
+    }
+}
@@ -3,23 +3,29 @@
 //! This module contains various types used across the Backpack Exchange API,
 //! including enums and structs for capital, markets, orders, trades, and user data.
 
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter, EnumString};
 
 pub mod account;
+pub mod analytics;
 pub mod borrow_lend;
 pub mod capital;
+#[cfg(feature = "csv")]
+pub mod export;
 pub mod futures;
 pub mod history;
 pub mod margin;
 pub mod markets;
 pub mod order;
+pub mod order_book;
 pub mod rfq;
 pub mod strategies;
+pub mod system;
 pub mod trade;
 pub mod user;
 
-#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, Default, EnumString, PartialEq, Eq, Hash, EnumIter)]
+#[derive(Debug, Display, Clone, Serialize, Deserialize, Default, EnumString, PartialEq, Eq, Hash, EnumIter)]
 #[strum(serialize_all = "PascalCase")]
 #[serde(rename_all = "PascalCase")]
 pub enum Blockchain {
@@ -39,6 +45,80 @@ pub enum Blockchain {
     XRP,
     Litecoin,
     Berachain,
+    /// A blockchain not yet known to this crate. Keeps responses listing newly added chains
+    /// deserializable.
+    #[strum(default)]
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+static BLOCKCHAINS: [Blockchain; 15] = [
+    Blockchain::Solana,
+    Blockchain::Ethereum,
+    Blockchain::Polygon,
+    Blockchain::Bitcoin,
+    Blockchain::Internal,
+    Blockchain::EqualsMoney,
+    Blockchain::Cardano,
+    Blockchain::Hyperliquid,
+    Blockchain::Story,
+    Blockchain::Bsc,
+    Blockchain::Dogecoin,
+    Blockchain::Sui,
+    Blockchain::XRP,
+    Blockchain::Litecoin,
+    Blockchain::Berachain,
+];
+
+impl Blockchain {
+    /// Returns every blockchain known to this crate, excluding [`Blockchain::Unknown`].
+    pub fn all() -> &'static [Blockchain] {
+        &BLOCKCHAINS
+    }
+
+    /// Returns a human readable name for the blockchain.
+    pub fn display_name(&self) -> &str {
+        match self {
+            Self::Solana => "Solana",
+            Self::Ethereum => "Ethereum",
+            Self::Polygon => "Polygon",
+            Self::Bitcoin => "Bitcoin",
+            Self::Internal => "Internal Transfer",
+            Self::EqualsMoney => "Equals Money",
+            Self::Cardano => "Cardano",
+            Self::Hyperliquid => "Hyperliquid",
+            Self::Story => "Story",
+            Self::Bsc => "BNB Smart Chain",
+            Self::Dogecoin => "Dogecoin",
+            Self::Sui => "Sui",
+            Self::XRP => "XRP Ledger",
+            Self::Litecoin => "Litecoin",
+            Self::Berachain => "Berachain",
+            Self::Unknown(name) => name,
+        }
+    }
+
+    /// Returns the block explorer URL of a transaction, or `None` for chains without a
+    /// public explorer.
+    pub fn explorer_tx_url(&self, txid: &str) -> Option<String> {
+        let base = match self {
+            Self::Solana => "https://solscan.io/tx/",
+            Self::Ethereum => "https://etherscan.io/tx/",
+            Self::Polygon => "https://polygonscan.com/tx/",
+            Self::Bitcoin => "https://mempool.space/tx/",
+            Self::Cardano => "https://cardanoscan.io/transaction/",
+            Self::Hyperliquid => "https://app.hyperliquid.xyz/explorer/tx/",
+            Self::Story => "https://www.storyscan.io/tx/",
+            Self::Bsc => "https://bscscan.com/tx/",
+            Self::Dogecoin => "https://blockchair.com/dogecoin/transaction/",
+            Self::Sui => "https://suiscan.xyz/mainnet/tx/",
+            Self::XRP => "https://livenet.xrpl.org/transactions/",
+            Self::Litecoin => "https://blockchair.com/litecoin/transaction/",
+            Self::Berachain => "https://berascan.com/tx/",
+            Self::Internal | Self::EqualsMoney | Self::Unknown(_) => return None,
+        };
+        Some(format!("{base}{txid}"))
+    }
 }
 
 #[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, PartialEq, Eq, Hash)]
@@ -48,3 +128,80 @@ pub enum SortDirection {
     Asc,
     Desc,
 }
+
+/// A calculation exceeded the range of [`Decimal`], e.g. a notional of absurdly large
+/// quantities and prices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Decimal overflow")]
+pub struct DecimalOverflow;
+
+/// Returns `a * b`, or [`DecimalOverflow`] instead of panicking if it is out of range.
+pub(crate) fn checked_mul(a: Decimal, b: Decimal) -> Result<Decimal, DecimalOverflow> {
+    a.checked_mul(b).ok_or(DecimalOverflow)
+}
+
+/// Returns `a + b`, or [`DecimalOverflow`] instead of panicking if it is out of range.
+pub(crate) fn checked_add(a: Decimal, b: Decimal) -> Result<Decimal, DecimalOverflow> {
+    a.checked_add(b).ok_or(DecimalOverflow)
+}
+
+/// A stream update about a single market.
+pub trait SymbolUpdate {
+    /// Returns the symbol of the market the update is about.
+    fn symbol(&self) -> &str;
+}
+
+macro_rules! impl_symbol_update {
+    ($($update:ty),* $(,)?) => {
+        $(
+            impl SymbolUpdate for $update {
+                fn symbol(&self) -> &str {
+                    &self.symbol
+                }
+            }
+        )*
+    };
+}
+
+impl_symbol_update!(
+    futures::OpenInterestUpdate,
+    futures::PositionUpdate,
+    markets::KlineUpdate,
+    markets::MarkPriceUpdate,
+    markets::OrderBookDepthUpdate,
+    markets::TickerStatisticsUpdate,
+    markets::TickerUpdate,
+    order::OrderUpdate,
+    trade::LiquidationUpdate,
+    trade::TradeUpdate,
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_blockchain_all_is_exhaustive() {
+        let known: Vec<_> = Blockchain::iter()
+            .filter(|b| !matches!(b, Blockchain::Unknown(_)))
+            .collect();
+        assert_eq!(Blockchain::all(), known.as_slice());
+    }
+
+    #[test]
+    fn test_blockchain_unknown_fallback() {
+        let chain: Blockchain = serde_json::from_str(r#""Monad""#).unwrap();
+        assert_eq!(chain, Blockchain::Unknown("Monad".to_string()));
+        assert_eq!(serde_json::to_string(&chain).unwrap(), r#""Monad""#);
+        assert_eq!(chain.to_string(), "Monad");
+
+        assert_eq!(Blockchain::from_str("Solana").unwrap(), Blockchain::Solana);
+        assert_eq!(
+            Blockchain::from_str("Monad").unwrap(),
+            Blockchain::Unknown("Monad".to_string())
+        );
+        assert_eq!(serde_json::from_str::<Blockchain>(r#""Bsc""#).unwrap(), Blockchain::Bsc);
+    }
+}
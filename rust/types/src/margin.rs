@@ -1,11 +1,127 @@
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, MathematicalOps};
 use serde::{Deserialize, Serialize};
 
+use crate::borrow_lend::BorrowLendPosition;
+use crate::futures::FuturePosition;
+use crate::{checked_add, checked_mul, DecimalOverflow};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct MarginFunction {
     pub base: Decimal,
     pub factor: Decimal,
     #[serde(rename = "type")]
     pub function_type: String,
 }
+
+impl MarginFunction {
+    /// Evaluates the margin fraction for a position of the given notional size.
+    ///
+    /// Only the `sqrt` function is known, computed as `max(base, factor * sqrt(notional))`.
+    /// Returns `None` for other function types.
+    pub fn evaluate(&self, notional: Decimal) -> Option<Decimal> {
+        match self.function_type.as_str() {
+            "sqrt" => Some(self.base.max(self.factor * notional.abs().sqrt()?)),
+            _ => None,
+        }
+    }
+}
+
+/// Margin consumed by an account's positions, see [`total_margin_used`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MarginUsage {
+    /// Initial margin, the sum of each position's `imf * |net_exposure_notional|`.
+    pub initial: Decimal,
+    /// Maintenance margin, the sum of each position's `mmf * |net_exposure_notional|`.
+    pub maintenance: Decimal,
+}
+
+/// Sums the initial and maintenance margin consumed by futures and borrow positions.
+///
+/// Each position contributes its notional weighted by its current `imf` and `mmf`. Long
+/// and short futures positions both consume margin, while borrow lend positions only do when
+/// borrowing: lent assets (a positive `net_quantity`) count as collateral and are skipped.
+/// Returns [`DecimalOverflow`] if a sum is out of range.
+pub fn total_margin_used(
+    futures: &[FuturePosition],
+    borrows: &[BorrowLendPosition],
+) -> Result<MarginUsage, DecimalOverflow> {
+    let futures = futures
+        .iter()
+        .map(|position| (position.net_exposure_notional, position.imf, position.mmf));
+    let borrows = borrows
+        .iter()
+        .filter(|position| position.net_quantity.is_sign_negative())
+        .map(|position| (position.net_exposure_notional, position.imf, position.mmf));
+
+    futures
+        .chain(borrows)
+        .try_fold(MarginUsage::default(), |usage, (notional, imf, mmf)| {
+            Ok(MarginUsage {
+                initial: checked_add(usage.initial, checked_mul(imf, notional.abs())?)?,
+                maintenance: checked_add(usage.maintenance, checked_mul(mmf, notional.abs())?)?,
+            })
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn future_position(net_exposure_notional: &str) -> FuturePosition {
+        serde_json::from_str(&format!(
+            r#"{{"breakEvenPrice":"100","cumulativeFundingPayment":"0","entryPrice":"100","estLiquidationPrice":"95",
+            "imf":"0.1","imfFunction":{{"base":"0.1","factor":"0.0001","type":"sqrt"}},"markPrice":"100",
+            "mmf":"0.05","mmfFunction":{{"base":"0.05","factor":"0.0001","type":"sqrt"}},"netCost":"1000",
+            "netExposureNotional":"{net_exposure_notional}","netExposureQuantity":"10","netQuantity":"10",
+            "pnlRealized":"0","pnlUnrealized":"0","positionId":"1","subaccountId":null,"symbol":"SOL_USDC_PERP","userId":1}}"#
+        ))
+        .unwrap()
+    }
+
+    fn borrow_lend_position(net_quantity: &str, net_exposure_notional: &str) -> BorrowLendPosition {
+        serde_json::from_str(&format!(
+            r#"{{"cumulativeInterest":"0","id":"1","symbol":"USDC","imf":"0.2",
+            "imfFunction":{{"base":"0.2","factor":"0","type":"sqrt"}},"markPrice":"1","mmf":"0.1",
+            "mmfFunction":{{"base":"0.1","factor":"0","type":"sqrt"}},"netExposureNotional":"{net_exposure_notional}",
+            "netExposureQuantity":"{net_quantity}","netQuantity":"{net_quantity}"}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_total_margin_used_across_futures_and_borrows() {
+        let futures = [future_position("1000"), future_position("-500")];
+        let borrows = [
+            borrow_lend_position("-250", "-250"),
+            // Lent, so collateral rather than margin used.
+            borrow_lend_position("400", "400"),
+        ];
+
+        let usage = total_margin_used(&futures, &borrows).unwrap();
+        // 0.1 * (1000 + 500) + 0.2 * 250
+        assert_eq!(usage.initial, dec!(200));
+        // 0.05 * (1000 + 500) + 0.1 * 250
+        assert_eq!(usage.maintenance, dec!(100));
+        assert_eq!(total_margin_used(&[], &[]), Ok(MarginUsage::default()));
+    }
+
+    #[test]
+    fn test_total_margin_used_reports_overflow() {
+        let mut position = future_position("0");
+        position.net_exposure_notional = Decimal::MAX;
+        position.imf = dec!(2);
+        assert_eq!(total_margin_used(&[position], &[]), Err(DecimalOverflow));
+
+        // Each product fits, but not their sum.
+        let mut position = future_position("0");
+        position.net_exposure_notional = Decimal::MAX;
+        position.imf = Decimal::ONE;
+        assert_eq!(
+            total_margin_used(&[position.clone(), position], &[]),
+            Err(DecimalOverflow)
+        );
+    }
+}
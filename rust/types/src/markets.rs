@@ -1,18 +1,34 @@
 use crate::margin::MarginFunction;
-use crate::Blockchain;
-use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use crate::order::{ExecuteOrderPayload, Side};
+use crate::{Blockchain, SymbolUpdate};
+use chrono::{DateTime, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
+use std::fmt;
 use strum::{Display, EnumString};
 
 /// An asset is most of the time a crypto coin that can have multiple representations
 /// across different blockchains. For example, USDT.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct Asset {
     /// Identifier
-    symbol: String,
+    pub symbol: String,
     /// See [`Token`]
-    tokens: Vec<Token>,
+    pub tokens: Vec<Token>,
+}
+
+impl Asset {
+    /// Returns the blockchains the asset can be deposited from.
+    pub fn depositable_chains(&self) -> impl Iterator<Item = &Blockchain> {
+        self.tokens.iter().filter(|t| t.deposit_enabled).map(|t| &t.blockchain)
+    }
+
+    /// Returns the blockchains the asset can be withdrawn to.
+    pub fn withdrawable_chains(&self) -> impl Iterator<Item = &Blockchain> {
+        self.tokens.iter().filter(|t| t.withdraw_enabled).map(|t| &t.blockchain)
+    }
 }
 
 #[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, PartialEq, Eq, Hash)]
@@ -27,8 +43,17 @@ pub enum MarketType {
     Rfq,
 }
 
+impl MarketType {
+    /// Returns `true` for futures markets, which have a mark price: perpetuals and dated
+    /// futures.
+    pub const fn is_futures(&self) -> bool {
+        matches!(self, Self::Perp | Self::Iperp | Self::Dated)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct Ticker {
     pub symbol: String,
     pub first_price: Decimal,
@@ -41,9 +66,39 @@ pub struct Ticker {
     pub trades: String,
 }
 
+/// Returns the `n` tickers with the highest and the `n` with the lowest
+/// `price_change_percent`, best gainer and worst loser first. Ties are ordered by symbol.
+pub fn top_movers(tickers: &[Ticker], n: usize) -> (Vec<&Ticker>, Vec<&Ticker>) {
+    let mut sorted: Vec<&Ticker> = tickers.iter().collect();
+    sorted.sort_by(|a, b| {
+        b.price_change_percent
+            .cmp(&a.price_change_percent)
+            .then_with(|| a.symbol.cmp(&b.symbol))
+    });
+    let gainers = sorted.iter().take(n).copied().collect();
+    sorted.sort_by(|a, b| {
+        a.price_change_percent
+            .cmp(&b.price_change_percent)
+            .then_with(|| a.symbol.cmp(&b.symbol))
+    });
+    sorted.truncate(n);
+    (gainers, sorted)
+}
+
+/// Returns the `n` tickers with the highest volume, highest first. Ties are ordered by symbol.
+///
+/// The volume is in the base asset, so it is only comparable across markets sharing a base.
+pub fn by_volume(tickers: &[Ticker], n: usize) -> Vec<&Ticker> {
+    let mut sorted: Vec<&Ticker> = tickers.iter().collect();
+    sorted.sort_by(|a, b| b.volume.cmp(&a.volume).then_with(|| a.symbol.cmp(&b.symbol)));
+    sorted.truncate(n);
+    sorted
+}
+
 /// Sent by an exchange to indicate a change in the order book, such as the execution of a bid or ask.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct TickerUpdate {
     /// Event type
     #[serde(rename = "e")]
@@ -81,6 +136,7 @@ pub struct TickerUpdate {
 /// Ticker stream pushes 24hr rolling statistics for a single symbol every second.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct TickerStatisticsUpdate {
     /// Event type
     #[serde(rename = "e")]
@@ -123,6 +179,32 @@ pub struct TickerStatisticsUpdate {
     pub number_of_trades: u64,
 }
 
+impl TickerStatisticsUpdate {
+    /// Price change over the window, `last_price - first_price`, as in [`Ticker::price_change`].
+    pub fn price_change(&self) -> Decimal {
+        self.last_price - self.first_price
+    }
+
+    /// Price change as a fraction of `first_price`, as in [`Ticker::price_change_percent`].
+    /// Returns zero when `first_price` is zero.
+    pub fn price_change_percent(&self) -> Decimal {
+        if self.first_price.is_zero() {
+            return Decimal::ZERO;
+        }
+        self.price_change() / self.first_price
+    }
+
+    /// Returns `true` if the update is older than `max_age` at `now`, judged by its event
+    /// time. Thinly traded markets keep pushing the same statistics with old prices, so check
+    /// the age before quoting off them.
+    pub fn is_stale(&self, now: DateTime<Utc>, max_age: chrono::Duration) -> bool {
+        match DateTime::from_timestamp_micros(self.event_time) {
+            Some(event_time) => now - event_time > max_age,
+            None => true,
+        }
+    }
+}
+
 #[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, PartialEq, Eq, Hash)]
 #[strum(serialize_all = "PascalCase")]
 #[serde(rename_all = "PascalCase")]
@@ -134,10 +216,21 @@ pub enum OrderBookState {
     PostOnly,
 }
 
+/// The resolution of a prediction market.
+#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, PartialEq, Eq, Hash)]
+#[strum(serialize_all = "PascalCase")]
+#[serde(rename_all = "PascalCase")]
+pub enum PredictionOutcome {
+    Yes,
+    No,
+    Unresolved,
+}
+
 /// A market is where two assets are exchanged. Most notably, in a `BTC/USDC` pair
 /// `BTC` is the base and `USDC` is the quote.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct Market {
     /// The `Market` identifier.
     pub symbol: String,
@@ -165,6 +258,16 @@ pub struct Market {
     pub order_book_state: OrderBookState,
     /// Market created at time.
     pub created_at: chrono::NaiveDateTime,
+    /// When a dated market expires and settles. Unset for other markets.
+    pub expiry: Option<chrono::NaiveDateTime>,
+    /// The asset a dated market settles in. Unset for other markets.
+    pub settlement_symbol: Option<String>,
+    /// Where a prediction market's outcome is determined from. Unset for other markets.
+    pub resolution_source: Option<String>,
+    /// When a prediction market resolves. Unset for other markets.
+    pub resolution_time: Option<chrono::NaiveDateTime>,
+    /// The resolution of a prediction market. Unset for other markets.
+    pub outcome: Option<PredictionOutcome>,
 }
 
 impl Market {
@@ -181,10 +284,326 @@ impl Market {
     pub const fn quantity_decimal_places(&self) -> u32 {
         self.filters.quantity.step_size.scale()
     }
+
+    /// Returns the price increment, [`PriceFilters::tick_size`].
+    pub const fn tick_size(&self) -> Decimal {
+        self.filters.price.tick_size
+    }
+
+    /// Returns the quantity increment, [`QuantityFilters::step_size`].
+    pub const fn step_size(&self) -> Decimal {
+        self.filters.quantity.step_size
+    }
+
+    /// Returns `true` if the market has an expiry at or before `now`. Markets without an
+    /// expiry never expire.
+    pub fn is_expired(&self, now: chrono::NaiveDateTime) -> bool {
+        self.expiry.is_some_and(|expiry| expiry <= now)
+    }
+
+    /// Returns the time left until the market expires, negative once it has expired, or
+    /// `None` for markets without an expiry.
+    pub fn time_to_expiry(&self, now: chrono::NaiveDateTime) -> Option<chrono::TimeDelta> {
+        self.expiry.map(|expiry| expiry - now)
+    }
+
+    /// Snaps a price to the market's tick size using the given rounding mode.
+    pub fn round_price(&self, price: Decimal, side: Side, rounding: PriceRounding) -> Decimal {
+        round_price_to_tick(price, self.tick_size(), side, rounding)
+    }
+
+    /// Snaps a quantity down to the market's step size, so the result never exceeds
+    /// the requested quantity.
+    pub fn round_quantity(&self, quantity: Decimal) -> Decimal {
+        round_to_increment(quantity, self.step_size(), RoundingStrategy::ToZero)
+    }
+
+    /// Returns the tradable quantity closest to `quantity`: snapped down to the step size,
+    /// then raised to [`QuantityFilters::min_quantity`] if below it, or lowered to
+    /// [`QuantityFilters::max_quantity`] if above it.
+    ///
+    /// The bounds are themselves snapped to the step size, inwards, so the result is always a
+    /// multiple of it. Unlike [`Market::round_quantity`], the result may exceed `quantity`.
+    pub fn clamp_quantity(&self, quantity: Decimal) -> Decimal {
+        let filters = &self.filters.quantity;
+        let mut quantity = self.round_quantity(quantity);
+        if let Some(max) = filters.max_quantity {
+            quantity = quantity.min(self.round_quantity(max));
+        }
+        let min = round_to_increment(filters.min_quantity, self.step_size(), RoundingStrategy::AwayFromZero);
+        quantity.max(min)
+    }
+
+    /// Returns a copy of the market's price and quantity filters, see [`FilterSnapshot`].
+    pub fn filter_snapshot(&self) -> FilterSnapshot {
+        let (price, quantity) = (&self.filters.price, &self.filters.quantity);
+        FilterSnapshot {
+            tick_size: price.tick_size,
+            min_price: price.min_price,
+            max_price: price.max_price,
+            step_size: quantity.step_size,
+            min_quantity: quantity.min_quantity,
+            max_quantity: quantity.max_quantity,
+        }
+    }
+
+    /// Checks that the prices of a stream update are multiples of the tick size, and its
+    /// quantities multiples of the step size. Returns the first value that isn't.
+    pub fn check_scale<U: ScaledUpdate>(&self, update: &U) -> Result<(), ScaleMismatch> {
+        let aligned = |value: Decimal, increment: Decimal| increment.is_zero() || (value % increment).is_zero();
+        if let Some(price) = update
+            .prices()
+            .into_iter()
+            .find(|&price| !aligned(price, self.tick_size()))
+        {
+            return Err(ScaleMismatch::Price {
+                symbol: update.symbol().to_string(),
+                price,
+                tick_size: self.tick_size(),
+            });
+        }
+        if let Some(quantity) = update
+            .quantities()
+            .into_iter()
+            .find(|&quantity| !aligned(quantity, self.step_size()))
+        {
+            return Err(ScaleMismatch::Quantity {
+                symbol: update.symbol().to_string(),
+                quantity,
+                step_size: self.step_size(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks a leverage against the market's [`LeverageFilters`]. Markets without leverage
+    /// filters, such as spot markets, accept any leverage.
+    pub fn validate_leverage(&self, leverage: Decimal) -> Result<(), LeverageError> {
+        let Some(filters) = &self.filters.leverage else {
+            return Ok(());
+        };
+        if leverage < filters.min_leverage {
+            return Err(LeverageError::BelowMin {
+                leverage,
+                min: filters.min_leverage,
+            });
+        }
+        if leverage > filters.max_leverage {
+            return Err(LeverageError::AboveMax {
+                leverage,
+                max: filters.max_leverage,
+            });
+        }
+        if !filters.step_size.is_zero() && !((leverage - filters.min_leverage) % filters.step_size).is_zero() {
+            return Err(LeverageError::InvalidStep {
+                leverage,
+                step_size: filters.step_size,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when a leverage falls outside a market's [`LeverageFilters`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LeverageError {
+    #[error("leverage {leverage} is below the minimum of {min}")]
+    BelowMin { leverage: Decimal, min: Decimal },
+    #[error("leverage {leverage} is above the maximum of {max}")]
+    AboveMax { leverage: Decimal, max: Decimal },
+    #[error("leverage {leverage} is not a multiple of the step size {step_size}")]
+    InvalidStep { leverage: Decimal, step_size: Decimal },
+}
+
+/// The price and quantity filters of a market, for validating and rounding orders on a hot
+/// path.
+///
+/// The snapshot is `Copy` and owns everything it needs, so its methods never lock, allocate
+/// or wait, unlike looking the market up in the client's cache for every order. It isn't
+/// updated when the exchange changes the market's filters: take a new one from the refreshed
+/// [`Market`] when that happens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterSnapshot {
+    pub tick_size: Decimal,
+    pub min_price: Decimal,
+    pub max_price: Option<Decimal>,
+    pub step_size: Decimal,
+    pub min_quantity: Decimal,
+    pub max_quantity: Option<Decimal>,
+}
+
+impl FilterSnapshot {
+    /// Checks a price against the price bounds and the tick size.
+    pub fn validate_price(&self, price: Decimal) -> Result<(), FilterError> {
+        if price < self.min_price {
+            return Err(FilterError::PriceBelowMin {
+                price,
+                min: self.min_price,
+            });
+        }
+        if let Some(max) = self.max_price.filter(|&max| price > max) {
+            return Err(FilterError::PriceAboveMax { price, max });
+        }
+        if !self.tick_size.is_zero() && !(price % self.tick_size).is_zero() {
+            return Err(FilterError::InvalidTick {
+                price,
+                tick_size: self.tick_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks a quantity against the quantity bounds and the step size.
+    pub fn validate_quantity(&self, quantity: Decimal) -> Result<(), FilterError> {
+        if quantity < self.min_quantity {
+            return Err(FilterError::QuantityBelowMin {
+                quantity,
+                min: self.min_quantity,
+            });
+        }
+        if let Some(max) = self.max_quantity.filter(|&max| quantity > max) {
+            return Err(FilterError::QuantityAboveMax { quantity, max });
+        }
+        if !self.step_size.is_zero() && !(quantity % self.step_size).is_zero() {
+            return Err(FilterError::InvalidStep {
+                quantity,
+                step_size: self.step_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the price and quantity of an order, when set. The symbol isn't checked, so the
+    /// snapshot must be of the order's market.
+    pub fn validate(&self, order: &ExecuteOrderPayload) -> Result<(), FilterError> {
+        if let Some(price) = order.price {
+            self.validate_price(price)?;
+        }
+        if let Some(quantity) = order.quantity {
+            self.validate_quantity(quantity)?;
+        }
+        Ok(())
+    }
+
+    /// Snaps a price to the tick size, as [`Market::round_price`].
+    pub fn round_price(&self, price: Decimal, side: Side, rounding: PriceRounding) -> Decimal {
+        round_price_to_tick(price, self.tick_size, side, rounding)
+    }
+
+    /// Snaps a quantity down to the step size, as [`Market::round_quantity`].
+    pub fn round_quantity(&self, quantity: Decimal) -> Decimal {
+        round_to_increment(quantity, self.step_size, RoundingStrategy::ToZero)
+    }
+}
+
+/// Error returned when an order doesn't pass a market's filters, see [`FilterSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FilterError {
+    #[error("price {price} is below the minimum of {min}")]
+    PriceBelowMin { price: Decimal, min: Decimal },
+    #[error("price {price} is above the maximum of {max}")]
+    PriceAboveMax { price: Decimal, max: Decimal },
+    #[error("price {price} is not a multiple of the tick size {tick_size}")]
+    InvalidTick { price: Decimal, tick_size: Decimal },
+    #[error("quantity {quantity} is below the minimum of {min}")]
+    QuantityBelowMin { quantity: Decimal, min: Decimal },
+    #[error("quantity {quantity} is above the maximum of {max}")]
+    QuantityAboveMax { quantity: Decimal, max: Decimal },
+    #[error("quantity {quantity} is not a multiple of the step size {step_size}")]
+    InvalidStep { quantity: Decimal, step_size: Decimal },
+}
+
+/// A stream update carrying prices and quantities that should conform to its market's
+/// filters, see [`Market::check_scale`].
+pub trait ScaledUpdate: SymbolUpdate {
+    /// Returns the prices of the update, which should be multiples of the tick size.
+    fn prices(&self) -> Vec<Decimal>;
+
+    /// Returns the quantities of the update, which should be multiples of the step size.
+    fn quantities(&self) -> Vec<Decimal>;
+}
+
+impl ScaledUpdate for OrderBookDepthUpdate {
+    fn prices(&self) -> Vec<Decimal> {
+        self.asks.iter().chain(&self.bids).map(|(price, _)| *price).collect()
+    }
+
+    fn quantities(&self) -> Vec<Decimal> {
+        self.asks
+            .iter()
+            .chain(&self.bids)
+            .map(|(_, quantity)| *quantity)
+            .collect()
+    }
+}
+
+/// Only the prices are checked: the volumes add up trades over a whole day.
+impl ScaledUpdate for TickerStatisticsUpdate {
+    fn prices(&self) -> Vec<Decimal> {
+        vec![self.first_price, self.last_price, self.high_price, self.low_price]
+    }
+
+    fn quantities(&self) -> Vec<Decimal> {
+        Vec::new()
+    }
+}
+
+/// A stream value that isn't a multiple of its market's increment, see
+/// [`Market::check_scale`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ScaleMismatch {
+    #[error("{symbol}: price {price} is not a multiple of the tick size {tick_size}")]
+    Price {
+        symbol: String,
+        price: Decimal,
+        tick_size: Decimal,
+    },
+    #[error("{symbol}: quantity {quantity} is not a multiple of the step size {step_size}")]
+    Quantity {
+        symbol: String,
+        quantity: Decimal,
+        step_size: Decimal,
+    },
+}
+
+/// How a price is snapped to a market's tick size.
+#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, Default, EnumString, PartialEq, Eq, Hash)]
+#[strum(serialize_all = "PascalCase")]
+#[serde(rename_all = "PascalCase")]
+pub enum PriceRounding {
+    /// Round to the closest tick.
+    #[default]
+    Nearest,
+    /// Round towards the other side of the book: bids up, asks down.
+    TowardSpread,
+    /// Round away from the other side of the book: bids down, asks up. This never makes an
+    /// order more aggressive than requested.
+    AwayFromSpread,
+}
+
+fn round_price_to_tick(price: Decimal, tick_size: Decimal, side: Side, rounding: PriceRounding) -> Decimal {
+    let strategy = match (rounding, side) {
+        (PriceRounding::Nearest, _) => RoundingStrategy::MidpointNearestEven,
+        (PriceRounding::TowardSpread, Side::Bid) | (PriceRounding::AwayFromSpread, Side::Ask) => {
+            RoundingStrategy::ToPositiveInfinity
+        }
+        (PriceRounding::TowardSpread, Side::Ask) | (PriceRounding::AwayFromSpread, Side::Bid) => {
+            RoundingStrategy::ToNegativeInfinity
+        }
+    };
+    round_to_increment(price, tick_size, strategy)
+}
+
+fn round_to_increment(value: Decimal, increment: Decimal, strategy: RoundingStrategy) -> Decimal {
+    if increment.is_zero() {
+        return value;
+    }
+    ((value / increment).round_dp_with_strategy(0, strategy) * increment).round_dp(increment.scale())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct MarketFilters {
     /// Defines the price rules for the order book.
     pub price: PriceFilters,
@@ -195,6 +614,7 @@ pub struct MarketFilters {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct PriceBandMarkPrice {
     /// Maximum allowed multiplier move from mean price.
     pub max_multiplier: Decimal,
@@ -204,6 +624,7 @@ pub struct PriceBandMarkPrice {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct PriceBandMeanPremium {
     /// Maximum allowed deviation from the mean premium. E.g. if tolerance_pct is 0.05 (5%), and
     /// the mean premium is 5%, then orders will be prevented from being placed if the premium
@@ -213,6 +634,7 @@ pub struct PriceBandMeanPremium {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct PriceFilters {
     /// Minimum price the order book will allow.
     pub min_price: Decimal,
@@ -246,6 +668,7 @@ pub struct PriceFilters {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct QuantityFilters {
     /// Minimum quantity the order book will allow. For futures, this will be the threshold at
     /// which a position gets closed and so it should be as close as possible, preferably equal, to
@@ -259,6 +682,7 @@ pub struct QuantityFilters {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct LeverageFilters {
     pub min_leverage: Decimal,
     pub max_leverage: Decimal,
@@ -267,6 +691,7 @@ pub struct LeverageFilters {
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct Token {
     pub blockchain: Blockchain,
     pub deposit_enabled: bool,
@@ -279,15 +704,89 @@ pub struct Token {
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct OrderBookDepth {
     pub asks: Vec<(Decimal, Decimal)>,
     pub bids: Vec<(Decimal, Decimal)>,
-    pub last_update_id: String,
+    /// Sent as a string by the REST API. Parsed to match
+    /// [`OrderBookDepthUpdate::last_update_id`] so a snapshot can be synchronized with the
+    /// depth stream.
+    #[serde(deserialize_with = "deserialize_u64_from_str_or_number")]
+    pub last_update_id: u64,
     pub timestamp: u64,
 }
 
+impl OrderBookDepth {
+    /// Returns the best bid and ask of the snapshot, or `None` if either side is empty.
+    ///
+    /// The levels are scanned for the highest bid and lowest ask, so they don't need to be
+    /// sorted.
+    pub fn top_of_book(&self) -> Option<TopOfBook> {
+        let (bid_price, bid_quantity) = self.bids.iter().copied().max_by_key(|(price, _)| *price)?;
+        let (ask_price, ask_quantity) = self.asks.iter().copied().min_by_key(|(price, _)| *price)?;
+        Some(TopOfBook {
+            bid_price,
+            bid_quantity,
+            ask_price,
+            ask_quantity,
+        })
+    }
+
+    /// Returns the touch price a taker order on `side` trades against first: the lowest ask
+    /// for a bid, the highest bid for an ask. `None` if that side of the book is empty.
+    pub fn touch(&self, side: Side) -> Option<Decimal> {
+        match side {
+            Side::Bid => self.asks.iter().map(|(price, _)| *price).min(),
+            Side::Ask => self.bids.iter().map(|(price, _)| *price).max(),
+        }
+    }
+}
+
+/// The best bid and ask of an order book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopOfBook {
+    pub bid_price: Decimal,
+    pub bid_quantity: Decimal,
+    pub ask_price: Decimal,
+    pub ask_quantity: Decimal,
+}
+
+/// Deserializes a `u64` given either as a JSON number or as a string.
+fn deserialize_u64_from_str_or_number<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct U64Visitor;
+
+    impl Visitor<'_> for U64Visitor {
+        type Value = u64;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an unsigned integer or a string containing one")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            v.parse().map_err(serde::de::Error::custom)
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            Ok(v)
+        }
+    }
+
+    deserializer.deserialize_any(U64Visitor)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct OrderBookDepthUpdate {
     /// Event type
     #[serde(rename = "e")]
@@ -367,8 +866,39 @@ pub enum KlineInterval {
     OneMonth,
 }
 
+impl KlineInterval {
+    /// Returns the length of the interval in seconds, or `None` for [`KlineInterval::OneMonth`]
+    /// as months vary in length.
+    pub fn duration_secs(&self) -> Option<u64> {
+        const MINUTE: u64 = 60;
+        const HOUR: u64 = 60 * MINUTE;
+        const DAY: u64 = 24 * HOUR;
+        Some(match self {
+            Self::OneMin => MINUTE,
+            Self::ThreeMin => 3 * MINUTE,
+            Self::FiveMin => 5 * MINUTE,
+            Self::FifteenMin => 15 * MINUTE,
+            Self::ThirtyMin => 30 * MINUTE,
+            Self::OneHour => HOUR,
+            Self::TwoHour => 2 * HOUR,
+            Self::FourHour => 4 * HOUR,
+            Self::SixHour => 6 * HOUR,
+            Self::EightHour => 8 * HOUR,
+            Self::TwelveHour => 12 * HOUR,
+            Self::OneDay => DAY,
+            Self::ThreeDay => 3 * DAY,
+            Self::OneWeek => 7 * DAY,
+            Self::OneMonth => return None,
+        })
+    }
+}
+
+/// Format of the `start` and `end` times of a [`Kline`].
+pub(crate) const KLINE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct Kline {
     pub start: String,
     pub end: String,
@@ -384,6 +914,7 @@ pub struct Kline {
 /// Ticker stream pushes 24hr rolling statistics for a single symbol every second.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct KlineUpdate {
     /// Event type
     #[serde(rename = "e")]
@@ -434,16 +965,66 @@ pub struct KlineUpdate {
     pub is_closed: bool,
 }
 
+impl KlineUpdate {
+    /// Returns the candle as a [`Kline`], in the format of the klines REST endpoint, so it
+    /// can be appended to a fetched series.
+    ///
+    /// Only closed updates (`is_closed`) should be converted: an open candle is still
+    /// changing and would need to be replaced later. The stream doesn't carry the quote
+    /// volume, so `quote_volume` is zero.
+    pub fn to_kline(&self) -> Kline {
+        // `NaiveDateTime` displays whole seconds as `KLINE_TIME_FORMAT`.
+        Kline {
+            start: self.start.to_string(),
+            end: self.end.to_string(),
+            open: Some(self.open),
+            high: Some(self.high),
+            low: Some(self.low),
+            close: Some(self.close),
+            volume: self.base_asset_volume,
+            quote_volume: Decimal::ZERO,
+            trades: self.number_of_trades.to_string(),
+        }
+    }
+}
+
+/// See [`KlineUpdate::to_kline`]; only closed updates should be converted.
+impl From<KlineUpdate> for Kline {
+    fn from(update: KlineUpdate) -> Self {
+        update.to_kline()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct FundingRate {
     pub symbol: String,
+    /// End of the funding interval, as sent by the API: a UTC time without offset such as
+    /// `2025-06-04T08:00:00`. Use [`FundingRate::interval_end`] to parse it.
     pub interval_end_timestamp: String,
     pub funding_rate: Decimal,
 }
 
+impl FundingRate {
+    /// Returns the end of the funding interval, parsed from `interval_end_timestamp`.
+    ///
+    /// Timestamps without an offset are taken as UTC; RFC 3339 timestamps with an offset are
+    /// also accepted.
+    pub fn interval_end(&self) -> Result<DateTime<Utc>, chrono::ParseError> {
+        let timestamp = self.interval_end_timestamp.as_str();
+        match DateTime::parse_from_rfc3339(timestamp) {
+            Ok(time) => Ok(time.to_utc()),
+            Err(_) => {
+                chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S%.f").map(|time| time.and_utc())
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct MarkPrice {
     pub symbol: String,
     pub funding_rate: Decimal,
@@ -452,8 +1033,43 @@ pub struct MarkPrice {
     pub next_funding_timestamp: u64,
 }
 
+impl MarkPrice {
+    /// Returns the next funding time, parsed from `next_funding_timestamp` (milliseconds).
+    pub fn next_funding(&self) -> Option<DateTime<Utc>> {
+        i64::try_from(self.next_funding_timestamp)
+            .ok()
+            .and_then(DateTime::from_timestamp_millis)
+    }
+}
+
+/// A flattened view of a [`MarkPrice`], ready to be rendered in a funding table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundingSnapshot {
+    pub symbol: String,
+    pub funding_rate: Decimal,
+    pub mark_price: Decimal,
+    pub index_price: Decimal,
+    /// The next funding time, or `None` if the timestamp is out of range, see
+    /// [`MarkPrice::next_funding`].
+    pub next_funding: Option<DateTime<Utc>>,
+}
+
+impl From<MarkPrice> for FundingSnapshot {
+    fn from(mark_price: MarkPrice) -> Self {
+        Self {
+            next_funding: mark_price.next_funding(),
+            symbol: mark_price.symbol,
+            funding_rate: mark_price.funding_rate,
+            mark_price: mark_price.mark_price,
+            index_price: mark_price.index_price,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct MarkPriceUpdate {
     /// Event type
     #[serde(rename = "e")]
@@ -479,7 +1095,7 @@ pub struct MarkPriceUpdate {
     #[serde(rename = "i")]
     pub index_price: Decimal,
 
-    /// Next funding timestamp in microseconds
+    /// Next funding timestamp in milliseconds, unlike the other timestamps of the update
     #[serde(rename = "n")]
     pub funding_timestamp: u64,
 
@@ -488,6 +1104,23 @@ pub struct MarkPriceUpdate {
     pub engine_timestamp: i64,
 }
 
+impl MarkPriceUpdate {
+    /// Returns the time of the next funding, parsed from `funding_timestamp` (milliseconds).
+    ///
+    /// Timestamps beyond the range of [`DateTime`] saturate to [`DateTime::<Utc>::MAX_UTC`].
+    pub fn next_funding_utc(&self) -> DateTime<Utc> {
+        i64::try_from(self.funding_timestamp)
+            .ok()
+            .and_then(DateTime::from_timestamp_millis)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+
+    /// Returns the time left until the next funding at `now`, or zero once it is due.
+    pub fn time_to_funding(&self, now: DateTime<Utc>) -> chrono::Duration {
+        (self.next_funding_utc() - now).max(chrono::Duration::zero())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -528,9 +1161,117 @@ mod test {
             open_interest_limit: None,
             order_book_state: OrderBookState::Open,
             created_at: Default::default(),
+            expiry: None,
+            settlement_symbol: None,
+            resolution_source: None,
+            resolution_time: None,
+            outcome: None,
+        }
+    }
+
+    fn ticker(symbol: &str, price_change_percent: Decimal, volume: Decimal) -> Ticker {
+        Ticker {
+            symbol: symbol.to_string(),
+            first_price: dec!(1),
+            last_price: dec!(1),
+            price_change: Decimal::ZERO,
+            price_change_percent,
+            high: dec!(1),
+            low: dec!(1),
+            volume,
+            trades: "0".to_string(),
         }
     }
 
+    #[test]
+    fn test_top_movers_and_volume() {
+        let tickers = vec![
+            ticker("C", dec!(0.05), dec!(10)),
+            ticker("A", dec!(-0.02), dec!(30)),
+            ticker("B", dec!(0.05), dec!(30)),
+            ticker("D", dec!(-0.10), dec!(5)),
+        ];
+        let symbols = |tickers: Vec<&Ticker>| tickers.iter().map(|t| t.symbol.clone()).collect::<Vec<_>>();
+
+        let (gainers, losers) = top_movers(&tickers, 2);
+        assert_eq!(symbols(gainers), ["B", "C"]);
+        assert_eq!(symbols(losers), ["D", "A"]);
+        assert_eq!(symbols(by_volume(&tickers, 3)), ["A", "B", "C"]);
+        assert_eq!(top_movers(&tickers, 10).0.len(), 4);
+    }
+
+    #[test]
+    fn test_ticker_statistics_change_matches_rest_ticker() {
+        let ticker: Ticker = serde_json::from_str(
+            r#"{"symbol":"SOL_USDC","firstPrice":"140","lastPrice":"142.1","priceChange":"2.1","priceChangePercent":"0.015","high":"143","low":"139.9","volume":"1000","trades":"42"}"#,
+        )
+        .unwrap();
+        let update: TickerStatisticsUpdate = serde_json::from_str(
+            r#"{"e":"ticker","E":1694687965941000,"s":"SOL_USDC","o":"140","c":"142.1","h":"143","l":"139.9","v":"1000","V":"141000","n":42}"#,
+        )
+        .unwrap();
+        assert_eq!(update.price_change(), ticker.price_change);
+        assert_eq!(update.price_change_percent(), ticker.price_change_percent);
+
+        let flat = TickerStatisticsUpdate {
+            first_price: Decimal::ZERO,
+            ..update
+        };
+        assert_eq!(flat.price_change_percent(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_ticker_statistics_staleness() {
+        let update: TickerStatisticsUpdate = serde_json::from_str(
+            r#"{"e":"ticker","E":1694687965941000,"s":"SOL_USDC","o":"140","c":"142.1","h":"143","l":"139.9","v":"1000","V":"141000","n":42}"#,
+        )
+        .unwrap();
+        let event_time = DateTime::from_timestamp_micros(update.event_time).unwrap();
+        let max_age = chrono::Duration::seconds(5);
+
+        assert!(!update.is_stale(event_time, max_age));
+        assert!(!update.is_stale(event_time + max_age, max_age));
+        assert!(update.is_stale(event_time + chrono::Duration::minutes(10), max_age));
+    }
+
+    #[test]
+    fn test_validate_leverage() {
+        let spot = get_test_market();
+        assert_eq!(spot.validate_leverage(dec!(1000)), Ok(()));
+
+        let mut perp = get_test_market();
+        perp.market_type = MarketType::Perp;
+        perp.filters.leverage = Some(LeverageFilters {
+            min_leverage: dec!(1),
+            max_leverage: dec!(20),
+            step_size: dec!(0.5),
+        });
+        assert_eq!(perp.validate_leverage(dec!(1)), Ok(()));
+        assert_eq!(perp.validate_leverage(dec!(10.5)), Ok(()));
+        assert_eq!(perp.validate_leverage(dec!(20)), Ok(()));
+        assert_eq!(
+            perp.validate_leverage(dec!(0.5)),
+            Err(LeverageError::BelowMin {
+                leverage: dec!(0.5),
+                min: dec!(1)
+            })
+        );
+        assert_eq!(
+            perp.validate_leverage(dec!(25)),
+            Err(LeverageError::AboveMax {
+                leverage: dec!(25),
+                max: dec!(20)
+            })
+        );
+        assert_eq!(
+            perp.validate_leverage(dec!(10.2)),
+            Err(LeverageError::InvalidStep {
+                leverage: dec!(10.2),
+                step_size: dec!(0.5)
+            })
+        );
+    }
+
     #[test]
     fn test_decimal_places_on_price_filters_4() {
         let market = get_test_market();
@@ -543,6 +1284,140 @@ mod test {
         assert_eq!(market.quantity_decimal_places(), 2);
     }
 
+    #[test]
+    fn test_dated_market_expiry() {
+        let market: Market = serde_json::from_str(
+            r#"{
+                "symbol": "BTC_USDC_20251226",
+                "baseSymbol": "BTC",
+                "quoteSymbol": "USDC",
+                "marketType": "DATED",
+                "filters": {
+                    "price": { "minPrice": "0.1", "tickSize": "0.1" },
+                    "quantity": { "minQuantity": "0.0001", "stepSize": "0.0001" }
+                },
+                "orderBookState": "Open",
+                "createdAt": "2025-06-01T00:00:00",
+                "expiry": "2025-12-26T08:00:00",
+                "settlementSymbol": "USDC"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(market.market_type, MarketType::Dated);
+        assert_eq!(market.settlement_symbol.as_deref(), Some("USDC"));
+
+        let now = chrono::NaiveDateTime::parse_from_str("2025-12-25T08:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+        assert!(!market.is_expired(now));
+        assert_eq!(market.time_to_expiry(now), Some(chrono::TimeDelta::days(1)));
+        assert!(market.is_expired(market.expiry.unwrap()));
+
+        let spot = get_test_market();
+        assert!(!spot.is_expired(now));
+        assert_eq!(spot.time_to_expiry(now), None);
+    }
+
+    #[test]
+    fn test_prediction_outcome_round_trip() {
+        for outcome in [
+            PredictionOutcome::Yes,
+            PredictionOutcome::No,
+            PredictionOutcome::Unresolved,
+        ] {
+            let json = serde_json::to_string(&outcome).unwrap();
+            assert_eq!(json, format!("\"{outcome}\""));
+            assert_eq!(serde_json::from_str::<PredictionOutcome>(&json).unwrap(), outcome);
+        }
+    }
+
+    #[test]
+    fn test_increments() {
+        let market = get_test_market();
+        assert_eq!(market.tick_size(), dec!(0.0001));
+        assert_eq!(market.step_size(), dec!(0.01));
+    }
+
+    #[test]
+    fn test_round_price() {
+        let market = get_test_market();
+        let price = dec!(1.23456);
+        assert_eq!(
+            market.round_price(price, Side::Bid, PriceRounding::Nearest),
+            dec!(1.2346)
+        );
+        assert_eq!(
+            market.round_price(price, Side::Bid, PriceRounding::TowardSpread),
+            dec!(1.2346)
+        );
+        assert_eq!(
+            market.round_price(price, Side::Ask, PriceRounding::TowardSpread),
+            dec!(1.2345)
+        );
+        assert_eq!(
+            market.round_price(price, Side::Bid, PriceRounding::AwayFromSpread),
+            dec!(1.2345)
+        );
+        assert_eq!(
+            market.round_price(price, Side::Ask, PriceRounding::AwayFromSpread),
+            dec!(1.2346)
+        );
+    }
+
+    #[test]
+    fn test_round_quantity() {
+        let market = get_test_market();
+        assert_eq!(market.round_quantity(dec!(1.239)), dec!(1.23));
+        assert_eq!(market.round_quantity(dec!(5)), dec!(5));
+    }
+
+    #[test]
+    fn test_clamp_quantity() {
+        let mut market = get_test_market();
+        market.filters.quantity.min_quantity = dec!(0.05);
+        market.filters.quantity.max_quantity = Some(dec!(100));
+        assert_eq!(market.clamp_quantity(dec!(1.239)), dec!(1.23));
+        // Just below the minimum, and rounding below it.
+        assert_eq!(market.clamp_quantity(dec!(0.049)), dec!(0.05));
+        assert_eq!(market.clamp_quantity(dec!(0.0501)), dec!(0.05));
+        assert_eq!(market.clamp_quantity(dec!(0)), dec!(0.05));
+        // Just above the maximum.
+        assert_eq!(market.clamp_quantity(dec!(100.01)), dec!(100));
+        assert_eq!(market.clamp_quantity(dec!(100)), dec!(100));
+
+        // Bounds off the step are snapped inwards.
+        market.filters.quantity.min_quantity = dec!(0.045);
+        market.filters.quantity.max_quantity = Some(dec!(99.999));
+        assert_eq!(market.clamp_quantity(dec!(0.01)), dec!(0.05));
+        assert_eq!(market.clamp_quantity(dec!(120)), dec!(99.99));
+
+        market.filters.quantity.max_quantity = None;
+        assert_eq!(market.clamp_quantity(dec!(1000000.001)), dec!(1000000));
+    }
+
+    #[test]
+    fn test_funding_snapshot_from_mark_price() {
+        let mark_price = MarkPrice {
+            symbol: "SOL_USDC_PERP".to_string(),
+            funding_rate: dec!(0.0001),
+            index_price: dec!(173.44),
+            mark_price: dec!(173.35),
+            next_funding_timestamp: 1747296000000,
+        };
+        let snapshot = FundingSnapshot::from(mark_price.clone());
+        let expected = chrono::NaiveDate::from_ymd_opt(2025, 5, 15)
+            .and_then(|d| d.and_hms_opt(8, 0, 0))
+            .unwrap()
+            .and_utc();
+        assert_eq!(snapshot.next_funding, Some(expected));
+        assert_eq!(snapshot.funding_rate, dec!(0.0001));
+
+        // Out of range timestamps are missing rather than the UNIX epoch.
+        let out_of_range = MarkPrice {
+            next_funding_timestamp: u64::MAX,
+            ..mark_price
+        };
+        assert_eq!(FundingSnapshot::from(out_of_range).next_funding, None);
+    }
+
     #[test]
     fn test_mark_price_update_parse() {
         let data = r#"
@@ -562,5 +1437,206 @@ mod test {
         assert_eq!(mark_price_update.symbol, "SOL_USDC_PERP".to_string());
         assert_eq!(mark_price_update.funding_rate, dec!(-0.0000039641039274236048482914));
         assert_eq!(mark_price_update.mark_price, dec!(173.35998175));
+
+        let next_funding = mark_price_update.next_funding_utc();
+        assert_eq!(next_funding.naive_utc().to_string(), "2025-05-15 08:00:00");
+        let now = DateTime::from_timestamp_micros(mark_price_update.event_time).unwrap();
+        let countdown = mark_price_update.time_to_funding(now);
+        assert_eq!(
+            countdown,
+            chrono::Duration::microseconds(1747296000000000 - 1747291031914525)
+        );
+        assert_eq!((countdown.num_minutes(), countdown.num_seconds() % 60), (82, 48));
+        assert_eq!(
+            mark_price_update.time_to_funding(next_funding + chrono::Duration::seconds(1)),
+            chrono::Duration::zero()
+        );
+    }
+
+    #[test]
+    fn test_kline_update_to_kline() {
+        let update: KlineUpdate = serde_json::from_str(
+            r#"{"e":"kline","E":1694687692980000,"s":"SOL_USD","t":"2024-09-11T12:00:00",
+            "T":"2024-09-11T12:01:00","o":"18.75","c":"19.25","h":"19.80","l":"18.50","v":"32123",
+            "n":93828,"X":true}"#,
+        )
+        .unwrap();
+        let kline = Kline::from(update.clone());
+        assert_eq!(kline.start, "2024-09-11 12:00:00");
+        assert_eq!(kline.end, "2024-09-11 12:01:00");
+        assert_eq!(
+            (kline.open, kline.high, kline.low, kline.close),
+            (
+                Some(dec!(18.75)),
+                Some(dec!(19.80)),
+                Some(dec!(18.50)),
+                Some(dec!(19.25))
+            )
+        );
+        assert_eq!(kline.volume, dec!(32123));
+        assert_eq!(kline.quote_volume, Decimal::ZERO);
+        assert_eq!(kline.trades, "93828");
+
+        // The converted candle reads back like one fetched over REST.
+        let json = serde_json::to_string(&update.to_kline()).unwrap();
+        let rest: Kline = serde_json::from_str(&json).unwrap();
+        assert_eq!(rest.start, kline.start);
+        assert_eq!(rest.close, kline.close);
+    }
+
+    #[test]
+    fn test_order_book_depth_last_update_id() {
+        let depth: OrderBookDepth = serde_json::from_str(
+            r#"{"asks":[["142.2","3.5"]],"bids":[["142.1","12"]],"lastUpdateId":"1746231486","timestamp":1746231486123456}"#,
+        )
+        .unwrap();
+        assert_eq!(depth.last_update_id, 1746231486);
+
+        let roundtrip: OrderBookDepth = serde_json::from_str(&serde_json::to_string(&depth).unwrap()).unwrap();
+        assert_eq!(roundtrip.last_update_id, 1746231486);
+
+        assert!(
+            serde_json::from_str::<OrderBookDepth>(r#"{"asks":[],"bids":[],"lastUpdateId":"abc","timestamp":0}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_funding_rate_interval_end() {
+        let rate: FundingRate = serde_json::from_str(
+            r#"{"symbol":"SOL_USDC_PERP","intervalEndTimestamp":"2025-06-04T08:00:00","fundingRate":"0.0000125"}"#,
+        )
+        .unwrap();
+        let expected = DateTime::from_timestamp(1_749_024_000, 0).unwrap();
+        assert_eq!(rate.interval_end().unwrap(), expected);
+
+        let rate = FundingRate {
+            interval_end_timestamp: "2025-06-04T10:00:00.000+02:00".to_string(),
+            ..rate
+        };
+        assert_eq!(rate.interval_end().unwrap(), expected);
+
+        let rate = FundingRate {
+            interval_end_timestamp: "soon".to_string(),
+            ..rate
+        };
+        assert!(rate.interval_end().is_err());
+    }
+
+    #[test]
+    fn test_filter_snapshot() {
+        let mut market = get_test_market();
+        market.filters.quantity.max_quantity = Some(dec!(100));
+        let filters = market.filter_snapshot();
+
+        let order = ExecuteOrderPayload {
+            symbol: "TEST_MARKET".to_string(),
+            price: Some(dec!(1.2345)),
+            quantity: Some(dec!(2.5)),
+            ..Default::default()
+        };
+        assert_eq!(filters.validate(&order), Ok(()));
+        assert_eq!(
+            filters.validate(&ExecuteOrderPayload {
+                price: Some(dec!(1.23456)),
+                ..order.clone()
+            }),
+            Err(FilterError::InvalidTick {
+                price: dec!(1.23456),
+                tick_size: dec!(0.0001)
+            })
+        );
+        assert_eq!(
+            filters.validate_price(dec!(0.00001)),
+            Err(FilterError::PriceBelowMin {
+                price: dec!(0.00001),
+                min: dec!(0.0001)
+            })
+        );
+        assert_eq!(
+            filters.validate_quantity(dec!(100.01)),
+            Err(FilterError::QuantityAboveMax {
+                quantity: dec!(100.01),
+                max: dec!(100)
+            })
+        );
+        assert!(matches!(
+            filters.validate_quantity(dec!(0.001)),
+            Err(FilterError::QuantityBelowMin { .. })
+        ));
+        assert!(matches!(
+            filters.validate_quantity(dec!(1.005)),
+            Err(FilterError::InvalidStep { .. })
+        ));
+
+        // Rounding matches the market's.
+        for price in [dec!(1.23456), dec!(1.23454)] {
+            for side in [Side::Bid, Side::Ask] {
+                assert_eq!(
+                    filters.round_price(price, side, PriceRounding::AwayFromSpread),
+                    market.round_price(price, side, PriceRounding::AwayFromSpread)
+                );
+            }
+        }
+        assert_eq!(filters.round_quantity(dec!(1.005)), market.round_quantity(dec!(1.005)));
+    }
+
+    #[test]
+    fn test_check_scale() {
+        let market = get_test_market();
+        let depth: OrderBookDepthUpdate = serde_json::from_str(
+            r#"{"e":"depth","E":1,"s":"TEST_MARKET","T":1,"U":1,"u":2,"a":[["1.2345","3"]],"b":[["1.2","0"]]}"#,
+        )
+        .unwrap();
+        assert_eq!(market.check_scale(&depth), Ok(()));
+
+        let depth = OrderBookDepthUpdate {
+            bids: vec![(dec!(1.23456), dec!(1))],
+            ..depth
+        };
+        assert_eq!(
+            market.check_scale(&depth),
+            Err(ScaleMismatch::Price {
+                symbol: "TEST_MARKET".to_string(),
+                price: dec!(1.23456),
+                tick_size: dec!(0.0001),
+            })
+        );
+    }
+
+    #[test]
+    fn test_touch() {
+        let depth: OrderBookDepth = serde_json::from_str(
+            r#"{"asks":[["152","1"],["151","2"]],"bids":[["149","3"],["150","4"]],"lastUpdateId":"1","timestamp":1}"#,
+        )
+        .unwrap();
+        assert_eq!(depth.touch(Side::Bid), Some(dec!(151)));
+        assert_eq!(depth.touch(Side::Ask), Some(dec!(150)));
+
+        let depth = OrderBookDepth { asks: vec![], ..depth };
+        assert_eq!(depth.touch(Side::Bid), None);
+        assert_eq!(depth.touch(Side::Ask), Some(dec!(150)));
+    }
+
+    #[test]
+    fn test_top_of_book_unsorted_levels() {
+        let mut depth = OrderBookDepth {
+            asks: vec![(dec!(101), dec!(1)), (dec!(100.5), dec!(2)), (dec!(102), dec!(3))],
+            bids: vec![(dec!(99), dec!(4)), (dec!(100.2), dec!(5)), (dec!(98), dec!(6))],
+            last_update_id: 1,
+            timestamp: 0,
+        };
+        assert_eq!(
+            depth.top_of_book(),
+            Some(TopOfBook {
+                bid_price: dec!(100.2),
+                bid_quantity: dec!(5),
+                ask_price: dec!(100.5),
+                ask_quantity: dec!(2),
+            })
+        );
+
+        depth.asks.clear();
+        assert_eq!(depth.top_of_book(), None);
     }
 }
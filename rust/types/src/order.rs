@@ -4,6 +4,8 @@ use rust_decimal::{prelude::FromPrimitive, Decimal};
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
 use strum::{Display, EnumString};
 
+use crate::markets::Market;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TriggerBy {
     LastPrice,
@@ -95,6 +97,7 @@ fn parse_str(s: &str) -> Result<TriggerQuantity, &'static str> {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct MarketOrder {
     pub id: String,
     pub client_id: Option<u32>,
@@ -124,6 +127,7 @@ pub struct MarketOrder {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct LimitOrder {
     pub id: String,
     pub client_id: Option<u32>,
@@ -170,6 +174,78 @@ pub enum Order {
     Limit(LimitOrder),
 }
 
+/// Common accessors shared by live [`Order`]s and [`crate::history::HistoricOrder`]s, so
+/// reporting code can work over either.
+///
+/// The trait is object safe, so mixed collections can be stored as `Box<dyn OrderLike>`.
+pub trait OrderLike {
+    /// Unique ID of the order.
+    fn id(&self) -> &str;
+    /// The market symbol of the order.
+    fn symbol(&self) -> &str;
+    /// Side of the order.
+    fn side(&self) -> Side;
+    /// Status of the order.
+    fn status(&self) -> OrderStatus;
+    /// Limit price of the order, if any.
+    fn price(&self) -> Option<Decimal>;
+    /// Quantity of the order in the base asset, if it was given in base.
+    fn quantity(&self) -> Option<Decimal>;
+    /// Quantity of the order that has been filled, if known.
+    fn executed_quantity(&self) -> Option<Decimal>;
+}
+
+impl OrderLike for Order {
+    fn id(&self) -> &str {
+        match self {
+            Order::Market(order) => &order.id,
+            Order::Limit(order) => &order.id,
+        }
+    }
+
+    fn symbol(&self) -> &str {
+        match self {
+            Order::Market(order) => &order.symbol,
+            Order::Limit(order) => &order.symbol,
+        }
+    }
+
+    fn side(&self) -> Side {
+        match self {
+            Order::Market(order) => order.side,
+            Order::Limit(order) => order.side,
+        }
+    }
+
+    fn status(&self) -> OrderStatus {
+        match self {
+            Order::Market(order) => order.status,
+            Order::Limit(order) => order.status,
+        }
+    }
+
+    fn price(&self) -> Option<Decimal> {
+        match self {
+            Order::Market(_) => None,
+            Order::Limit(order) => Some(order.price),
+        }
+    }
+
+    fn quantity(&self) -> Option<Decimal> {
+        match self {
+            Order::Market(order) => order.quantity,
+            Order::Limit(order) => Some(order.quantity),
+        }
+    }
+
+    fn executed_quantity(&self) -> Option<Decimal> {
+        match self {
+            Order::Market(order) => Some(order.executed_quantity),
+            Order::Limit(order) => Some(order.executed_quantity),
+        }
+    }
+}
+
 #[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, Default, EnumString, PartialEq, Eq, Hash)]
 #[strum(serialize_all = "UPPERCASE")]
 #[serde(rename_all = "UPPERCASE")]
@@ -200,6 +276,25 @@ pub enum SlippageToleranceType {
     Percent,
 }
 
+impl SlippageToleranceType {
+    /// Returns the worst acceptable execution price for an order on `side`, given a
+    /// `reference` price and a `tolerance`.
+    ///
+    /// With [`SlippageToleranceType::TickSize`] the tolerance is a number of ticks of
+    /// `tick_size`; with [`SlippageToleranceType::Percent`] it is a percentage of the
+    /// reference price, so `1` means 1%. Buys may fill above the reference and sells below it.
+    pub fn bound_price(&self, reference: Decimal, tolerance: Decimal, tick_size: Decimal, side: Side) -> Decimal {
+        let offset = match self {
+            Self::TickSize => tolerance * tick_size,
+            Self::Percent => reference * tolerance / Decimal::ONE_HUNDRED,
+        };
+        match side {
+            Side::Bid => reference + offset,
+            Side::Ask => reference - offset,
+        }
+    }
+}
+
 #[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, Default, EnumString, PartialEq, Eq, Hash)]
 #[strum(serialize_all = "PascalCase")]
 #[serde(rename_all = "PascalCase")]
@@ -212,6 +307,24 @@ pub enum OrderStatus {
     PartiallyFilled,
     Triggered,
     TriggerPending,
+    /// A status added to the API after this version of the crate.
+    #[serde(other)]
+    Unknown,
+}
+
+impl OrderStatus {
+    /// Returns `true` if the order can no longer change: it was filled, cancelled or expired.
+    ///
+    /// [`OrderStatus::Unknown`] is not terminal, so polling loops keep waiting on a status
+    /// they don't recognize.
+    pub const fn is_terminal(&self) -> bool {
+        matches!(self, Self::Cancelled | Self::Expired | Self::Filled)
+    }
+
+    /// Returns `true` if the order may still change, that is if it is not terminal.
+    pub const fn is_active(&self) -> bool {
+        !self.is_terminal()
+    }
 }
 
 #[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, Default, EnumString, PartialEq, Eq, Hash)]
@@ -255,14 +368,14 @@ pub struct ExecuteOrderPayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop_loss_limit_price: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub stop_loss_trigger_by: Option<Decimal>,
+    pub stop_loss_trigger_by: Option<TriggerBy>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop_loss_trigger_price: Option<Decimal>,
     pub symbol: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub take_profit_limit_price: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub take_profit_trigger_by: Option<Decimal>,
+    pub take_profit_trigger_by: Option<TriggerBy>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub take_profit_trigger_price: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -279,6 +392,140 @@ pub struct ExecuteOrderPayload {
     pub slippage_tolerance_type: Option<SlippageToleranceType>,
 }
 
+impl ExecuteOrderPayload {
+    /// Sets the time in force. When unset it is not sent, and the exchange uses
+    /// [`TimeInForce::GTC`] for limit orders.
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+
+    /// Makes this a trigger order, placed once the `trigger_by` price crosses
+    /// `trigger_price`. The order type and price apply to the order placed on trigger.
+    pub fn with_trigger(mut self, trigger_price: Decimal, trigger_by: TriggerBy) -> Self {
+        self.trigger_price = Some(trigger_price);
+        self.trigger_by = Some(trigger_by);
+        self
+    }
+
+    /// Returns `true` if this is a trigger order, that is a standalone stop or take profit.
+    pub fn is_trigger_order(&self) -> bool {
+        self.trigger_price.is_some()
+    }
+
+    /// Checks the trigger fields of the order.
+    ///
+    /// `trigger_by` and `trigger_quantity` only apply to trigger orders, so they require a
+    /// `trigger_price`, which must be positive. A percentage trigger quantity must be above
+    /// zero and at most 100%, and an amount must be positive. Orders without any trigger
+    /// field are always valid.
+    pub fn validate_trigger(&self) -> Result<(), TriggerOrderError> {
+        let Some(trigger_price) = self.trigger_price else {
+            if self.trigger_by.is_some() || self.trigger_quantity.is_some() {
+                return Err(TriggerOrderError::MissingTriggerPrice);
+            }
+            return Ok(());
+        };
+        if trigger_price <= Decimal::ZERO {
+            return Err(TriggerOrderError::InvalidTriggerPrice(trigger_price));
+        }
+        match &self.trigger_quantity {
+            Some(TriggerQuantity::Percent(percent)) if *percent <= Decimal::ZERO || *percent > Decimal::ONE_HUNDRED => {
+                Err(TriggerOrderError::InvalidTriggerQuantity(TriggerQuantity::Percent(
+                    *percent,
+                )))
+            }
+            Some(TriggerQuantity::Amount(amount)) if *amount <= Decimal::ZERO => Err(
+                TriggerOrderError::InvalidTriggerQuantity(TriggerQuantity::Amount(*amount)),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that a post-only order would rest on the book given its current best bid and
+    /// ask, rather than be rejected by the exchange for taking liquidity.
+    ///
+    /// A post-only bid crosses at or above the best ask, and a post-only ask at or below the
+    /// best bid. Orders that are not post-only, have no price, or face an empty side always
+    /// pass.
+    pub fn check_post_only(
+        &self,
+        best_bid: Option<Decimal>,
+        best_ask: Option<Decimal>,
+    ) -> Result<(), PostOnlyWouldCross> {
+        let (Some(true), Some(price)) = (self.post_only, self.price) else {
+            return Ok(());
+        };
+        let opposite = match self.side {
+            Side::Bid => best_ask.filter(|ask| price >= *ask),
+            Side::Ask => best_bid.filter(|bid| price <= *bid),
+        };
+        match opposite {
+            Some(opposite) => Err(PostOnlyWouldCross {
+                side: self.side,
+                price,
+                opposite,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Formats the order's prices to the scale of the market's tick size and its quantity to
+    /// the scale of the step size, so `1.5` is sent as `1.50` on a market with a `0.01` tick.
+    ///
+    /// Values are never rounded: a value finer than the market's increments only has its
+    /// trailing zeros removed, and is left for the exchange to reject.
+    pub fn normalize_scale(mut self, market: &Market) -> Self {
+        let price_scale = market.tick_size().normalize().scale();
+        for price in [
+            &mut self.price,
+            &mut self.stop_loss_limit_price,
+            &mut self.stop_loss_trigger_price,
+            &mut self.take_profit_limit_price,
+            &mut self.take_profit_trigger_price,
+            &mut self.trigger_price,
+        ] {
+            *price = price.map(|p| with_scale(p, price_scale));
+        }
+        let quantity_scale = market.step_size().normalize().scale();
+        self.quantity = self.quantity.map(|q| with_scale(q, quantity_scale));
+        self
+    }
+}
+
+/// Error returned when the trigger fields of an [`ExecuteOrderPayload`] are inconsistent.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TriggerOrderError {
+    #[error("trigger_by and trigger_quantity require a trigger_price")]
+    MissingTriggerPrice,
+    #[error("trigger price {0} must be positive")]
+    InvalidTriggerPrice(Decimal),
+    #[error("invalid trigger quantity {0:?}")]
+    InvalidTriggerQuantity(TriggerQuantity),
+}
+
+/// A post-only order priced through the opposite side of the book, which the exchange would
+/// reject instead of placing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("post-only {side} at {price} would cross the opposite best price {opposite}")]
+pub struct PostOnlyWouldCross {
+    /// Side of the order.
+    pub side: Side,
+    /// Price of the order.
+    pub price: Decimal,
+    /// Best price on the opposite side of the book.
+    pub opposite: Decimal,
+}
+
+/// Returns `value` with exactly `scale` decimal places, or its shortest form if it has more.
+fn with_scale(value: Decimal, scale: u32) -> Decimal {
+    let mut value = value.normalize();
+    if value.scale() < scale {
+        value.rescale(scale);
+    }
+    value
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct CancelOrderPayload {
@@ -295,6 +542,35 @@ pub struct CancelOpenOrdersPayload {
     pub symbol: String,
 }
 
+/// Selects open orders by their flags, to cancel only some of them, such as the post-only
+/// quotes while keeping the reduce-only stops in place.
+///
+/// A flag set to `Some(value)` keeps only the orders whose flag equals `value`, and `None`
+/// keeps orders either way; the default filter matches every order. Market orders are never
+/// post-only, and orders without a reduce-only flag count as not reduce-only.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CancelAllFilter {
+    pub post_only: Option<bool>,
+    pub reduce_only: Option<bool>,
+}
+
+impl CancelAllFilter {
+    /// Returns `true` if the filter matches every order.
+    pub const fn matches_all(&self) -> bool {
+        self.post_only.is_none() && self.reduce_only.is_none()
+    }
+
+    /// Returns `true` if `order` passes the filter.
+    pub fn matches(&self, order: &Order) -> bool {
+        let (post_only, reduce_only) = match order {
+            Order::Market(order) => (false, order.reduce_only),
+            Order::Limit(order) => (order.post_only, order.reduce_only),
+        };
+        self.post_only.is_none_or(|flag| flag == post_only)
+            && self.reduce_only.is_none_or(|flag| flag == reduce_only.unwrap_or(false))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum OrderUpdateType {
@@ -309,6 +585,7 @@ pub enum OrderUpdateType {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct OrderUpdate {
     /// Event type
     #[serde(rename = "e")]
@@ -426,6 +703,10 @@ pub struct OrderUpdate {
     #[serde(rename = "V")]
     pub self_trade_prevention: SelfTradePrevention,
 
+    /// Reduce only
+    #[serde(rename = "r")]
+    pub reduce_only: Option<bool>,
+
     /// Engine timestamp in microseconds
     #[serde(rename = "T")]
     pub timestamp: i64,
@@ -440,6 +721,7 @@ pub struct OrderUpdate {
 }
 
 #[derive(Deserialize, Debug)]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct BulkOrderResponseError {
     pub code: String,
     pub message: String,
@@ -447,6 +729,7 @@ pub struct BulkOrderResponseError {
 
 #[derive(Deserialize, Debug)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum BulkOrderResponse {
     Success(Order),
     Error(BulkOrderResponseError),
@@ -465,6 +748,45 @@ mod tests {
     use rust_decimal_macros::dec;
     use serde_json::json;
 
+    #[test]
+    fn test_slippage_bound_price_tick_size() {
+        let tolerance = SlippageToleranceType::TickSize;
+        assert_eq!(
+            tolerance.bound_price(dec!(100), dec!(3), dec!(0.01), Side::Bid),
+            dec!(100.03)
+        );
+        assert_eq!(
+            tolerance.bound_price(dec!(100), dec!(3), dec!(0.01), Side::Ask),
+            dec!(99.97)
+        );
+    }
+
+    #[test]
+    fn test_slippage_bound_price_percent() {
+        let tolerance = SlippageToleranceType::Percent;
+        assert_eq!(
+            tolerance.bound_price(dec!(200), dec!(0.5), dec!(0.01), Side::Bid),
+            dec!(201)
+        );
+        assert_eq!(
+            tolerance.bound_price(dec!(200), dec!(0.5), dec!(0.01), Side::Ask),
+            dec!(199)
+        );
+    }
+
+    #[test]
+    fn test_time_in_force_skipped_when_unset() {
+        let payload = ExecuteOrderPayload {
+            symbol: "SOL_USDC".to_string(),
+            ..Default::default()
+        };
+        let body = serde_json::to_value(&payload).unwrap();
+        assert!(body.get("timeInForce").is_none());
+
+        let body = serde_json::to_value(payload.with_time_in_force(TimeInForce::IOC)).unwrap();
+        assert_eq!(body["timeInForce"], "IOC");
+    }
+
     #[test]
     fn both_forms_round_trip() {
         let q: TriggerQuantity = serde_json::from_value(json!("12.5%")).unwrap();
@@ -508,6 +830,118 @@ mod tests {
         assert_eq!(trigger_by_index_str, "\"IndexPrice\"");
     }
 
+    #[test]
+    fn test_trigger_order_body() {
+        let payload = ExecuteOrderPayload {
+            symbol: "SOL_USDC_PERP".to_string(),
+            side: Side::Ask,
+            order_type: OrderType::Market,
+            quantity: Some(dec!(1.5)),
+            reduce_only: Some(true),
+            trigger_quantity: Some(TriggerQuantity::Percent(dec!(50))),
+            ..Default::default()
+        }
+        .with_trigger(dec!(140.25), TriggerBy::MarkPrice);
+        assert!(payload.is_trigger_order());
+        assert_eq!(payload.validate_trigger(), Ok(()));
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            json!({
+                "orderType": "Market",
+                "quantity": "1.5",
+                "reduceOnly": true,
+                "side": "Ask",
+                "symbol": "SOL_USDC_PERP",
+                "triggerBy": "MarkPrice",
+                "triggerPrice": "140.25",
+                "triggerQuantity": "50%"
+            })
+        );
+
+        let limit = ExecuteOrderPayload {
+            symbol: "SOL_USDC".to_string(),
+            side: Side::Bid,
+            order_type: OrderType::Limit,
+            price: Some(dec!(151)),
+            quantity: Some(dec!(2)),
+            ..Default::default()
+        }
+        .with_trigger(dec!(150), TriggerBy::LastPrice);
+        assert_eq!(
+            serde_json::to_value(&limit).unwrap(),
+            json!({
+                "orderType": "Limit",
+                "price": "151",
+                "quantity": "2",
+                "side": "Bid",
+                "symbol": "SOL_USDC",
+                "triggerBy": "LastPrice",
+                "triggerPrice": "150"
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_trigger() {
+        let plain = ExecuteOrderPayload::default();
+        assert!(!plain.is_trigger_order());
+        assert_eq!(plain.validate_trigger(), Ok(()));
+
+        let payload = ExecuteOrderPayload {
+            trigger_by: Some(TriggerBy::IndexPrice),
+            ..Default::default()
+        };
+        assert_eq!(payload.validate_trigger(), Err(TriggerOrderError::MissingTriggerPrice));
+
+        let payload = payload.with_trigger(dec!(0), TriggerBy::IndexPrice);
+        assert_eq!(
+            payload.validate_trigger(),
+            Err(TriggerOrderError::InvalidTriggerPrice(dec!(0)))
+        );
+
+        let payload = ExecuteOrderPayload {
+            trigger_quantity: Some(TriggerQuantity::Percent(dec!(100.1))),
+            ..payload.with_trigger(dec!(1), TriggerBy::IndexPrice)
+        };
+        assert!(matches!(
+            payload.validate_trigger(),
+            Err(TriggerOrderError::InvalidTriggerQuantity(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_post_only() {
+        let bid = ExecuteOrderPayload {
+            side: Side::Bid,
+            order_type: OrderType::Limit,
+            price: Some(dec!(101)),
+            post_only: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            bid.check_post_only(Some(dec!(99)), Some(dec!(100.5))),
+            Err(PostOnlyWouldCross {
+                side: Side::Bid,
+                price: dec!(101),
+                opposite: dec!(100.5)
+            })
+        );
+        assert!(bid.check_post_only(Some(dec!(99)), Some(dec!(101))).is_err());
+        assert_eq!(bid.check_post_only(Some(dec!(99)), Some(dec!(101.5))), Ok(()));
+        assert_eq!(bid.check_post_only(Some(dec!(99)), None), Ok(()));
+
+        let ask = ExecuteOrderPayload {
+            side: Side::Ask,
+            price: Some(dec!(99)),
+            ..bid.clone()
+        };
+        assert!(ask.check_post_only(Some(dec!(99)), Some(dec!(100))).is_err());
+        assert_eq!(ask.check_post_only(Some(dec!(98.5)), Some(dec!(100))), Ok(()));
+
+        let taker = ExecuteOrderPayload { post_only: None, ..bid };
+        assert_eq!(taker.check_post_only(Some(dec!(99)), Some(dec!(100.5))), Ok(()));
+    }
+
     #[test]
     fn test_order_update() {
         let data = r#"
@@ -536,4 +970,42 @@ mod tests {
         let order_update: OrderUpdate = serde_json::from_str(data).unwrap();
         assert_eq!(order_update.trigger_price.unwrap(), dec!(178.55));
     }
+
+    #[test]
+    fn test_normalize_scale_matches_market_increments() {
+        let market: Market = serde_json::from_value(json!({
+            "symbol": "SOL_USDC",
+            "baseSymbol": "SOL",
+            "quoteSymbol": "USDC",
+            "marketType": "SPOT",
+            "filters": {
+                "price": { "minPrice": "0.01", "tickSize": "0.010" },
+                "quantity": { "minQuantity": "0.1", "stepSize": "0.1" }
+            },
+            "orderBookState": "Open",
+            "createdAt": "2025-01-01T00:00:00"
+        }))
+        .unwrap();
+        let payload = ExecuteOrderPayload {
+            price: Some(dec!(150.5)),
+            trigger_price: Some(dec!(151.000)),
+            quantity: Some(dec!(2.123)),
+            ..Default::default()
+        }
+        .normalize_scale(&market);
+
+        assert_eq!(payload.price.unwrap().to_string(), "150.50");
+        assert_eq!(payload.trigger_price.unwrap().to_string(), "151.00");
+        assert_eq!(payload.quantity.unwrap().to_string(), "2.123");
+    }
+
+    #[test]
+    fn test_unknown_order_status_is_not_terminal() {
+        let status: OrderStatus = serde_json::from_str(r#""PendingReview""#).unwrap();
+        assert_eq!(status, OrderStatus::Unknown);
+        assert!(!status.is_terminal());
+        assert!(status.is_active());
+        assert!(OrderStatus::Filled.is_terminal());
+        assert!(!OrderStatus::PartiallyFilled.is_terminal());
+    }
 }
@@ -0,0 +1,331 @@
+//! A locally maintained order book built from depth snapshots and updates.
+
+use std::collections::BTreeMap;
+
+use rust_decimal::Decimal;
+
+use crate::markets::{OrderBookDepth, OrderBookDepthUpdate};
+use crate::{checked_add, DecimalOverflow};
+
+/// Number of levels per side covered by [`OrderBook::checksum`].
+pub const CHECKSUM_DEPTH: usize = 25;
+
+/// An order book keyed by price.
+///
+/// Bids and asks are kept in `BTreeMap`s so the best levels can be read without sorting.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrderBook {
+    pub bids: BTreeMap<Decimal, Decimal>,
+    pub asks: BTreeMap<Decimal, Decimal>,
+    /// ID of the last update reflected in the book.
+    pub last_update_id: u64,
+}
+
+/// A depth update that doesn't follow the last update applied to an [`OrderBook`]: updates
+/// were missed and the book must be rebuilt from a fresh snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("order book update {first_update_id} does not follow update {last_update_id}")]
+pub struct SequenceGap {
+    /// ID of the last update applied to the book.
+    pub last_update_id: u64,
+    /// First update ID of the update that was rejected.
+    pub first_update_id: u64,
+}
+
+impl OrderBook {
+    /// Applies a depth update to the book.
+    ///
+    /// Each level of the update sets the quantity at its price on its side, and a level with
+    /// a zero quantity removes the price instead, so no empty level is ever left to shadow
+    /// the best bid or ask.
+    ///
+    /// Updates already reflected in the book (`last_update_id` at or below the book's) are
+    /// ignored. An update whose first ID skips past the next expected one is rejected with a
+    /// [`SequenceGap`] and leaves the book unchanged.
+    pub fn apply_update(&mut self, update: &OrderBookDepthUpdate) -> Result<(), SequenceGap> {
+        if update.last_update_id <= self.last_update_id {
+            return Ok(());
+        }
+        if update.first_update_id > self.last_update_id + 1 {
+            return Err(SequenceGap {
+                last_update_id: self.last_update_id,
+                first_update_id: update.first_update_id,
+            });
+        }
+        for (levels, side) in [(&update.bids, &mut self.bids), (&update.asks, &mut self.asks)] {
+            for &(price, quantity) in levels {
+                if quantity.is_zero() {
+                    side.remove(&price);
+                } else {
+                    side.insert(price, quantity);
+                }
+            }
+        }
+        self.last_update_id = update.last_update_id;
+        Ok(())
+    }
+
+    /// Returns the number of bid and ask levels whose quantity differs from `other`, counting
+    /// levels present in only one of the books.
+    pub fn diverging_levels(&self, other: &OrderBook) -> (usize, usize) {
+        fn diverging(a: &BTreeMap<Decimal, Decimal>, b: &BTreeMap<Decimal, Decimal>) -> usize {
+            let missing = b.keys().filter(|price| !a.contains_key(price)).count();
+            a.iter()
+                .filter(|(price, quantity)| b.get(price) != Some(quantity))
+                .count()
+                + missing
+        }
+        (diverging(&self.bids, &other.bids), diverging(&self.asks, &other.asks))
+    }
+
+    /// Returns the highest bid as `(price, quantity)`.
+    pub fn best_bid(&self) -> Option<(Decimal, Decimal)> {
+        self.bids.iter().next_back().map(|(p, q)| (*p, *q))
+    }
+
+    /// Returns the lowest ask as `(price, quantity)`.
+    pub fn best_ask(&self) -> Option<(Decimal, Decimal)> {
+        self.asks.iter().next().map(|(p, q)| (*p, *q))
+    }
+
+    /// Returns the number of bid levels.
+    pub fn bid_levels(&self) -> usize {
+        self.bids.len()
+    }
+
+    /// Returns the number of ask levels.
+    pub fn ask_levels(&self) -> usize {
+        self.asks.len()
+    }
+
+    /// Returns the quantity summed over all bid levels, or [`DecimalOverflow`] if the sum is
+    /// out of range.
+    pub fn total_bid_liquidity(&self) -> Result<Decimal, DecimalOverflow> {
+        total_liquidity(&self.bids)
+    }
+
+    /// Returns the quantity summed over all ask levels, or [`DecimalOverflow`] if the sum is
+    /// out of range.
+    pub fn total_ask_liquidity(&self) -> Result<Decimal, DecimalOverflow> {
+        total_liquidity(&self.asks)
+    }
+
+    /// Returns the imbalance between bid and ask liquidity, `(bids - asks) / (bids + asks)`.
+    ///
+    /// It ranges from `1` when the book only has bids to `-1` when it only has asks, so it is
+    /// positive when bids outweigh asks. An empty book has an imbalance of zero. Returns
+    /// [`DecimalOverflow`] if the liquidity sums are out of range.
+    pub fn imbalance(&self) -> Result<Decimal, DecimalOverflow> {
+        let (bids, asks) = (self.total_bid_liquidity()?, self.total_ask_liquidity()?);
+        let total = checked_add(bids, asks)?;
+        if total.is_zero() {
+            return Ok(Decimal::ZERO);
+        }
+        Ok((bids - asks) / total)
+    }
+
+    /// Computes the CRC32 checksum of the top [`CHECKSUM_DEPTH`] levels of each side.
+    ///
+    /// The checksummed string interleaves the best levels from each side as
+    /// `bid_price:bid_qty:ask_price:ask_qty:...`, best first. When one side runs out of
+    /// levels the remaining levels of the other side are appended on their own. Prices and
+    /// quantities are formatted as received, so trailing zeros are significant.
+    pub fn checksum(&self) -> u32 {
+        let mut bids = self.bids.iter().rev().take(CHECKSUM_DEPTH);
+        let mut asks = self.asks.iter().take(CHECKSUM_DEPTH);
+        let mut parts = Vec::with_capacity(CHECKSUM_DEPTH * 4);
+        loop {
+            let (bid, ask) = (bids.next(), asks.next());
+            if bid.is_none() && ask.is_none() {
+                break;
+            }
+            for (price, quantity) in bid.into_iter().chain(ask) {
+                parts.push(price.to_string());
+                parts.push(quantity.to_string());
+            }
+        }
+        crc32(parts.join(":").as_bytes())
+    }
+
+    /// Returns `true` if the book matches the `expected` checksum.
+    ///
+    /// A mismatch means the local book has diverged from the exchange; it should be discarded
+    /// and rebuilt from a fresh snapshot.
+    pub fn verify_checksum(&self, expected: u32) -> bool {
+        self.checksum() == expected
+    }
+}
+
+impl From<OrderBookDepth> for OrderBook {
+    fn from(depth: OrderBookDepth) -> Self {
+        Self {
+            bids: depth.bids.into_iter().collect(),
+            asks: depth.asks.into_iter().collect(),
+            last_update_id: depth.last_update_id,
+        }
+    }
+}
+
+/// Sums the quantities of one side of the book.
+fn total_liquidity(levels: &BTreeMap<Decimal, Decimal>) -> Result<Decimal, DecimalOverflow> {
+    levels
+        .values()
+        .try_fold(Decimal::ZERO, |total, quantity| checked_add(total, *quantity))
+}
+
+/// CRC-32 (IEEE 802.3), as used by zlib.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn get_test_book() -> OrderBook {
+        OrderBook::from(OrderBookDepth {
+            asks: vec![
+                (dec!(100.5), dec!(2)),
+                (dec!(100.6), dec!(0.25)),
+                (dec!(101), dec!(7.1)),
+            ],
+            bids: vec![(dec!(100.4), dec!(1.5)), (dec!(100.3), dec!(3))],
+            last_update_id: 1,
+            timestamp: 0,
+        })
+    }
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_checksum_known_good() {
+        let book = get_test_book();
+        // crc32("100.4:1.5:100.5:2:100.3:3:100.6:0.25:101:7.1")
+        assert!(book.verify_checksum(0xCFB4_21DE));
+    }
+
+    #[test]
+    fn test_checksum_detects_divergence() {
+        let mut book = get_test_book();
+        let expected = book.checksum();
+        book.bids.insert(dec!(100.4), dec!(1.4));
+        assert!(!book.verify_checksum(expected));
+    }
+
+    #[test]
+    fn test_best_levels() {
+        let book = get_test_book();
+        assert_eq!(book.best_bid(), Some((dec!(100.4), dec!(1.5))));
+        assert_eq!(book.best_ask(), Some((dec!(100.5), dec!(2))));
+    }
+
+    #[test]
+    fn test_liquidity_metrics() {
+        let book = get_test_book();
+        assert_eq!((book.bid_levels(), book.ask_levels()), (2, 3));
+        assert_eq!(book.total_bid_liquidity(), Ok(dec!(4.5)));
+        assert_eq!(book.total_ask_liquidity(), Ok(dec!(9.35)));
+        // Asks outweigh bids: (4.5 - 9.35) / 13.85
+        let imbalance = book.imbalance().unwrap();
+        assert!(imbalance.is_sign_negative());
+        assert_eq!(imbalance.round_dp(4), dec!(-0.3502));
+
+        let mut bid_heavy = book.clone();
+        bid_heavy.bids.insert(dec!(100), dec!(20));
+        assert!(bid_heavy.imbalance().unwrap() > Decimal::ZERO);
+
+        let mut bids_only = book;
+        bids_only.asks.clear();
+        assert_eq!(bids_only.imbalance(), Ok(Decimal::ONE));
+        assert_eq!(OrderBook::default().imbalance(), Ok(Decimal::ZERO));
+    }
+
+    #[test]
+    fn test_liquidity_metrics_report_overflow() {
+        let mut book = OrderBook::default();
+        book.bids.insert(dec!(100), Decimal::MAX);
+        book.asks.insert(dec!(101), Decimal::ONE);
+        // Each side fits, but not their sum.
+        assert_eq!(book.total_bid_liquidity(), Ok(Decimal::MAX));
+        assert_eq!(book.imbalance(), Err(DecimalOverflow));
+
+        book.bids.insert(dec!(99), Decimal::ONE);
+        assert_eq!(book.total_bid_liquidity(), Err(DecimalOverflow));
+        assert_eq!(book.imbalance(), Err(DecimalOverflow));
+    }
+
+    fn update(first_update_id: u64, last_update_id: u64, bids: Vec<(Decimal, Decimal)>) -> OrderBookDepthUpdate {
+        OrderBookDepthUpdate {
+            event_type: "depth".to_string(),
+            event_time: 0,
+            symbol: "SOL_USDC".to_string(),
+            timestamp: 0,
+            first_update_id,
+            last_update_id,
+            asks: vec![],
+            bids,
+        }
+    }
+
+    #[test]
+    fn test_apply_update_sequence() {
+        let mut book = get_test_book();
+        book.apply_update(&update(2, 3, vec![(dec!(100.4), dec!(0)), (dec!(100.35), dec!(4))]))
+            .unwrap();
+        assert_eq!(book.best_bid(), Some((dec!(100.35), dec!(4))));
+        assert_eq!(book.last_update_id, 3);
+
+        // Already applied.
+        book.apply_update(&update(1, 3, vec![(dec!(99), dec!(1))])).unwrap();
+        assert_eq!(book.bids.len(), 2);
+
+        let gap = book.apply_update(&update(5, 6, vec![])).unwrap_err();
+        assert_eq!(
+            gap,
+            SequenceGap {
+                last_update_id: 3,
+                first_update_id: 5
+            }
+        );
+        assert_eq!(book.last_update_id, 3);
+    }
+
+    #[test]
+    fn test_zero_quantity_removes_best_levels() {
+        let mut book = get_test_book();
+        let mut removal = update(2, 2, vec![(dec!(100.4), dec!(0.000))]);
+        removal.asks = vec![(dec!(100.5), dec!(0)), (dec!(100.7), dec!(0))];
+        book.apply_update(&removal).unwrap();
+
+        assert_eq!(book.best_bid(), Some((dec!(100.3), dec!(3))));
+        assert_eq!(book.best_ask(), Some((dec!(100.6), dec!(0.25))));
+        assert!(!book.bids.contains_key(&dec!(100.4)));
+        assert!(!book.asks.contains_key(&dec!(100.5)));
+        // Removing a price that isn't in the book doesn't add it.
+        assert!(!book.asks.contains_key(&dec!(100.7)));
+        assert!(book.bids.values().chain(book.asks.values()).all(|q| !q.is_zero()));
+    }
+
+    #[test]
+    fn test_diverging_levels() {
+        let book = get_test_book();
+        let mut other = book.clone();
+        assert_eq!(book.diverging_levels(&other), (0, 0));
+        other.bids.insert(dec!(100.4), dec!(1));
+        other.asks.remove(&dec!(101));
+        other.asks.insert(dec!(102), dec!(1));
+        assert_eq!(book.diverging_levels(&other), (1, 2));
+    }
+}
@@ -39,6 +39,7 @@ pub struct QuotePayload {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct RequestForQuoteStream {
     pub stream: String,
     pub data: RequestForQuoteUpdate,
@@ -230,6 +231,7 @@ pub enum RequestForQuoteUpdate {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct Quote {
     pub rfq_id: String,
     pub quote_id: String,
@@ -239,6 +241,7 @@ pub struct Quote {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct RequestForQuote {
     pub rfq_id: String,
     pub client_id: Option<u32>,
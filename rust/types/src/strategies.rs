@@ -1,9 +1,10 @@
-use crate::markets::MarketType;
+use crate::history::{validate_limit, ParamError};
+use crate::markets::{Market, MarketType};
 use crate::order::{SelfTradePrevention, Side, SlippageToleranceType, TimeInForce};
 use crate::SortDirection;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use strum::{Display, EnumString};
+use strum::{Display, EnumIter, EnumString};
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -45,8 +46,71 @@ impl StrategyHistorySearchParams {
     }
 }
 
+impl StrategyHistorySearchParams {
+    /// Returns a builder for the search params.
+    pub fn builder() -> StrategyHistorySearchParamsBuilder {
+        StrategyHistorySearchParamsBuilder::default()
+    }
+
+    /// Checks that `limit` is within the API maximum.
+    pub fn validate(&self) -> Result<(), ParamError> {
+        validate_limit(self.limit)
+    }
+}
+
+/// Builder for [`StrategyHistorySearchParams`].
+#[derive(Debug, Default, Clone)]
+pub struct StrategyHistorySearchParamsBuilder {
+    params: StrategyHistorySearchParams,
+}
+
+impl StrategyHistorySearchParamsBuilder {
+    /// Filter to the given strategy.
+    pub fn strategy_id(mut self, strategy_id: impl Into<String>) -> Self {
+        self.params.strategy_id = Some(strategy_id.into());
+        self
+    }
+
+    /// Filter to the given symbol.
+    pub fn symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.params.symbol = Some(symbol.into());
+        self
+    }
+
+    /// Maximum number to return, at most [`crate::history::MAX_HISTORY_LIMIT`].
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.params.limit = Some(limit);
+        self
+    }
+
+    /// Offset.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.params.offset = Some(offset);
+        self
+    }
+
+    /// Market type.
+    pub fn market_type(mut self, market_type: MarketType) -> Self {
+        self.params.market_type = Some(market_type);
+        self
+    }
+
+    /// Sort direction.
+    pub fn sort_direction(mut self, sort_direction: SortDirection) -> Self {
+        self.params.sort_direction = Some(sort_direction);
+        self
+    }
+
+    /// Validates and returns the search params.
+    pub fn build(self) -> Result<StrategyHistorySearchParams, ParamError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct Strategy {
     pub id: i32,
     pub created_at: chrono::NaiveDateTime,
@@ -68,7 +132,74 @@ pub struct Strategy {
     pub slippage_tolerance_type: Option<SlippageToleranceType>,
 }
 
-#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, PartialEq, Eq, Hash)]
+/// Parameters of a scheduled strategy, which executes `quantity` in slices every `interval`
+/// over `duration`. Both durations are in milliseconds, as in [`Strategy`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateStrategyPayload {
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: Decimal,
+    pub duration: u64,
+    pub interval: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub randomized_interval_quantity: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slippage_tolerance: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slippage_tolerance_type: Option<SlippageToleranceType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<TimeInForce>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_trade_prevention: Option<SelfTradePrevention>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_strategy_id: Option<u32>,
+}
+
+impl CreateStrategyPayload {
+    /// Checks the quantity against the market's quantity filters and the scheduling
+    /// constraints, which the exchange would otherwise reject.
+    pub fn validate(&self, market: &Market) -> Result<(), StrategyValidationError> {
+        if self.interval == 0 {
+            return Err(StrategyValidationError::ZeroInterval);
+        }
+        if self.interval > self.duration {
+            return Err(StrategyValidationError::IntervalExceedsDuration {
+                interval: self.interval,
+                duration: self.duration,
+            });
+        }
+        let filters = &market.filters.quantity;
+        if self.quantity < filters.min_quantity {
+            return Err(StrategyValidationError::BelowMinQuantity {
+                quantity: self.quantity,
+                min: filters.min_quantity,
+            });
+        }
+        if !filters.step_size.is_zero() && !(self.quantity % filters.step_size).is_zero() {
+            return Err(StrategyValidationError::InvalidStep {
+                quantity: self.quantity,
+                step_size: filters.step_size,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Error returned by [`CreateStrategyPayload::validate`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StrategyValidationError {
+    #[error("`interval` must not be zero")]
+    ZeroInterval,
+    #[error("`interval` ({interval}) must not exceed `duration` ({duration})")]
+    IntervalExceedsDuration { interval: u64, duration: u64 },
+    #[error("quantity {quantity} is below the minimum of {min}")]
+    BelowMinQuantity { quantity: Decimal, min: Decimal },
+    #[error("quantity {quantity} is not a multiple of the step size {step_size}")]
+    InvalidStep { quantity: Decimal, step_size: Decimal },
+}
+
+#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, EnumIter, PartialEq, Eq, Hash)]
 #[strum(serialize_all = "PascalCase")]
 #[serde(rename_all = "PascalCase")]
 pub enum StrategyCancelReason {
@@ -88,6 +219,29 @@ pub enum StrategyCancelReason {
     UserPermissions,
 }
 
+impl StrategyCancelReason {
+    /// Returns a plain English explanation of why the strategy was cancelled, for display to
+    /// users.
+    pub const fn description(&self) -> &'static str {
+        match self {
+            Self::Expired => "The strategy's duration elapsed before it completed",
+            Self::FillOrKill => "A fill-or-kill slice could not be filled in full immediately",
+            Self::InsufficientBorrowableQuantity => "Not enough of the asset could be borrowed for a slice",
+            Self::InsufficientFunds => "The account had insufficient funds for a slice",
+            Self::InsufficientLiquidity => "There was not enough liquidity in the order book to fill a slice",
+            Self::InvalidPrice => "A slice's price was invalid for the market",
+            Self::InvalidQuantity => "A slice's quantity was invalid for the market",
+            Self::InsufficientMargin => "The account had insufficient margin for a slice",
+            Self::Liquidation => "The strategy was cancelled because the account was liquidated",
+            Self::PriceOutOfBounds => "A slice's price was outside the market's allowed price band",
+            Self::ReduceOnlyNotReduced => "A reduce-only slice would not have reduced the position",
+            Self::SelfTradePrevention => "A slice would have traded against another order of the account",
+            Self::Unknown => "The strategy was cancelled for an unknown reason",
+            Self::UserPermissions => "The account lacked the permissions required for the strategy",
+        }
+    }
+}
+
 #[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, PartialEq, Eq, Hash)]
 #[strum(serialize_all = "PascalCase")]
 #[serde(rename_all = "PascalCase")]
@@ -96,4 +250,116 @@ pub enum StrategyStatus {
     Completed,
     Cancelled,
     Terminated,
+    /// A status added to the API after this version of the crate.
+    #[serde(other)]
+    Unknown,
+}
+
+impl StrategyStatus {
+    /// Returns `true` if the strategy has stopped: it completed, was cancelled or terminated.
+    ///
+    /// [`StrategyStatus::Unknown`] is not terminal, so polling loops keep waiting on a status
+    /// they don't recognize.
+    pub const fn is_terminal(&self) -> bool {
+        matches!(self, Self::Completed | Self::Cancelled | Self::Terminated)
+    }
+
+    /// Returns `true` if the strategy may still change, that is if it is not terminal.
+    pub const fn is_active(&self) -> bool {
+        !self.is_terminal()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn get_test_market() -> Market {
+        serde_json::from_str(
+            r#"{
+                "symbol": "SOL_USDC",
+                "baseSymbol": "SOL",
+                "quoteSymbol": "USDC",
+                "marketType": "SPOT",
+                "filters": {
+                    "price": { "minPrice": "0.01", "tickSize": "0.01" },
+                    "quantity": { "minQuantity": "0.01", "stepSize": "0.01" }
+                },
+                "orderBookState": "Open",
+                "createdAt": "2025-01-01T00:00:00"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn get_test_payload() -> CreateStrategyPayload {
+        CreateStrategyPayload {
+            symbol: "SOL_USDC".to_string(),
+            side: Side::Bid,
+            quantity: dec!(10),
+            duration: 3_600_000,
+            interval: 60_000,
+            randomized_interval_quantity: None,
+            slippage_tolerance: None,
+            slippage_tolerance_type: None,
+            time_in_force: None,
+            self_trade_prevention: None,
+            client_strategy_id: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_strategy_payload() {
+        let market = get_test_market();
+        assert_eq!(get_test_payload().validate(&market), Ok(()));
+
+        let mut payload = get_test_payload();
+        payload.interval = 0;
+        assert_eq!(payload.validate(&market), Err(StrategyValidationError::ZeroInterval));
+
+        payload.interval = payload.duration + 1;
+        assert!(matches!(
+            payload.validate(&market),
+            Err(StrategyValidationError::IntervalExceedsDuration { .. })
+        ));
+
+        let mut payload = get_test_payload();
+        payload.quantity = dec!(0.005);
+        assert_eq!(
+            payload.validate(&market),
+            Err(StrategyValidationError::BelowMinQuantity {
+                quantity: dec!(0.005),
+                min: dec!(0.01)
+            })
+        );
+
+        payload.quantity = dec!(1.005);
+        assert!(matches!(
+            payload.validate(&market),
+            Err(StrategyValidationError::InvalidStep { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unknown_strategy_status_is_not_terminal() {
+        let status: StrategyStatus = serde_json::from_str(r#""Paused""#).unwrap();
+        assert_eq!(status, StrategyStatus::Unknown);
+        assert!(!status.is_terminal());
+        assert!(status.is_active());
+        assert!(StrategyStatus::Completed.is_terminal());
+    }
+
+    #[test]
+    fn test_strategy_cancel_reason_descriptions_are_distinct() {
+        use std::collections::HashSet;
+        use strum::IntoEnumIterator;
+
+        let mut seen = HashSet::new();
+        for reason in StrategyCancelReason::iter() {
+            let description = reason.description();
+            assert!(!description.is_empty(), "{reason} has no description");
+            assert!(seen.insert(description), "{reason} shares its description");
+        }
+    }
 }
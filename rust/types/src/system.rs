@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, EnumString, PartialEq, Eq, Hash)]
+#[strum(serialize_all = "PascalCase")]
+#[serde(rename_all = "PascalCase")]
+pub enum StatusType {
+    Ok,
+    Maintenance,
+}
+
+/// The exchange's system status.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
+pub struct Status {
+    pub status: StatusType,
+    /// Details about the status, such as the reason for maintenance.
+    pub message: Option<String>,
+}
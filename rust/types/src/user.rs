@@ -11,6 +11,7 @@ pub struct RequestTwoFactorPayload {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "strict-deserialize", serde(deny_unknown_fields))]
 pub struct RequestTwoFactorResponse {
     pub signature: String,
 }